@@ -0,0 +1,65 @@
+// Measures what lesson 17 says about dispatch: the same work through a generic, through
+// Box<dyn Describe> and through &dyn Describe. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use learn_borrow_checker::lesson_17_trait_objects::{
+    total_size, total_size_boxed, total_size_dyn, Describe,
+};
+use learn_borrow_checker::teaching_types::{MyCopyableStruct, Point, SimpleStruct};
+
+const ITEMS: usize = 1_000;
+const ROUNDS: u32 = 5_000;
+
+// Runs f ROUNDS times after a warm up and prints the average time per item.
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    for _ in 0..ROUNDS / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    let per_item = start.elapsed().as_nanos() as f64 / (ROUNDS as f64 * ITEMS as f64);
+    println!("{:<40} {:>8.3} ns per call", name, per_item);
+}
+
+fn main() {
+    let simple: Vec<SimpleStruct> = (0..ITEMS)
+        .map(|i| SimpleStruct {
+            data: "x".repeat(i % 10),
+        })
+        .collect();
+    let boxed: Vec<Box<dyn Describe>> = (0..ITEMS)
+        .map(|i| -> Box<dyn Describe> {
+            Box::new(SimpleStruct {
+                data: "x".repeat(i % 10),
+            })
+        })
+        .collect();
+    let borrowed: Vec<&dyn Describe> = simple.iter().map(|s| s as &dyn Describe).collect();
+    // Three types taking turns, so every call goes through a different vtable than the last
+    let zoo: Vec<Box<dyn Describe>> = (0..ITEMS)
+        .map(|i| -> Box<dyn Describe> {
+            match i % 3 {
+                0 => Box::new(Point { x: 1, y: 2 }),
+                1 => Box::new(MyCopyableStruct { data: 3 }),
+                _ => Box::new(SimpleStruct {
+                    data: "x".repeat(i % 10),
+                }),
+            }
+        })
+        .collect();
+
+    bench("generic &[SimpleStruct]", || total_size(black_box(&simple)));
+    bench("&[Box<dyn Describe>], all SimpleStruct", || {
+        total_size_boxed(black_box(&boxed))
+    });
+    bench("&[&dyn Describe], all SimpleStruct", || {
+        total_size_dyn(black_box(&borrowed))
+    });
+    bench("&[Box<dyn Describe>], mixed zoo", || {
+        total_size_boxed(black_box(&zoo))
+    });
+}
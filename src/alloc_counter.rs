@@ -0,0 +1,53 @@
+/////////////////////////////////////////////////////////
+// Allocation counter
+/////////////////////////////////////////////////////////
+
+// Wraps the system allocator so a lesson can count how many heap allocations a piece of
+// code makes. Counts are kept per thread, so tests running in parallel do not disturb
+// each other. Only new allocations are counted; growing an existing one is a realloc.
+//
+// The library does not install the allocator, so the benchmark and anything else linking it
+// keeps its own. The lessons binary installs it in main.rs, and the library's tests here.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    // A const initialized Cell with no destructor, so the allocator may touch it at any time.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs f and returns its result along with the number of heap allocations it made. Nothing
+/// is counted unless the program installed CountingAllocator as its global allocator.
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    let after = ALLOCATIONS.with(Cell::get);
+    (result, after - before)
+}
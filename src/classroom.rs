@@ -0,0 +1,785 @@
+/////////////////////////////////////////////////////////
+// Classroom server
+/////////////////////////////////////////////////////////
+
+// `serve` turns the presenter's laptop into the classroom: a small HTTP server on the local
+// network that hands out the lessons as web pages and the exercises as forms. Attendees
+// without a Rust toolchain submit their solutions from a browser, the laptop compiles them
+// (see exercises.rs and compile_service.rs) and the leaderboard keeps score. Everything is served from memory by
+// std's TcpListener, so nothing needs the internet.
+//
+// One thread per connection is plenty for a room full of people, up to CONNECTION_LIMIT of
+// them, after which new connections are turned away until one finishes. A request has
+// REQUEST_DEADLINE to arrive in full, so a client that trickles it in a byte at a time cannot
+// keep its connection for longer than that. Submissions take a
+// compile slot, at most COMPILE_LIMIT compilers run at once and a burst of submissions queues
+// up for them instead of swamping the presenter's laptop.
+//
+// The lessons' prose comes from the message catalogs (see i18n.rs), so `/?lang=es` shows
+// the index and lessons in Spanish, and the links keep the language.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::compile_service::{CompileService, Edition};
+use crate::exercises::{self, Exercise, Verdict, EXERCISES};
+use crate::i18n::{self, Messages};
+
+const HEADER_LIMIT: usize = 8 * 1024;
+const NAME_LIMIT: usize = 40;
+const CONNECTION_LIMIT: usize = 64;
+const REQUEST_DEADLINE: Duration = Duration::from_secs(10);
+// Each compiler may use up to the memory limit in exercises::limits.
+const COMPILE_LIMIT: usize = 2;
+
+pub struct Classroom {
+    /// Each lesson's id and source.
+    lessons: Vec<(&'static str, &'static str)>,
+    leaderboard: Mutex<Leaderboard>,
+    compiler: CompileService,
+    edition: Edition,
+    compiles: Arc<Slots>,
+    started: Instant,
+}
+
+impl Classroom {
+    pub fn new(lessons: Vec<(&'static str, &'static str)>, edition: Edition) -> Classroom {
+        Classroom {
+            lessons,
+            leaderboard: Mutex::new(Leaderboard::default()),
+            compiler: CompileService::new(exercises::limits()),
+            edition,
+            compiles: Slots::new(COMPILE_LIMIT),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// Serves the classroom until the process is stopped.
+pub fn serve(listener: TcpListener, classroom: Classroom) -> io::Result<()> {
+    let classroom = Arc::new(classroom);
+    let connections = Slots::new(CONNECTION_LIMIT);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // Such as running out of file descriptors, which passes once connections finish.
+            Err(error) => {
+                eprintln!("Unable to accept a connection: {}", error);
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+        let Some(slot) = connections.try_take() else {
+            let busy = Response::error(
+                "503 Service Unavailable",
+                "Too many people at once, try again.",
+            );
+            let _ = busy.write_to(&mut &stream);
+            continue;
+        };
+        let classroom = Arc::clone(&classroom);
+        std::thread::spawn(move || {
+            let _slot = slot;
+            // A browser that goes away mid-request is not worth reporting.
+            let _ = handle(stream, &classroom);
+        });
+    }
+    Ok(())
+}
+
+/// A counting semaphore: at most `limit` slots are taken at a time.
+struct Slots {
+    taken: Mutex<usize>,
+    freed: Condvar,
+    limit: usize,
+}
+
+/// A taken slot, given back when dropped.
+struct Slot(Arc<Slots>);
+
+impl Slots {
+    fn new(limit: usize) -> Arc<Slots> {
+        Arc::new(Slots {
+            taken: Mutex::new(0),
+            freed: Condvar::new(),
+            limit,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, usize> {
+        self.taken.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes a slot, or returns None when all of them are taken.
+    fn try_take(self: &Arc<Self>) -> Option<Slot> {
+        let mut taken = self.lock();
+        if *taken == self.limit {
+            return None;
+        }
+        *taken += 1;
+        Some(Slot(Arc::clone(self)))
+    }
+
+    /// Takes a slot, waiting for one to be given back if need be.
+    fn take(self: &Arc<Self>) -> Slot {
+        let mut taken = self.lock();
+        while *taken == self.limit {
+            taken = self.freed.wait(taken).unwrap_or_else(|e| e.into_inner());
+        }
+        *taken += 1;
+        Slot(Arc::clone(self))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.lock() -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
+/// The address attendees can reach this machine at, when it has one on a network. Nothing
+/// is sent, connecting a UDP socket only picks the interface a packet would leave from.
+pub fn lan_address() -> Option<std::net::IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+fn handle(stream: TcpStream, classroom: &Classroom) -> io::Result<()> {
+    stream.set_write_timeout(Some(REQUEST_DEADLINE))?;
+    let mut reader = BufReader::new(Deadline::new(stream.try_clone()?, REQUEST_DEADLINE));
+    let response = match read_request(&mut reader) {
+        Ok(request) => respond(classroom, &request),
+        Err(error) => Response::error("400 Bad Request", &error.to_string()),
+    };
+    response.write_to(&mut &stream)
+}
+
+/// Reads from a stream until a deadline, however slowly the bytes arrive.
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Deadline {
+    fn new(stream: TcpStream, within: Duration) -> Deadline {
+        Deadline {
+            stream,
+            until: Instant::now() + within,
+        }
+    }
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the request took too long to arrive",
+            ));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Request> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    // Only so much is read before the blank line that ends the headers.
+    let mut head = reader.take(HEADER_LIMIT as u64);
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    // The request line, such as `GET /lessons/lesson_1_scope HTTP/1.1`.
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(invalid("bad request line"));
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body: String::new(),
+    };
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 {
+            return Err(invalid("the request headers are too long"));
+        }
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("bad Content-Length"))?;
+            }
+        }
+    }
+    // Room for a submission at its limit, once the form has percent-encoded it.
+    if length > exercises::SUBMISSION_LIMIT * 3 + 1024 {
+        return Err(invalid("the submission is too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    request.body = String::from_utf8_lossy(&body).into_owned();
+    Ok(request)
+}
+
+struct Response {
+    status: &'static str,
+    /// A whole HTML page.
+    body: String,
+}
+
+impl Response {
+    fn page(title: &str, body: &str) -> Response {
+        Response {
+            status: "200 OK",
+            body: page(title, body, None),
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Response {
+        Response {
+            status,
+            body: page(status, &format!("<p>{}</p>", escape(message)), None),
+        }
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+fn respond(classroom: &Classroom, request: &Request) -> Response {
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let language = parse_form(query)
+        .into_iter()
+        .find(|(key, _)| key == "lang")
+        .map(|(_, value)| value)
+        .filter(|value| i18n::languages().contains(&value.as_str()));
+    let language = language.as_deref();
+    match (request.method.as_str(), &segments[..]) {
+        ("GET", []) => index(classroom, language),
+        ("GET", ["lessons", id]) => match classroom.lessons.iter().find(|(l, _)| l == id) {
+            Some((id, source)) => lesson(id, source, language),
+            None => Response::error("404 Not Found", "There is no such lesson"),
+        },
+        ("GET", ["exercises", id]) => match exercises::find(id) {
+            Some(exercise) => exercise_page(exercise, exercise.starter, "", None),
+            None => Response::error("404 Not Found", "There is no such exercise"),
+        },
+        ("POST", ["exercises", id]) => match exercises::find(id) {
+            Some(exercise) => submit(classroom, exercise, &request.body),
+            None => Response::error("404 Not Found", "There is no such exercise"),
+        },
+        ("GET", ["leaderboard"]) => Response {
+            status: "200 OK",
+            // Refreshed every few seconds, so it can stay up on the projector.
+            body: page("Leaderboard", &leaderboard(classroom), Some(5)),
+        },
+        ("GET", _) => Response::error("404 Not Found", "There is no such page"),
+        _ => Response::error("405 Method Not Allowed", "Only GET and POST are supported"),
+    }
+}
+
+// A query string keeping the language the page was asked for, if any.
+fn lang_query(language: Option<&str>) -> String {
+    language
+        .map(|language| format!("?lang={}", language))
+        .unwrap_or_default()
+}
+
+fn index(classroom: &Classroom, language: Option<&str>) -> Response {
+    let messages = Messages::new(language.unwrap_or(i18n::ENGLISH));
+    let mut body = String::from("<h2>Lessons</h2>\n<ul>\n");
+    for (id, _) in &classroom.lessons {
+        body.push_str(&format!(
+            "<li><a href=\"/lessons/{}{}\">{}</a></li>\n",
+            id,
+            lang_query(language),
+            escape(&messages.lesson_title(id).unwrap_or_else(|| id.to_string()))
+        ));
+    }
+    body.push_str("</ul>\n<h2>Exercises</h2>\n<ul>\n");
+    for exercise in EXERCISES {
+        body.push_str(&format!(
+            "<li><a href=\"/exercises/{}\">{}</a> (after <a href=\"/lessons/{}{}\">{}</a>)</li>\n",
+            exercise.id,
+            escape(exercise.title),
+            exercise.lesson,
+            lang_query(language),
+            exercise.lesson
+        ));
+    }
+    body.push_str("</ul>\n<p><a href=\"/leaderboard\">Leaderboard</a></p>\n<p>");
+    for language in i18n::languages() {
+        body.push_str(&format!(
+            "<a href=\"/?lang={}\">{}</a> ",
+            language, language
+        ));
+    }
+    body.push_str("</p>\n");
+    Response::page("Learn the borrow checker", &body)
+}
+
+// The lesson's introduction and vocabulary in the language asked for, then its source.
+fn lesson(id: &str, source: &str, language: Option<&str>) -> Response {
+    let messages = Messages::new(language.unwrap_or(i18n::ENGLISH));
+    let title = messages.lesson_title(id).unwrap_or_else(|| id.to_string());
+    let mut body = String::new();
+    if let Some(intro) = messages.intro(id) {
+        body.push_str(&format!("<p>{}</p>\n", escape(intro)));
+    }
+    let terms = messages.terms(id);
+    if !terms.is_empty() {
+        let heading = messages.get("runner.vocabulary").unwrap_or("Vocabulary");
+        body.push_str(&format!("<h2>{}</h2>\n<dl>\n", escape(heading)));
+        for (term, definition) in terms {
+            body.push_str(&format!(
+                "<dt>{}</dt><dd>{}</dd>\n",
+                escape(term),
+                escape(definition)
+            ));
+        }
+        body.push_str("</dl>\n");
+    }
+    body.push_str(&format!("<pre><code>{}</code></pre>\n", escape(source)));
+    body.push_str(&format!(
+        "<p><a href=\"/{}\">All lessons and exercises</a></p>\n",
+        lang_query(language)
+    ));
+    Response::page(&title, &body)
+}
+
+fn exercise_page(exercise: &Exercise, code: &str, name: &str, result: Option<&str>) -> Response {
+    let mut body = format!(
+        "<p>{}</p>\n<form method=\"post\" action=\"/exercises/{}\">\n\
+         <p><label>Your name <input name=\"name\" value=\"{}\" maxlength=\"{}\" required></label></p>\n\
+         <textarea name=\"code\" rows=\"20\" cols=\"90\" spellcheck=\"false\">{}</textarea>\n\
+         <p><button type=\"submit\">Submit</button></p>\n</form>\n",
+        escape(exercise.prompt),
+        exercise.id,
+        escape(name),
+        NAME_LIMIT,
+        escape(code)
+    );
+    if let Some(result) = result {
+        body.push_str(result);
+    }
+    body.push_str("<p><a href=\"/\">All lessons and exercises</a> | <a href=\"/leaderboard\">Leaderboard</a></p>\n");
+    Response::page(exercise.title, &body)
+}
+
+fn submit(classroom: &Classroom, exercise: &'static Exercise, body: &str) -> Response {
+    let form = parse_form(body);
+    let field = |name: &str| {
+        form.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .unwrap_or("")
+    };
+    let name: String = field("name").trim().chars().take(NAME_LIMIT).collect();
+    let code = field("code");
+    if name.is_empty() {
+        let message = "<p class=\"failed\">Please give your name for the leaderboard.</p>\n";
+        return exercise_page(exercise, code, &name, Some(message));
+    }
+    let verdict = {
+        let _turn = classroom.compiles.take();
+        exercises::grade(&classroom.compiler, classroom.edition, exercise, code)
+    };
+    let result = match verdict {
+        Ok(Verdict::Passed) => {
+            let at = classroom.started.elapsed();
+            let mut board = classroom
+                .leaderboard
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            board.record(&name, exercise.id, at);
+            String::from("<p class=\"passed\">It compiles. Well done!</p>\n")
+        }
+        Ok(Verdict::Failed(stderr)) => format!(
+            "<p class=\"failed\">Not yet, the compiler says:</p>\n<pre>{}</pre>\n",
+            escape(&stderr)
+        ),
+        Ok(Verdict::Refused(reason)) => format!("<p class=\"failed\">{}</p>\n", escape(&reason)),
+        Ok(Verdict::TimedOut) => {
+            String::from("<p class=\"failed\">The compiler ran out of time.</p>\n")
+        }
+        Err(error) => format!(
+            "<p class=\"failed\">Unable to compile on the host: {}</p>\n",
+            escape(&error.to_string())
+        ),
+    };
+    exercise_page(exercise, code, &name, Some(&result))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Attendee {
+    name: String,
+    solved: Vec<&'static str>,
+    /// When the latest exercise was solved, since the server started.
+    last: Duration,
+}
+
+#[derive(Default)]
+struct Leaderboard {
+    attendees: Vec<Attendee>,
+}
+
+impl Leaderboard {
+    fn record(&mut self, name: &str, exercise: &'static str, at: Duration) {
+        let index = match self.attendees.iter().position(|a| a.name == name) {
+            Some(index) => index,
+            None => {
+                self.attendees.push(Attendee {
+                    name: name.to_string(),
+                    solved: Vec::new(),
+                    last: at,
+                });
+                self.attendees.len() - 1
+            }
+        };
+        let attendee = &mut self.attendees[index];
+        if !attendee.solved.contains(&exercise) {
+            attendee.solved.push(exercise);
+            attendee.last = at;
+        }
+    }
+
+    /// Most exercises solved first, and of those, who got there first.
+    fn ranking(&self) -> Vec<&Attendee> {
+        let mut ranking: Vec<&Attendee> = self.attendees.iter().collect();
+        ranking.sort_by(|a, b| {
+            b.solved
+                .len()
+                .cmp(&a.solved.len())
+                .then(a.last.cmp(&b.last))
+        });
+        ranking
+    }
+}
+
+fn leaderboard(classroom: &Classroom) -> String {
+    let board = classroom
+        .leaderboard
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let ranking = board.ranking();
+    if ranking.is_empty() {
+        return String::from("<p>Nobody has solved an exercise yet.</p>\n");
+    }
+    let mut body = String::from(
+        "<table>\n<tr><th>#</th><th>Name</th><th>Solved</th><th>Last solved</th></tr>\n",
+    );
+    for (place, attendee) in ranking.iter().enumerate() {
+        let seconds = attendee.last.as_secs();
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}/{}</td><td>{}:{:02}</td></tr>\n",
+            place + 1,
+            escape(&attendee.name),
+            attendee.solved.len(),
+            EXERCISES.len(),
+            seconds / 60,
+            seconds % 60
+        ));
+    }
+    body.push_str("</table>\n");
+    body
+}
+
+fn page(title: &str, body: &str, refresh: Option<u32>) -> String {
+    let refresh = refresh
+        .map(|seconds| format!("<meta http-equiv=\"refresh\" content=\"{}\">\n", seconds))
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n{}<title>{}</title>\n\
+         <style>body {{ font-family: sans-serif; max-width: 60em; margin: auto; }} \
+         pre {{ background: #f4f4f4; padding: 1em; overflow-x: auto; }} \
+         .passed {{ color: #060; }} .failed {{ color: #a00; }} \
+         td, th {{ padding: 0.2em 1em; text-align: left; }}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n{}</body>\n</html>\n",
+        refresh,
+        escape(title),
+        escape(title),
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// An application/x-www-form-urlencoded body, as browsers post forms.
+fn parse_form(body: &str) -> Vec<(String, String)> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn classroom() -> Classroom {
+        Classroom::new(
+            vec![("lesson_1_scope", include_str!("lesson_1_scope.rs"))],
+            Edition::E2021,
+        )
+    }
+
+    fn get(classroom: &Classroom, path: &str) -> Response {
+        let request = Request {
+            method: String::from("GET"),
+            path: path.to_string(),
+            body: String::new(),
+        };
+        respond(classroom, &request)
+    }
+
+    fn post(classroom: &Classroom, path: &str, form: &[(&str, &str)]) -> Response {
+        let encode = |text: &str| {
+            text.bytes()
+                .map(|b| match b {
+                    b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (b as char).to_string(),
+                    b' ' => String::from("+"),
+                    b => format!("%{:02X}", b),
+                })
+                .collect::<String>()
+        };
+        let body = form
+            .iter()
+            .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let request = Request {
+            method: String::from("POST"),
+            path: path.to_string(),
+            body,
+        };
+        respond(classroom, &request)
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw =
+            "POST /exercises/x HTTP/1.1\r\nHost: laptop\r\ncontent-length: 9\r\n\r\nname=Ada&extra";
+        let request = read_request(&mut Cursor::new(raw)).expect("Unable to read request");
+        assert_eq!(
+            Request {
+                method: String::from("POST"),
+                path: String::from("/exercises/x"),
+                body: String::from("name=Ada&"),
+            },
+            request
+        );
+        let endless = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(HEADER_LIMIT));
+        assert!(read_request(&mut Cursor::new(endless)).is_err());
+        let huge = "POST / HTTP/1.1\r\nContent-Length: 999999999\r\n\r\n";
+        assert!(read_request(&mut Cursor::new(huge)).is_err());
+    }
+
+    #[test]
+    fn test_parse_form() {
+        assert_eq!(
+            vec![
+                (String::from("name"), String::from("Ferris the crab")),
+                (String::from("code"), String::from("fn f() {}\n%")),
+            ],
+            parse_form("name=Ferris+the%20crab&code=fn+f%28%29+%7B%7D%0A%")
+        );
+        assert_eq!(
+            vec![(String::from("é"), String::new())],
+            parse_form("%C3%A9")
+        );
+    }
+
+    #[test]
+    fn test_pages_escape_what_they_show() {
+        let classroom = classroom();
+        let index = get(&classroom, "/");
+        assert_eq!("200 OK", index.status);
+        assert!(index.body.contains(
+            "<a href=\"/lessons/lesson_1_scope\">Lesson 1: Ownership and Dropping - It&#39;s All About Scope</a>"
+        ));
+        let lesson = get(&classroom, "/lessons/lesson_1_scope");
+        assert!(lesson
+            .body
+            .contains("<p>Rust fundamentally emphasizes scope management"));
+        assert!(lesson.body.contains("<dt>Lifetime</dt><dd>A scope of time"));
+        assert!(lesson.body.contains("let both_ends: VecDeque&lt;i32&gt;"));
+        let spanish = get(&classroom, "/?lang=es");
+        assert!(spanish
+            .body
+            .contains("<a href=\"/lessons/lesson_1_scope?lang=es\">Lección 1: "));
+        let unknown = get(&classroom, "/lessons/lesson_1_scope?lang=%3Cxx%3E");
+        assert_eq!(lesson.body, unknown.body);
+        assert_eq!(
+            "404 Not Found",
+            get(&classroom, "/lessons/lesson_99").status
+        );
+        assert_eq!("404 Not Found", get(&classroom, "/exercises/none").status);
+    }
+
+    #[test]
+    fn test_submissions_make_the_leaderboard() {
+        let classroom = classroom();
+        let path = "/exercises/longest_lifetime";
+        let starter = exercises::find("longest_lifetime")
+            .expect("No such exercise")
+            .starter;
+        let failed = post(&classroom, path, &[("name", "Ada"), ("code", starter)]);
+        assert!(failed.body.contains("error[E0106]"), "{}", failed.body);
+        let fixed = starter.replace(
+            "(a: &str, b: &str) -> &str",
+            "<'a>(a: &'a str, b: &'a str) -> &'a str",
+        );
+        let passed = post(
+            &classroom,
+            path,
+            &[("name", "<b>Ada</b>"), ("code", &fixed)],
+        );
+        assert!(passed.body.contains("It compiles"), "{}", passed.body);
+        let board = get(&classroom, "/leaderboard");
+        assert!(
+            board
+                .body
+                .contains("<td>&lt;b&gt;Ada&lt;/b&gt;</td><td>1/3</td>"),
+            "{}",
+            board.body
+        );
+        let nameless = post(&classroom, path, &[("name", " "), ("code", &fixed)]);
+        assert!(nameless.body.contains("Please give your name"));
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut board = Leaderboard::default();
+        board.record("late", "a", Duration::from_secs(30));
+        board.record("early", "a", Duration::from_secs(10));
+        board.record("most", "a", Duration::from_secs(40));
+        board.record("most", "b", Duration::from_secs(50));
+        // Solving the same exercise again changes nothing.
+        board.record("early", "a", Duration::from_secs(60));
+        let names: Vec<&str> = board.ranking().iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(vec!["most", "early", "late"], names);
+    }
+
+    #[test]
+    fn test_slots_are_limited() {
+        let slots = Slots::new(2);
+        let first = slots.try_take().expect("A slot should be free");
+        let _second = slots.try_take().expect("A slot should be free");
+        assert!(slots.try_take().is_none());
+        let waiting = {
+            let slots = Arc::clone(&slots);
+            std::thread::spawn(move || drop(slots.take()))
+        };
+        drop(first);
+        waiting.join().expect("The waiting thread panicked");
+        assert!(slots.try_take().is_some());
+    }
+
+    #[test]
+    fn test_slow_requests_hit_the_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to listen");
+        let mut client = TcpStream::connect(listener.local_addr().expect("No address"))
+            .expect("Unable to connect");
+        let (server, _) = listener.accept().expect("Unable to accept");
+        let trickle = std::thread::spawn(move || {
+            for _ in 0..40 {
+                if client.write_all(b"G").is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let started = Instant::now();
+        let mut reader = BufReader::new(Deadline::new(server, Duration::from_millis(300)));
+        let error = read_request(&mut reader).expect_err("The request never ends");
+        assert_eq!(io::ErrorKind::TimedOut, error.kind());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(reader);
+        trickle.join().expect("The trickling thread panicked");
+    }
+
+    #[test]
+    fn test_serves_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to listen");
+        let address = listener.local_addr().expect("No address");
+        std::thread::spawn(move || serve(listener, classroom()));
+        let mut stream = TcpStream::connect(address).expect("Unable to connect");
+        stream
+            .write_all(b"GET /leaderboard HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .expect("Unable to send");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Unable to read");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("Nobody has solved an exercise yet."));
+    }
+}
@@ -0,0 +1,163 @@
+/////////////////////////////////////////////////////////
+// Compile-fail harness
+/////////////////////////////////////////////////////////
+
+// The lessons show counter-examples as commented-out lines. Each lesson's tests also keep
+// a copy of those counter-examples as stand-alone snippets, and this harness hands them to
+// the compile service to confirm rustc still rejects them with the error code the lesson
+// claims. When one does not, the diagnostics are shown against the lines of the lesson the
+// snippet was copied from, which is the file that needs fixing. Under `matrix` the harness
+// checks every edition instead of asserting, and writes down what it finds.
+
+use std::fs;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::compile_service::{CompileService, Edition, Limits, Outcome, Snippet};
+use crate::diagnostics::LineMap;
+use crate::matrix;
+
+/// A snippet the compiler must reject, and the error code it is expected to produce.
+pub(crate) struct CompileFail {
+    pub id: &'static str,
+    pub code: &'static str,
+    pub source: &'static str,
+}
+
+/// Compiles every snippet as its own library crate and panics if any of them builds,
+/// or fails without reporting the expected error code.
+#[track_caller]
+pub(crate) fn assert_all_rejected(snippets: &[CompileFail]) {
+    assert_all_rejected_in("2021", snippets);
+}
+
+/// Same as assert_all_rejected, for claims about an older (or newer) edition.
+#[track_caller]
+pub(crate) fn assert_all_rejected_in(edition: &str, snippets: &[CompileFail]) {
+    if matrix::recording() {
+        for snippet in snippets {
+            for edition in Edition::ALL {
+                let outcome = compile_in(edition, snippet.source);
+                matrix::record(
+                    snippet.id,
+                    snippet.code,
+                    edition,
+                    outcome.has_error(snippet.code),
+                );
+            }
+        }
+        return;
+    }
+    for snippet in snippets {
+        let outcome = compile(edition, snippet.source);
+        assert!(
+            !outcome.success(),
+            "snippet `{}` compiled but should have failed",
+            snippet.id
+        );
+        assert!(
+            outcome.has_error(snippet.code),
+            "snippet `{}` should fail with {} but rustc said:\n{}",
+            snippet.id,
+            snippet.code,
+            in_lesson(&outcome, snippet.source)
+        );
+    }
+}
+
+/// Puts the teaching types, and the drop tracer they record to, in front of a snippet as
+/// modules of its crate, so the snippet checks the real definitions rather than copies of
+/// them. Lessons named before the snippet are included from src/ the same way.
+macro_rules! with_teaching_types {
+    ($($module:ident,)* $source:literal) => {
+        concat!(
+            "mod drop_tracer {\n",
+            include_str!("drop_tracer.rs"),
+            "}\nmod teaching_types {\n",
+            include_str!("teaching_types.rs"),
+            "}\n",
+            $(
+                "mod ",
+                stringify!($module),
+                " {\n",
+                include_str!(concat!(stringify!($module), ".rs")),
+                "}\n",
+            )*
+            "use teaching_types::*;\n",
+            $source
+        )
+    };
+}
+pub(crate) use with_teaching_types;
+
+/// A snippet the compiler must accept, for lessons that claim something compiles.
+pub(crate) struct CompilePass {
+    pub id: &'static str,
+    pub source: &'static str,
+}
+
+/// Compiles every snippet as its own library crate and panics if any of them is rejected.
+#[track_caller]
+pub(crate) fn assert_all_accepted_in(edition: &str, snippets: &[CompilePass]) {
+    if matrix::recording() {
+        for snippet in snippets {
+            for edition in Edition::ALL {
+                let outcome = compile_in(edition, snippet.source);
+                matrix::record(snippet.id, "compiles", edition, outcome.success());
+            }
+        }
+        return;
+    }
+    for snippet in snippets {
+        let outcome = compile(edition, snippet.source);
+        assert!(
+            outcome.success(),
+            "snippet `{}` should compile in {} but rustc said:\n{}",
+            snippet.id,
+            edition,
+            in_lesson(&outcome, snippet.source)
+        );
+    }
+}
+
+// One service for all the lessons' tests, so a snippet shared between them compiles once.
+fn compile(edition: &str, source: &str) -> Arc<Outcome> {
+    compile_in(edition.parse().expect("Unknown edition"), source)
+}
+
+fn compile_in(edition: Edition, source: &str) -> Arc<Outcome> {
+    static SERVICE: OnceLock<CompileService> = OnceLock::new();
+    SERVICE
+        .get_or_init(|| CompileService::new(Limits::default()))
+        .compile(&Snippet::new(source).edition(edition))
+        .expect("Unable to run rustc")
+}
+
+// The diagnostics against the lesson whose test called in, or as rustc printed them when that
+// lesson cannot be read. The snippets themselves are in the lesson's tests, which are left
+// out, or every snippet would be found there.
+#[track_caller]
+fn in_lesson(outcome: &Outcome, source: &str) -> String {
+    let file = Location::caller().file();
+    let Ok(lesson) = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(file)) else {
+        return outcome.rendered();
+    };
+    let lesson = lesson.split("#[cfg(test)]").next().unwrap_or_default();
+    let map = LineMap::new(source, lesson);
+    outcome
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.located(&map, file))
+        .collect()
+}
+
+pub(crate) fn scratch_dir(id: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "learn-borrow-checker-{}-{}",
+        std::process::id(),
+        id
+    ));
+    fs::create_dir_all(&dir).expect("Unable to create scratch dir");
+    dir
+}
@@ -0,0 +1,537 @@
+/////////////////////////////////////////////////////////
+// Compile service
+/////////////////////////////////////////////////////////
+
+// Everything that hands code to the compiler goes through here: the compile-fail harness
+// the lessons' tests use, and the grader behind `serve`, whose snippets come from strangers.
+// Each snippet is written into a temp crate of its own, compiled by the local rustc or cargo
+// under a time limit, a memory limit and a cap on how much it may print, and the temp crate
+// is removed afterwards. The compiler is asked for JSON diagnostics, which the diagnostics
+// module reads into types so nobody has to pick through the text it prints for people.
+//
+// Compiling the same snippet twice gives the same answer, so outcomes are cached by the
+// snippet itself. A room full of people submitting the untouched starter code costs one
+// compile. The cache keeps the CACHE_LIMIT snippets used most recently, so people posting
+// snippet after snippet cannot grow it without end.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::diagnostics::{Diagnostic, Level};
+
+const CACHE_LIMIT: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Edition {
+    E2015,
+    E2018,
+    E2021,
+    E2024,
+}
+
+impl Edition {
+    pub const ALL: [Edition; 4] = [
+        Edition::E2015,
+        Edition::E2018,
+        Edition::E2021,
+        Edition::E2024,
+    ];
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let year = match self {
+            Edition::E2015 => "2015",
+            Edition::E2018 => "2018",
+            Edition::E2021 => "2021",
+            Edition::E2024 => "2024",
+        };
+        write!(f, "{}", year)
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    fn from_str(year: &str) -> Result<Edition, String> {
+        Edition::ALL
+            .into_iter()
+            .find(|edition| edition.to_string() == year)
+            .ok_or_else(|| format!("unknown edition {}", year))
+    }
+}
+
+/// What compiles the snippet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Driver {
+    /// `rustc --crate-type=lib --emit=metadata`, checking without generating code.
+    Rustc,
+    /// `cargo check` in a package of its own, for snippets that need a manifest.
+    #[cfg_attr(not(test), allow(dead_code))]
+    Cargo,
+}
+
+/// A piece of code to compile as a library crate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Snippet {
+    source: String,
+    edition: Edition,
+    driver: Driver,
+}
+
+impl Snippet {
+    pub fn new(source: &str) -> Snippet {
+        Snippet {
+            source: source.to_string(),
+            edition: Edition::E2021,
+            driver: Driver::Rustc,
+        }
+    }
+
+    pub fn edition(mut self, edition: Edition) -> Snippet {
+        self.edition = edition;
+        self
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn driver(mut self, driver: Driver) -> Snippet {
+        self.driver = driver;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub time: Duration,
+    /// Address space, in KiB as `ulimit -v` takes it. Only enforced on Unix.
+    pub memory_kib: u64,
+    /// Bytes kept of each of stdout and stderr, the rest is read and thrown away.
+    pub output: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            time: Duration::from_secs(30),
+            // Plenty for checking a small crate.
+            memory_kib: 1024 * 1024,
+            output: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Compiled {
+        success: bool,
+        diagnostics: Vec<Diagnostic>,
+        /// The compiler printed more than the limit, so some diagnostics are missing.
+        truncated: bool,
+    },
+    TimedOut,
+}
+
+impl Outcome {
+    pub fn success(&self) -> bool {
+        matches!(self, Outcome::Compiled { success: true, .. })
+    }
+
+    // The compile-fail harness is test only, and the grader only needs rendered().
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Outcome::Compiled { diagnostics, .. } => diagnostics,
+            Outcome::TimedOut => &[],
+        }
+    }
+
+    /// True when the compiler reported an error with this code, such as "E0502".
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn has_error(&self, code: &str) -> bool {
+        self.diagnostics()
+            .iter()
+            .any(|d| d.level == Level::Error && d.code.as_deref() == Some(code))
+    }
+
+    /// Everything the compiler said, as it would have printed it.
+    pub fn rendered(&self) -> String {
+        match self {
+            Outcome::Compiled { diagnostics, .. } => {
+                diagnostics.iter().map(|d| d.rendered.as_str()).collect()
+            }
+            Outcome::TimedOut => String::from("the compiler ran out of time\n"),
+        }
+    }
+}
+
+pub struct CompileService {
+    limits: Limits,
+    // The limits are the same for every snippet the service compiles, so the snippet is all
+    // a cached outcome depends on.
+    cache: Mutex<Cache>,
+}
+
+impl CompileService {
+    pub fn new(limits: Limits) -> CompileService {
+        CompileService {
+            limits,
+            cache: Mutex::new(Cache::new(CACHE_LIMIT)),
+        }
+    }
+
+    /// Compiles a snippet, or answers from the cache when it has been compiled before.
+    pub fn compile(&self, snippet: &Snippet) -> io::Result<Arc<Outcome>> {
+        if let Some(outcome) = self.lock_cache().get(snippet) {
+            return Ok(outcome);
+        }
+        let outcome = Arc::new(self.compile_uncached(snippet)?);
+        // A timeout may be the machine being busy, so it is worth trying again later.
+        if *outcome != Outcome::TimedOut {
+            self.lock_cache()
+                .insert(snippet.clone(), Arc::clone(&outcome));
+        }
+        Ok(outcome)
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, Cache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn compile_uncached(&self, snippet: &Snippet) -> io::Result<Outcome> {
+        let dir = TempCrate::new()?;
+        let mut command = match snippet.driver {
+            Driver::Rustc => {
+                fs::write(dir.path().join("lib.rs"), &snippet.source)?;
+                let rustc = std::env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+                let mut command = self.limited(&rustc);
+                command.arg(format!("--edition={}", snippet.edition)).args([
+                    "--crate-type=lib",
+                    "--emit=metadata",
+                    "--error-format=json",
+                    "--out-dir=.",
+                    // Relative, so the diagnostics say lib.rs and not the temp dir.
+                    "lib.rs",
+                ]);
+                command
+            }
+            Driver::Cargo => {
+                fs::create_dir_all(dir.path().join("src"))?;
+                fs::write(
+                    dir.path().join("Cargo.toml"),
+                    format!(
+                        "[package]\nname = \"snippet\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n[workspace]\n",
+                        snippet.edition
+                    ),
+                )?;
+                fs::write(dir.path().join("src/lib.rs"), &snippet.source)?;
+                let cargo = std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
+                let mut command = self.limited(&cargo);
+                command
+                    .args(["check", "--offline", "--quiet", "--message-format=json"])
+                    .env("CARGO_TARGET_DIR", dir.path().join("target"));
+                command
+            }
+        };
+        command.current_dir(dir.path());
+        let Some(output) = run(command, &self.limits)? else {
+            return Ok(Outcome::TimedOut);
+        };
+        // rustc writes its diagnostics to stderr, cargo to stdout.
+        let messages = match snippet.driver {
+            Driver::Rustc => &output.stderr,
+            Driver::Cargo => &output.stdout,
+        };
+        Ok(Outcome::Compiled {
+            success: output.status.success(),
+            diagnostics: messages.lines().filter_map(Diagnostic::from_json).collect(),
+            truncated: output.truncated,
+        })
+    }
+
+    // A command for the program that runs it under the memory limit where the shell can set one.
+    fn limited(&self, program: &str) -> Command {
+        if cfg!(unix) {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!(
+                    "ulimit -v {} && exec \"$0\" \"$@\"",
+                    self.limits.memory_kib
+                ))
+                .arg(program);
+            command
+        } else {
+            Command::new(program)
+        }
+    }
+}
+
+// Outcomes of the snippets used most recently, forgetting the least recently used one when
+// it is full.
+struct Cache {
+    outcomes: HashMap<Snippet, (Arc<Outcome>, u64)>,
+    limit: usize,
+    // Counts lookups and inserts, so a larger stamp means used more recently.
+    clock: u64,
+}
+
+impl Cache {
+    fn new(limit: usize) -> Cache {
+        Cache {
+            outcomes: HashMap::new(),
+            limit,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, snippet: &Snippet) -> Option<Arc<Outcome>> {
+        self.clock += 1;
+        let (outcome, used) = self.outcomes.get_mut(snippet)?;
+        *used = self.clock;
+        Some(Arc::clone(outcome))
+    }
+
+    fn insert(&mut self, snippet: Snippet, outcome: Arc<Outcome>) {
+        self.clock += 1;
+        if self.outcomes.len() >= self.limit && !self.outcomes.contains_key(&snippet) {
+            let oldest = self
+                .outcomes
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(snippet, _)| snippet.clone());
+            if let Some(oldest) = oldest {
+                self.outcomes.remove(&oldest);
+            }
+        }
+        self.outcomes.insert(snippet, (outcome, self.clock));
+    }
+}
+
+// A temp dir of its own for each compile, removed when dropped.
+struct TempCrate(PathBuf);
+
+impl TempCrate {
+    fn new() -> io::Result<TempCrate> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "learn-borrow-checker-snippet-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        Ok(TempCrate(dir))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempCrate {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+struct Output {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
+    truncated: bool,
+}
+
+// Reads a pipe on its own thread, keeping the first `limit` bytes and draining the rest so
+// the child never blocks on a full pipe.
+fn read_limited(mut pipe: impl Read + Send + 'static, limit: usize) -> JoinHandle<(String, bool)> {
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let _ = (&mut pipe).take(limit as u64).read_to_end(&mut kept);
+        let dropped = io::copy(&mut pipe, &mut io::sink()).unwrap_or(0);
+        (String::from_utf8_lossy(&kept).into_owned(), dropped > 0)
+    })
+}
+
+// Runs a command to completion, or None when it runs out of time. On Unix the command gets a
+// process group of its own, and running out of time kills the whole group: cargo's rustc
+// children would otherwise keep the pipes open, and us waiting, after cargo was killed.
+fn run(mut command: Command, limits: &Limits) -> io::Result<Option<Output>> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_limited(child.stdout.take().expect("stdout is piped"), limits.output);
+    let stderr = read_limited(child.stderr.take().expect("stderr is piped"), limits.output);
+    let deadline = Instant::now() + limits.time;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            kill_group(&mut child)?;
+            child.wait()?;
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let (stdout, stdout_truncated) = stdout.join().unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.join().unwrap_or_default();
+    Ok(status.map(|status| Output {
+        status,
+        stdout,
+        stderr,
+        truncated: stdout_truncated || stderr_truncated,
+    }))
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut Child) -> io::Result<()> {
+    // The group's id is the id of the child that leads it.
+    let killed = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .status();
+    match killed {
+        Ok(status) if status.success() => Ok(()),
+        _ => child.kill(),
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVED: &str = "pub fn f() { let s = String::new(); drop(s); drop(s); }";
+
+    #[test]
+    fn test_errors_are_typed() {
+        let service = CompileService::new(Limits::default());
+        let outcome = service
+            .compile(&Snippet::new(MOVED))
+            .expect("Unable to compile");
+        assert!(!outcome.success());
+        assert!(outcome.has_error("E0382"), "{}", outcome.rendered());
+        let error = &outcome.diagnostics()[0];
+        assert_eq!(Level::Error, error.level);
+        assert_eq!("use of moved value: `s`", error.message);
+        assert!(
+            error.rendered.contains("--> lib.rs:1:"),
+            "{}",
+            error.rendered
+        );
+    }
+
+    #[test]
+    fn test_outcomes_are_cached() {
+        let service = CompileService::new(Limits::default());
+        let snippet = Snippet::new("pub fn f() {}");
+        let first = service.compile(&snippet).expect("Unable to compile");
+        let second = service.compile(&snippet).expect("Unable to compile");
+        assert!(first.success());
+        assert!(Arc::ptr_eq(&first, &second));
+        let other = service
+            .compile(&snippet.clone().edition(Edition::E2018))
+            .expect("Unable to compile");
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+
+    #[test]
+    fn test_cache_forgets_the_least_recently_used() {
+        let mut cache = Cache::new(2);
+        let outcome = Arc::new(Outcome::TimedOut);
+        let (a, b, c) = (Snippet::new("a"), Snippet::new("b"), Snippet::new("c"));
+        cache.insert(a.clone(), Arc::clone(&outcome));
+        cache.insert(b.clone(), Arc::clone(&outcome));
+        assert!(cache.get(&a).is_some());
+        cache.insert(c.clone(), outcome);
+        assert!(cache.get(&b).is_none());
+        assert!(cache.get(&a).is_some());
+        assert!(cache.get(&c).is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_timeout_kills_grandchildren() {
+        // The sleep in the background holds stdout open after sh itself is gone.
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 10 & sleep 10"]);
+        let limits = Limits {
+            time: Duration::from_millis(200),
+            ..Limits::default()
+        };
+        let started = Instant::now();
+        let output = run(command, &limits).expect("Unable to run sh");
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_edition_is_passed_on() {
+        // `async` is only a keyword since 2018.
+        let snippet = Snippet::new("pub fn async() {}");
+        let service = CompileService::new(Limits::default());
+        for driver in [Driver::Rustc, Driver::Cargo] {
+            let snippet = snippet.clone().driver(driver);
+            let old = service
+                .compile(&snippet.clone().edition(Edition::E2015))
+                .expect("Unable to compile");
+            assert!(old.success(), "{:?}: {}", driver, old.rendered());
+            let new = service
+                .compile(&snippet.edition(Edition::E2021))
+                .expect("Unable to compile");
+            assert!(!new.success(), "{:?}", driver);
+            assert!(!new.diagnostics().is_empty(), "{:?}", driver);
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let source = "pub fn f() { let s = String::new(); drop(s); drop(s); drop(s); }";
+        let service = CompileService::new(Limits {
+            output: 100,
+            ..Limits::default()
+        });
+        let outcome = service
+            .compile(&Snippet::new(source))
+            .expect("Unable to compile");
+        assert!(matches!(
+            *outcome,
+            Outcome::Compiled {
+                success: false,
+                truncated: true,
+                ..
+            }
+        ));
+        let service = CompileService::new(Limits {
+            time: Duration::ZERO,
+            ..Limits::default()
+        });
+        let outcome = service
+            .compile(&Snippet::new(source))
+            .expect("Unable to compile");
+        assert_eq!(Outcome::TimedOut, *outcome);
+    }
+
+    #[test]
+    fn test_editions_parse() {
+        for edition in Edition::ALL {
+            assert_eq!(Ok(edition), edition.to_string().parse());
+        }
+        assert!("2019".parse::<Edition>().is_err());
+    }
+}
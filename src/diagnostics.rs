@@ -0,0 +1,393 @@
+/////////////////////////////////////////////////////////
+// Diagnostics
+/////////////////////////////////////////////////////////
+
+// What rustc says about a snippet, read from its `--error-format=json` output into types: the
+// error code, the level, the message, the spans it points at with their labels ("first
+// mutable borrow occurs here"), and the fixes it suggests. The compile service hands these
+// out, so nothing that checks a snippet has to pick through the text rustc prints.
+//
+// The snippets the lessons' tests compile are copies of counter-examples that sit in the
+// lessons as commented-out lines. A LineMap finds each snippet line in the lesson again, so
+// an error can be shown against the line the learner reads, not line 4 of a temp file.
+
+// The grader only shows rustc's own rendering, the rest is for the compile-fail harness,
+// which is test only.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::fmt::Write as _;
+
+use crate::json::Json;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+    /// Such as "failure-note" or "error: internal compiler error".
+    Other(String),
+}
+
+impl Level {
+    fn parse(level: &str) -> Level {
+        match level {
+            "error" => Level::Error,
+            "warning" => Level::Warning,
+            "note" => Level::Note,
+            "help" => Level::Help,
+            other => Level::Other(other.to_string()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+            Level::Help => "help",
+            Level::Other(other) => other,
+        }
+    }
+}
+
+/// A stretch of the snippet a diagnostic points at. Lines and columns count from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    /// The span the diagnostic is about, rather than one that explains it.
+    pub primary: bool,
+    /// Such as "first mutable borrow occurs here".
+    pub label: Option<String>,
+    /// The text a suggestion would put in place of the span.
+    pub replacement: Option<String>,
+    /// How sure rustc is of that replacement, such as "MachineApplicable".
+    pub applicability: Option<String>,
+}
+
+impl Span {
+    fn from_json(json: &Json) -> Option<Span> {
+        let text = |key: &str| json.get(key).and_then(Json::as_str).map(str::to_string);
+        let number = |key: &str| json.get(key).and_then(Json::as_usize);
+        Some(Span {
+            file: text("file_name")?,
+            line_start: number("line_start")?,
+            line_end: number("line_end")?,
+            column_start: number("column_start")?,
+            column_end: number("column_end")?,
+            primary: json.get("is_primary").and_then(Json::as_bool)?,
+            label: text("label").filter(|label| !label.is_empty()),
+            replacement: text("suggested_replacement"),
+            applicability: text("suggestion_applicability"),
+        })
+    }
+}
+
+/// One message from the compiler, with the notes and help that came with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// Such as "E0502", for the errors that have one.
+    pub code: Option<String>,
+    pub message: String,
+    pub spans: Vec<Span>,
+    pub children: Vec<Diagnostic>,
+    /// The message as the compiler would have printed it for a person. Empty for children.
+    pub rendered: String,
+}
+
+/// A fix rustc offers: replace the span with the replacement.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Suggestion<'d> {
+    pub message: &'d str,
+    pub span: &'d Span,
+    pub replacement: &'d str,
+}
+
+impl Diagnostic {
+    /// Reads one of rustc's `--error-format=json` lines, or the `compiler-message` lines
+    /// cargo wraps them in. None for anything else.
+    pub fn from_json(line: &str) -> Option<Diagnostic> {
+        let json = Json::parse(line).ok()?;
+        match json.get("reason").and_then(Json::as_str) {
+            Some("compiler-message") => Diagnostic::from_value(json.get("message")?),
+            Some(_) => None,
+            None => Diagnostic::from_value(&json),
+        }
+    }
+
+    fn from_value(json: &Json) -> Option<Diagnostic> {
+        Some(Diagnostic {
+            level: Level::parse(json.get("level")?.as_str()?),
+            code: json
+                .get("code")
+                .and_then(|code| code.get("code"))
+                .and_then(Json::as_str)
+                .map(str::to_string),
+            message: json.get("message")?.as_str()?.to_string(),
+            spans: json
+                .get("spans")?
+                .as_array()
+                .iter()
+                .filter_map(Span::from_json)
+                .collect(),
+            children: json
+                .get("children")
+                .map(Json::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Diagnostic::from_value)
+                .collect(),
+            rendered: json
+                .get("rendered")
+                .and_then(Json::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.spans.iter().find(|span| span.primary)
+    }
+
+    pub fn secondary_spans(&self) -> impl Iterator<Item = &Span> {
+        self.spans.iter().filter(|span| !span.primary)
+    }
+
+    /// The labels of every span, the primary span's first.
+    pub fn labels(&self) -> Vec<&str> {
+        self.primary_first()
+            .filter_map(|span| span.label.as_deref())
+            .collect()
+    }
+
+    fn primary_first(&self) -> impl Iterator<Item = &Span> {
+        self.primary_span()
+            .into_iter()
+            .chain(self.secondary_spans())
+    }
+
+    /// The replacements offered by this diagnostic and its children.
+    pub fn suggestions(&self) -> Vec<Suggestion<'_>> {
+        let own = self.spans.iter().filter_map(|span| {
+            Some(Suggestion {
+                message: &self.message,
+                span,
+                replacement: span.replacement.as_deref()?,
+            })
+        });
+        own.chain(self.children.iter().flat_map(Diagnostic::suggestions))
+            .collect()
+    }
+
+    /// The diagnostic as a few lines pointing into the lesson instead of the snippet:
+    ///
+    /// ```text
+    /// error[E0502]: cannot borrow `data` as mutable because it is also borrowed as immutable
+    ///   src/lesson_3_borrow.rs:67: mutable borrow occurs here
+    ///   src/lesson_3_borrow.rs:65: immutable borrow occurs here
+    /// ```
+    ///
+    /// Spans on snippet lines the lesson does not have keep their snippet line numbers.
+    pub fn located(&self, map: &LineMap, file: &str) -> String {
+        let mut text = match &self.code {
+            Some(code) => format!("{}[{}]: {}\n", self.level.name(), code, self.message),
+            None => format!("{}: {}\n", self.level.name(), self.message),
+        };
+        for span in self.primary_first() {
+            let place = match map.lesson_line(span.line_start) {
+                Some(line) => format!("{}:{}", file, line),
+                None => format!("snippet line {}", span.line_start),
+            };
+            let _ = match &span.label {
+                Some(label) => writeln!(text, "  {}: {}", place, label),
+                None => writeln!(text, "  {}", place),
+            };
+        }
+        for child in &self.children {
+            let _ = writeln!(text, "  = {}: {}", child.level.name(), child.message);
+        }
+        text
+    }
+}
+
+/// Where each line of a snippet is in the lesson it was copied from.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LineMap {
+    /// The lesson line of each snippet line, by snippet line - 1.
+    lines: Vec<Option<usize>>,
+}
+
+impl LineMap {
+    /// Lines are compared as code, so the lesson's commented-out lines and trailing comments
+    /// match the snippet's live code. Lines such as `}` could be anywhere and are left out.
+    /// Snippets copy lines in order, and usually from one example, so of all the ways to
+    /// find the snippet's lines in order in the lesson, the one matching most lines and then
+    /// spread over the fewest lesson lines wins.
+    pub fn new(snippet: &str, lesson: &str) -> LineMap {
+        let snippet: Vec<Option<String>> = snippet.lines().map(significant).collect();
+        let lesson: Vec<Option<String>> = lesson.lines().map(significant).collect();
+        let matches = |s: usize, l: usize| snippet[s].is_some() && snippet[s] == lesson[l];
+        let mut best: Option<(usize, usize, Vec<Option<usize>>)> = None;
+        // Every pair of matching lines anchors a match that works backwards from it, taking
+        // the nearest lesson line for each earlier snippet line.
+        let anchors = (0..snippet.len()).flat_map(|s| (0..lesson.len()).map(move |l| (s, l)));
+        for (s_last, l_last) in anchors.filter(|&(s, l)| matches(s, l)) {
+            let mut lines = vec![None; snippet.len()];
+            lines[s_last] = Some(l_last + 1);
+            let mut first = l_last;
+            let mut count = 1;
+            for s in (0..s_last).rev() {
+                if let Some(l) = (0..first).rev().find(|&l| matches(s, l)) {
+                    lines[s] = Some(l + 1);
+                    first = l;
+                    count += 1;
+                }
+            }
+            let spread = l_last - first;
+            let better = best
+                .as_ref()
+                .is_none_or(|(c, sp, _)| count > *c || (count == *c && spread < *sp));
+            if better {
+                best = Some((count, spread, lines));
+            }
+        }
+        LineMap {
+            lines: best.map(|(_, _, lines)| lines).unwrap_or_default(),
+        }
+    }
+
+    /// The lesson line a snippet line was copied from, both counting from 1.
+    pub fn lesson_line(&self, snippet_line: usize) -> Option<usize> {
+        self.lines
+            .get(snippet_line.checked_sub(1)?)
+            .copied()
+            .flatten()
+    }
+}
+
+// A line as code: uncommented, without its trailing comment, spaces collapsed. None for
+// lines with nothing distinctive on them.
+fn significant(line: &str) -> Option<String> {
+    let mut code = line.trim();
+    while let Some(rest) = code.strip_prefix("//") {
+        code = rest.trim_start();
+    }
+    if let Some(comment) = code.find(" //") {
+        code = &code[..comment];
+    }
+    let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    let distinctive = code.chars().filter(|c| c.is_alphanumeric()).count() >= 3;
+    distinctive.then_some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_service::{CompileService, Limits, Snippet};
+
+    const LESSON: &str = include_str!("lesson_3_borrow.rs");
+
+    // Lesson 3 example 3, with the line the lesson says will not compile put back in.
+    const SNIPPET: &str = r#"pub fn demo() {
+    let mut data = String::from("Hello");
+    data.push_str(" World");
+    let reference1 = &data;
+    let reference2 = &data;
+    data.push_str(" World"); // Uncommenting this line will cause a compilation error
+    println!("reference1: {}", reference1);
+    println!("reference2: {}", reference2);
+}
+"#;
+
+    fn lesson_line(text: &str) -> usize {
+        LESSON
+            .lines()
+            .position(|line| line.contains(text))
+            .expect("No such line")
+            + 1
+    }
+
+    #[test]
+    fn test_diagnostics_are_typed() {
+        let service = CompileService::new(Limits::default());
+        let outcome = service
+            .compile(&Snippet::new(SNIPPET))
+            .expect("Unable to compile");
+        let error = &outcome.diagnostics()[0];
+        assert_eq!(Level::Error, error.level);
+        assert_eq!(Some("E0502"), error.code.as_deref());
+        let primary = error.primary_span().expect("No primary span");
+        assert_eq!((6, 5), (primary.line_start, primary.column_start));
+        assert_eq!(
+            vec![
+                "mutable borrow occurs here",
+                "immutable borrow occurs here",
+                "immutable borrow later used here"
+            ],
+            error.labels()
+        );
+
+        // The lesson has the two references right above the commented-out line, and the
+        // prints after the one below it.
+        let push = lesson_line("// data.push_str(\" World\"); // Uncommenting");
+        let map = LineMap::new(SNIPPET, LESSON);
+        assert_eq!(
+            format!(
+                "error[E0502]: {}\n  \
+                 lesson.rs:{}: mutable borrow occurs here\n  \
+                 lesson.rs:{}: immutable borrow occurs here\n  \
+                 lesson.rs:{}: immutable borrow later used here\n",
+                error.message,
+                push,
+                push - 2,
+                push + 3
+            ),
+            error.located(&map, "lesson.rs")
+        );
+    }
+
+    #[test]
+    fn test_suggestions_come_from_children() {
+        let snippet = "pub fn longest(a: &str, b: &str) -> &str { a }";
+        let service = CompileService::new(Limits::default());
+        let outcome = service
+            .compile(&Snippet::new(snippet))
+            .expect("Unable to compile");
+        let error = &outcome.diagnostics()[0];
+        let suggestions = error.suggestions();
+        assert!(
+            suggestions
+                .iter()
+                .any(|suggestion| suggestion.replacement.contains("'a")),
+            "{:?}",
+            suggestions
+        );
+        assert!(error
+            .children
+            .iter()
+            .any(|child| child.level == Level::Help));
+    }
+
+    #[test]
+    fn test_line_map_prefers_the_closest_copy() {
+        let map = LineMap::new(SNIPPET, LESSON);
+        // `let mut data = String::from("Hello");` is in several examples, the one right above
+        // the rest of the snippet is picked.
+        let data = map.lesson_line(2).expect("Line 2 is not mapped");
+        let reference1 = map.lesson_line(4).expect("Line 4 is not mapped");
+        assert!(reference1 - data < 5, "{} and {}", data, reference1);
+        assert_eq!(
+            Some(lesson_line("// data.push_str(\" World\"); // Uncommenting")),
+            map.lesson_line(6)
+        );
+        // Braces are not mapped, and there is no line 0.
+        assert_eq!(None, map.lesson_line(1));
+        assert_eq!(None, map.lesson_line(9));
+        assert_eq!(None, map.lesson_line(0));
+    }
+}
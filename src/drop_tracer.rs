@@ -0,0 +1,54 @@
+/////////////////////////////////////////////////////////
+// Drop tracer
+/////////////////////////////////////////////////////////
+
+// The lessons print from their Drop impls so we can watch values go away. A Traced value
+// does the same, and also records its name, so an example or test can ask afterwards
+// which values were dropped and, more interestingly, which ones never were.
+
+use std::cell::RefCell;
+
+thread_local! {
+    // Drops are recorded on the thread that performs them.
+    static DROPPED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A value that announces and records its own drop.
+#[derive(Debug)]
+pub struct Traced {
+    name: String,
+}
+
+impl Traced {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for Traced {
+    fn drop(&mut self) {
+        println!("Dropping Traced: {}", self.name);
+        record(&self.name);
+    }
+}
+
+/// Records a drop under this name, for types that print their own drop message.
+pub fn record(name: &str) {
+    DROPPED.with(|dropped| dropped.borrow_mut().push(name.to_string()));
+}
+
+/// Returns true if a Traced value with this name was dropped on the current thread.
+pub fn was_dropped(name: &str) -> bool {
+    DROPPED.with(|dropped| dropped.borrow().iter().any(|n| n == name))
+}
+
+/// Returns the names dropped on the current thread so far, in order, and clears the record.
+pub fn take_dropped() -> Vec<String> {
+    DROPPED.with(|dropped| dropped.borrow_mut().drain(..).collect())
+}
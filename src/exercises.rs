@@ -0,0 +1,261 @@
+/////////////////////////////////////////////////////////
+// Exercises
+/////////////////////////////////////////////////////////
+
+// Small programs the borrow checker rejects, for attendees to fix during a session. Each is
+// handed out as starter code, and a submission passes when it compiles along with a few
+// lines that use it the way the starter code meant, so deleting the problem is not a fix.
+//
+// Submissions come from other people's laptops, so they are only ever compiled, never run.
+// The compile service gives each one a temp crate of its own and limits what rustc may
+// spend on it, and the macros that read files or the environment of the host at compile
+// time are refused before rustc sees them.
+
+use std::io;
+use std::time::Duration;
+
+use crate::compile_service::{CompileService, Edition, Limits, Outcome, Snippet};
+
+pub struct Exercise {
+    pub id: &'static str,
+    /// The lesson that teaches what the exercise needs.
+    pub lesson: &'static str,
+    /// The vocabulary terms a solution uses, which that lesson or the ones before it define.
+    pub requires: &'static [&'static str],
+    pub title: &'static str,
+    pub prompt: &'static str,
+    pub starter: &'static str,
+    /// Compiled after the submission, so it has to keep the starter code's signatures.
+    pub uses: &'static str,
+}
+
+pub const EXERCISES: &[Exercise] = &[
+    Exercise {
+        id: "borrow_instead_of_moving",
+        lesson: "lesson_3_borrow",
+        requires: &["Move", "Immutable Reference"],
+        title: "Borrow instead of moving",
+        prompt: "sum_twice passes the same Vec to sum twice, but the first call moves it. \
+                 Change sum so it borrows the data instead.",
+        starter: r#"pub fn sum(data: Vec<i32>) -> i32 {
+    data.iter().sum()
+}
+
+pub fn sum_twice(data: Vec<i32>) -> i32 {
+    let first = sum(data);
+    let second = sum(data);
+    first + second
+}
+"#,
+        uses: "fn uses() -> i32 { sum_twice(vec![1, 2, 3]) }",
+    },
+    Exercise {
+        id: "one_mutable_borrow",
+        lesson: "lesson_3_borrow",
+        requires: &["Mutable Reference", "Immutable Reference"],
+        title: "Read, then write",
+        prompt: "greet_first still holds a reference into names when it pushes a guest. \
+                 Make it compile without changing its signature.",
+        starter: r#"pub fn greet_first(names: &mut Vec<String>) -> String {
+    let first = &names[0];
+    names.push(String::from("guest"));
+    format!("Hello, {}!", first)
+}
+"#,
+        uses: "fn uses(names: &mut Vec<String>) -> String { greet_first(names) }",
+    },
+    Exercise {
+        id: "longest_lifetime",
+        lesson: "lesson_5_lifetimes",
+        requires: &["Lifetime Parameter", "Elision"],
+        title: "Which lifetime is returned?",
+        prompt: "longest returns one of its arguments, and the compiler needs to be told \
+                 how long the result may be used. Add the lifetimes.",
+        starter: r#"pub fn longest(a: &str, b: &str) -> &str {
+    if a.len() >= b.len() {
+        a
+    } else {
+        b
+    }
+}
+"#,
+        uses: "fn uses<'a>(a: &'a str, b: &'a str) -> &'a str { longest(a, b) }",
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static Exercise> {
+    EXERCISES.iter().find(|exercise| exercise.id == id)
+}
+
+// Each of these reads a file or the environment of the machine doing the compiling. This is
+// a best-effort denylist, not a sandbox: it refuses any submission with one of these words
+// anywhere in it, which also catches raw identifiers and names passed to macros, but it is
+// only as complete as this list. Submissions are compiled, never run.
+const FORBIDDEN: &[&str] = &[
+    "include",
+    "include_str",
+    "include_bytes",
+    "env",
+    "option_env",
+    "path",
+];
+
+pub const SUBMISSION_LIMIT: usize = 32 * 1024;
+
+/// Tighter than the defaults, since anyone in the room can submit.
+pub fn limits() -> Limits {
+    Limits {
+        time: Duration::from_secs(10),
+        output: 16 * 1024,
+        ..Limits::default()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    /// What the compiler said.
+    Failed(String),
+    /// Refused without compiling, and why.
+    Refused(String),
+    TimedOut,
+}
+
+/// Compiles a submission in a sandbox of its own.
+pub fn grade(
+    service: &CompileService,
+    edition: Edition,
+    exercise: &Exercise,
+    submission: &str,
+) -> io::Result<Verdict> {
+    if submission.len() > SUBMISSION_LIMIT {
+        return Ok(Verdict::Refused(format!(
+            "Submissions are limited to {} KiB",
+            SUBMISSION_LIMIT / 1024
+        )));
+    }
+    if let Some(word) = words(submission).find(|word| FORBIDDEN.contains(word)) {
+        return Ok(Verdict::Refused(format!(
+            "`{}` is not allowed here, it can read files or settings of the host",
+            word
+        )));
+    }
+    let source = format!("{}\n#[allow(dead_code)]\n{}\n", submission, exercise.uses);
+    let outcome = service.compile(&Snippet::new(&source).edition(edition))?;
+    Ok(match *outcome {
+        Outcome::TimedOut => Verdict::TimedOut,
+        _ if outcome.success() => Verdict::Passed,
+        _ => Verdict::Failed(outcome.rendered()),
+    })
+}
+
+// Splitting at `#` also takes raw identifiers apart, r#include_str is r and include_str.
+fn words(code: &str) -> impl Iterator<Item = &str> {
+    code.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> CompileService {
+        CompileService::new(limits())
+    }
+
+    const SOLUTIONS: &[(&str, &str)] = &[
+        (
+            "borrow_instead_of_moving",
+            r#"pub fn sum(data: &[i32]) -> i32 {
+    data.iter().sum()
+}
+
+pub fn sum_twice(data: Vec<i32>) -> i32 {
+    let first = sum(&data);
+    let second = sum(&data);
+    first + second
+}
+"#,
+        ),
+        (
+            "one_mutable_borrow",
+            r#"pub fn greet_first(names: &mut Vec<String>) -> String {
+    let greeting = format!("Hello, {}!", names[0]);
+    names.push(String::from("guest"));
+    greeting
+}
+"#,
+        ),
+        (
+            "longest_lifetime",
+            r#"pub fn longest<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if a.len() >= b.len() {
+        a
+    } else {
+        b
+    }
+}
+"#,
+        ),
+    ];
+
+    #[test]
+    fn test_starters_fail_and_solutions_pass() {
+        for (exercise, (id, solution)) in EXERCISES.iter().zip(SOLUTIONS) {
+            assert_eq!(exercise.id, *id);
+            let starter = grade(&service(), Edition::E2021, exercise, exercise.starter)
+                .expect("Unable to grade");
+            assert!(
+                matches!(&starter, Verdict::Failed(stderr) if stderr.contains("error[E")),
+                "the starter code of {} should not compile: {:?}",
+                exercise.id,
+                starter
+            );
+            assert_eq!(
+                Verdict::Passed,
+                grade(&service(), Edition::E2021, exercise, solution).expect("Unable to grade"),
+                "{}",
+                exercise.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_removing_the_code_does_not_pass() {
+        let exercise = find("one_mutable_borrow").expect("No such exercise");
+        let verdict = grade(&service(), Edition::E2021, exercise, "").expect("Unable to grade");
+        assert!(matches!(verdict, Verdict::Failed(_)), "{:?}", verdict);
+    }
+
+    #[test]
+    fn test_reading_host_files_is_refused() {
+        let exercise = &EXERCISES[0];
+        for submission in [
+            r#"const S: &str = include_str!("/etc/passwd");"#,
+            r#"const S: &str = include_str ! ("/etc/passwd");"#,
+            r#"use std::include_bytes as b;"#,
+            r#"#[path = "/etc/passwd"] mod m;"#,
+            r#"const S: &str = r#include_str!("/etc/passwd");"#,
+            r#"const S: Option<&str> = r#option_env!("HOME");"#,
+            r#"macro_rules! read { ($m:ident) => { $m!("/etc/passwd") }; }
+const S: &str = read!(include_str);"#,
+            r#"macro_rules! at { () => { "/etc/passwd" }; }
+#[r#path = at!()] mod m;"#,
+        ] {
+            let verdict =
+                grade(&service(), Edition::E2021, exercise, submission).expect("Unable to grade");
+            assert!(matches!(verdict, Verdict::Refused(_)), "{}", submission);
+        }
+    }
+
+    #[test]
+    fn test_sandbox_path_is_hidden() {
+        let exercise = &EXERCISES[0];
+        let Verdict::Failed(stderr) =
+            grade(&service(), Edition::E2021, exercise, exercise.starter).expect("Unable to grade")
+        else {
+            panic!("the starter code should not compile");
+        };
+        assert!(stderr.contains("--> lib.rs:"), "{}", stderr);
+        assert!(!stderr.contains("learn-borrow-checker-"), "{}", stderr);
+    }
+}
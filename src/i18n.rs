@@ -0,0 +1,478 @@
+/////////////////////////////////////////////////////////
+// Translations
+/////////////////////////////////////////////////////////
+
+// The prose around the code, each lesson's title, introduction, vocabulary and example
+// headings, lives in message catalogs under locales/, one per language, so attendees who
+// read Spanish or Mandarin more easily than English can follow along in their language.
+// Entries are keyed by lesson and example, such as `lesson_3_borrow.4` for the heading of
+// lesson 3 example 4. English is the original, and whatever a catalog is missing is shown
+// in English.
+//
+// A translation is only right for the English it was made from, so each translated entry
+// carries the fingerprint of that English text. When the English changes, the fingerprints
+// stop matching and the tests list the entries that need another look.
+//
+// The example banners stay as they are in the lessons' output, since the stepper finds the
+// examples by them. `run --lang` rewrites them on the way out instead.
+//
+// What the examples print themselves stays in English. Those lines are the program's
+// output, values and all, the same output a reader gets running the lesson code by hand.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The language the lessons are written in, and the fallback for every other one.
+pub const ENGLISH: &str = "en";
+
+// Every catalog, English first.
+const CATALOGS: &[(&str, &str)] = &[
+    (ENGLISH, include_str!("../locales/en.txt")),
+    ("es", include_str!("../locales/es.txt")),
+    ("zh", include_str!("../locales/zh.txt")),
+];
+
+const WIDTH: usize = 90;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub key: &'static str,
+    /// The fingerprint of the English text this translates. None in the English catalog.
+    pub fingerprint: Option<u32>,
+    pub text: &'static str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Catalog {
+    pub language: &'static str,
+    pub entries: Vec<Entry>,
+}
+
+impl Catalog {
+    /// Reads `key = text` lines, or `key [fingerprint] = text` in translations. Blank lines
+    /// and lines starting with # are skipped.
+    pub fn parse(language: &'static str, text: &'static str) -> Result<Catalog, ParseError> {
+        let mut entries: Vec<Entry> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: &str| ParseError {
+                line: index + 1,
+                message: message.to_string(),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, text) = line
+                .split_once(" = ")
+                .ok_or_else(|| error("expected key = text"))?;
+            let (key, fingerprint) = match key.strip_suffix(']').and_then(|k| k.rsplit_once(" [")) {
+                Some((key, hex)) => {
+                    let fingerprint = u32::from_str_radix(hex, 16)
+                        .map_err(|_| error("the fingerprint is not hexadecimal"))?;
+                    (key, Some(fingerprint))
+                }
+                None => (key, None),
+            };
+            if entries.iter().any(|entry| entry.key == key) {
+                return Err(error("the key is already in this catalog"));
+            }
+            entries.push(Entry {
+                key,
+                fingerprint,
+                text: text.trim(),
+            });
+        }
+        Ok(Catalog { language, entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Parsed once. A catalog that does not parse is left out, so its language falls back to
+// English, and the tests say why.
+fn catalogs() -> &'static [Catalog] {
+    static CATALOGS_PARSED: OnceLock<Vec<Catalog>> = OnceLock::new();
+    CATALOGS_PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .filter_map(|(language, text)| Catalog::parse(language, text).ok())
+            .collect()
+    })
+}
+
+/// The languages there is a catalog for.
+pub fn languages() -> Vec<&'static str> {
+    CATALOGS.iter().map(|(language, _)| *language).collect()
+}
+
+/// FNV-1a, which unlike std's hashers gives the same answer on every Rust version.
+// Only the tests compare fingerprints.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn fingerprint(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// The messages of one language, falling back to English entry by entry.
+pub struct Messages {
+    translation: Option<&'static Catalog>,
+    english: Option<&'static Catalog>,
+}
+
+impl Messages {
+    /// Any language may be asked for. Without a catalog of its own, everything is English.
+    pub fn new(language: &str) -> Messages {
+        let find = |language: &str| catalogs().iter().find(|c| c.language == language);
+        Messages {
+            translation: find(language).filter(|_| language != ENGLISH),
+            english: find(ENGLISH),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        [self.translation, self.english]
+            .into_iter()
+            .flatten()
+            .find_map(|catalog| catalog.get(key))
+            .map(|entry| entry.text)
+    }
+
+    /// Such as "Lesson 3: Borrow Checking in Rust".
+    pub fn lesson_title(&self, lesson: &str) -> Option<String> {
+        let title = self.get(&format!("{}.title", lesson))?;
+        Some(self.fill(
+            "runner.lesson",
+            &[("number", number(lesson)), ("title", title)],
+        ))
+    }
+
+    pub fn intro(&self, lesson: &str) -> Option<&'static str> {
+        self.get(&format!("{}.intro", lesson))
+    }
+
+    /// Everything the catalogs say about a lesson: its title, introduction, the definitions
+    /// of its terms and its example headings.
+    pub fn lesson_text(&self, lesson: &str) -> Vec<&'static str> {
+        let prefix = format!("{}.", lesson);
+        let Some(english) = self.english else {
+            return Vec::new();
+        };
+        english
+            .entries
+            .iter()
+            .filter(|entry| entry.key.starts_with(&prefix))
+            .filter_map(|entry| self.get(entry.key))
+            .collect()
+    }
+
+    /// The lesson's vocabulary in the order the English catalog has it, each term with its
+    /// definition.
+    pub fn terms(&self, lesson: &str) -> Vec<(&'static str, &'static str)> {
+        let prefix = format!("{}.term.", lesson);
+        let Some(english) = self.english else {
+            return Vec::new();
+        };
+        english
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let term = entry.key.strip_prefix(&prefix)?;
+                Some((term, self.get(entry.key)?))
+            })
+            .collect()
+    }
+
+    /// The title, introduction and vocabulary of a lesson, wrapped for a terminal.
+    pub fn prose(&self, lesson: &str) -> String {
+        let mut text = String::new();
+        if let Some(title) = self.lesson_title(lesson) {
+            text += &format!(" =============== {} ===============\n", title);
+        }
+        if let Some(intro) = self.intro(lesson) {
+            text += &wrap(intro, "", "");
+            text.push('\n');
+        }
+        let terms = self.terms(lesson);
+        if !terms.is_empty() {
+            text += self.get("runner.vocabulary").unwrap_or("Vocabulary");
+            text.push('\n');
+            for (term, definition) in terms {
+                text += &wrap(&format!("{}: {}", term, definition), "  ", "      ");
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// A lesson's output with each example banner naming the example in this language,
+    /// such as " --------------- lesson 1 example 2: Passing Ownership ---------------".
+    pub fn localize(&self, lesson: &str, output: &str) -> String {
+        let mut text = String::new();
+        for line in output.lines() {
+            let banner = line
+                .strip_prefix(" --------------- ")
+                .and_then(|rest| rest.strip_suffix(" ---------------"));
+            let localized =
+                banner.and_then(|title| match title.split(' ').collect::<Vec<_>>()[..] {
+                    ["lesson", number, "example", example] => {
+                        let heading = self.get(&format!("{}.{}", lesson, example))?;
+                        let title = self.fill(
+                            "runner.example",
+                            &[
+                                ("number", number),
+                                ("example", example),
+                                ("heading", heading),
+                            ],
+                        );
+                        Some(format!(" --------------- {} ---------------", title))
+                    }
+                    _ => None,
+                });
+            text += localized.as_deref().unwrap_or(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    // A message with {name} placeholders filled in.
+    fn fill(&self, key: &str, values: &[(&str, &str)]) -> String {
+        let mut text = self.get(key).unwrap_or(key).to_string();
+        for (name, value) in values {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+// The number in a lesson id such as lesson_3_borrow.
+fn number(lesson: &str) -> &str {
+    lesson.split('_').nth(1).unwrap_or(lesson)
+}
+
+// Word wraps text at WIDTH columns, with a prefix for the first line and another for the
+// rest. Text without spaces, such as Mandarin, is wrapped between any two characters.
+fn wrap(text: &str, first: &str, rest: &str) -> String {
+    let mut wrapped = String::new();
+    let mut line = String::from(first);
+    let mut empty = true;
+    for word in text.split(' ') {
+        let wide = word.chars().any(|c| columns(c) == 2);
+        if !empty && !wide && width(&line) + 1 + width(word) > WIDTH {
+            wrapped += line.trim_end();
+            wrapped.push('\n');
+            line = String::from(rest);
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        for c in word.chars() {
+            if columns(c) == 2 && width(&line) + 2 > WIDTH {
+                wrapped += line.trim_end();
+                wrapped.push('\n');
+                line = String::from(rest);
+            }
+            line.push(c);
+        }
+        empty = false;
+    }
+    wrapped += line.trim_end();
+    wrapped.push('\n');
+    wrapped
+}
+
+fn width(text: &str) -> usize {
+    text.chars().map(columns).sum()
+}
+
+// How many columns a terminal gives a character: two for the CJK ideographs, kana, hangul
+// and fullwidth forms, one for the rest.
+fn columns(c: char) -> usize {
+    match c {
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}' => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline;
+
+    #[test]
+    fn test_catalogs_parse() {
+        for (language, text) in CATALOGS {
+            if let Err(error) = Catalog::parse(language, text) {
+                panic!("locales/{}.txt, {}", language, error);
+            }
+        }
+        assert_eq!(CATALOGS.len(), catalogs().len());
+        let bad = Catalog::parse("xx", "a = 1\na = 2\n");
+        assert_eq!(2, bad.expect_err("a key given twice").line);
+        let bad = Catalog::parse("xx", "a [not hex] = 1\n");
+        assert_eq!(1, bad.expect_err("a bad fingerprint").line);
+    }
+
+    // Each lesson needs its title and introduction in English, and a heading for every
+    // example it prints a banner for.
+    #[test]
+    fn test_english_covers_every_lesson() {
+        let english = Messages::new(ENGLISH);
+        let mut missing = Vec::new();
+        for lesson in crate::LESSONS {
+            for key in ["title", "intro"] {
+                let key = format!("{}.{}", lesson.id, key);
+                if english.get(&key).is_none() {
+                    missing.push(key);
+                }
+            }
+            for example in timeline::examples(lesson.source) {
+                let number = example.title.rsplit(' ').next().unwrap_or_default();
+                let key = format!("{}.{}", lesson.id, number);
+                if english.get(&key).is_none() {
+                    missing.push(key);
+                }
+            }
+        }
+        assert!(
+            missing.is_empty(),
+            "locales/en.txt has no {}",
+            missing.join(", ")
+        );
+    }
+
+    // Every English entry needs a translation made from its current text, and a translation
+    // of something English no longer has is left over.
+    #[test]
+    fn test_translations_are_complete_and_current() {
+        let english = &catalogs()[0];
+        let mut problems = Vec::new();
+        for catalog in &catalogs()[1..] {
+            let file = format!("locales/{}.txt", catalog.language);
+            for entry in &english.entries {
+                let current = fingerprint(entry.text);
+                match catalog.get(entry.key) {
+                    None => problems.push(format!(
+                        "{} is missing {} [{:08x}], in English: {}",
+                        file, entry.key, current, entry.text
+                    )),
+                    Some(translation) if translation.fingerprint != Some(current) => {
+                        problems.push(format!(
+                            "{} translates an older {}, update it and its fingerprint to \
+                             [{:08x}], the English is now: {}",
+                            file, entry.key, current, entry.text
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for entry in &catalog.entries {
+                if english.get(entry.key).is_none() {
+                    problems.push(format!(
+                        "{} has {}, which English no longer has",
+                        file, entry.key
+                    ));
+                }
+            }
+        }
+        assert!(problems.is_empty(), "\n{}", problems.join("\n"));
+    }
+
+    #[test]
+    fn test_missing_entries_fall_back_to_english() {
+        let english = Messages::new(ENGLISH);
+        let unknown = Messages::new("tlh");
+        assert_eq!(
+            english.get("lesson_3_borrow.4"),
+            unknown.get("lesson_3_borrow.4")
+        );
+        assert_eq!(
+            Some(String::from("Lesson 3: Borrow Checking in Rust")),
+            unknown.lesson_title("lesson_3_borrow")
+        );
+
+        let partial = Catalog {
+            language: "xx",
+            entries: vec![Entry {
+                key: "lesson_3_borrow.title",
+                fingerprint: None,
+                text: "Xx",
+            }],
+        };
+        let messages = Messages {
+            translation: Some(Box::leak(Box::new(partial))),
+            english: english.english,
+        };
+        assert_eq!(
+            Some(String::from("Lesson 3: Xx")),
+            messages.lesson_title("lesson_3_borrow")
+        );
+        assert_eq!(
+            english.intro("lesson_3_borrow"),
+            messages.intro("lesson_3_borrow")
+        );
+    }
+
+    #[test]
+    fn test_banners_are_localized() {
+        let output = " --------------- lesson 3 example 4 ---------------\n\
+                      data: Hello\n\
+                      \x20--------------- lesson 3 example 99 ---------------\n";
+        assert_eq!(
+            " --------------- lesson 3 example 4: Using Scopes for References ---------------\n\
+             data: Hello\n\
+             \x20--------------- lesson 3 example 99 ---------------\n",
+            Messages::new(ENGLISH).localize("lesson_3_borrow", output)
+        );
+        let spanish = Messages::new("es").localize("lesson_3_borrow", output);
+        assert!(
+            spanish.starts_with(" --------------- lección 3, ejemplo 4: "),
+            "{}",
+            spanish
+        );
+    }
+
+    #[test]
+    fn test_prose_is_wrapped() {
+        let prose = Messages::new(ENGLISH).prose("lesson_1_scope");
+        assert!(prose.starts_with(
+            " =============== Lesson 1: Ownership and Dropping - It's All About Scope ===============\n\
+             Rust fundamentally emphasizes scope management"
+        ));
+        assert!(prose.contains("\nVocabulary\n  Own, Owned, Ownership: The concept of a scope"));
+        assert!(prose.lines().skip(1).all(|line| width(line) <= WIDTH));
+        let mandarin = wrap(&"借用".repeat(30), "  ", "    ");
+        assert_eq!(2, mandarin.lines().count());
+        assert_eq!(Some(WIDTH), mandarin.lines().next().map(width));
+        assert!(mandarin
+            .lines()
+            .nth(1)
+            .is_some_and(|line| line.starts_with("    借")));
+        let prose = Messages::new("zh").prose("lesson_3_borrow");
+        assert!(
+            prose.lines().skip(1).all(|line| width(line) <= WIDTH),
+            "{}",
+            prose
+        );
+    }
+}
@@ -0,0 +1,288 @@
+/////////////////////////////////////////////////////////
+// JSON
+/////////////////////////////////////////////////////////
+
+// rustc and cargo report diagnostics as JSON, one object per line. This is just enough of a
+// reader for those: it parses a whole document into a tree and has accessors for walking it.
+// It is not a general purpose JSON library, and it does not need to be one.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The value of a field, or None when this is not an object or has no such field.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The items of an array, or none when this is not one.
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// A number that is a whole, non-negative one, such as a line or a column.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Json, ParseError> {
+        let mut parser = Parser { text, at: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.at < text.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The byte offset the parser gave up at.
+    pub at: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.at)
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            at: self.at,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &'static str, value: Json) -> Result<Json, ParseError> {
+        if self.text[self.at..].starts_with(literal) {
+            self.at += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected word"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, ParseError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null", Json::Null),
+            Some(b't') => self.expect("true", Json::Bool(true)),
+            Some(b'f') => self.expect("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b']') => {
+                            self.at += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.whitespace();
+                    if self.peek() != Some(b':') {
+                        return Err(self.error("expected :"));
+                    }
+                    self.at += 1;
+                    fields.push((key, self.value()?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b'}') => {
+                            self.at += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while matches!(
+                    self.peek(),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.at += 1;
+                }
+                self.text[start..self.at]
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| self.error("bad number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        // Past the opening quote.
+        self.at += 1;
+        let mut string = String::new();
+        loop {
+            let rest = &self.text[self.at..];
+            let Some(end) = rest.find(['"', '\\']) else {
+                return Err(self.error("unterminated string"));
+            };
+            string.push_str(&rest[..end]);
+            self.at += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(string);
+            }
+            let escape = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.at += 1;
+            match escape {
+                b'"' => string.push('"'),
+                b'\\' => string.push('\\'),
+                b'/' => string.push('/'),
+                b'b' => string.push('\u{8}'),
+                b'f' => string.push('\u{c}'),
+                b'n' => string.push('\n'),
+                b'r' => string.push('\r'),
+                b't' => string.push('\t'),
+                b'u' => {
+                    let high = self.hex4()?;
+                    // Characters outside the basic plane come as a surrogate pair.
+                    let code = if (0xD800..0xDC00).contains(&high)
+                        && self.text[self.at..].starts_with("\\u")
+                    {
+                        self.at += 2;
+                        let low = self.hex4()?;
+                        0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+                    } else {
+                        high
+                    };
+                    string.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                }
+                _ => return Err(self.error("bad escape")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let digits = self
+            .text
+            .get(self.at..self.at + 4)
+            .ok_or_else(|| self.error("bad \\u escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("bad \\u escape"))?;
+        self.at += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested() {
+        let json = Json::parse(
+            r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d\n\"e\" é 🦀"}, "f": []} "#,
+        )
+        .expect("Unable to parse");
+        assert_eq!(
+            Some(&Json::Array(vec![
+                Json::Number(1.0),
+                Json::Number(-25.0),
+                Json::Bool(true),
+                Json::Null
+            ])),
+            json.get("a")
+        );
+        assert_eq!(
+            Some("d\n\"e\" é 🦀"),
+            json.get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(Json::as_str)
+        );
+        assert_eq!(Some(&Json::Array(Vec::new())), json.get("f"));
+        assert_eq!(None, json.get("missing"));
+        assert_eq!(
+            Ok(Json::String(String::from("é🦀"))),
+            Json::parse(r#""\u00e9\ud83e\udd80""#)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in ["", "{", "[1,]", "{\"a\" 1}", "\"open", "tru", "1 2"] {
+            assert!(Json::parse(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+}
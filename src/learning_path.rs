@@ -0,0 +1,424 @@
+/////////////////////////////////////////////////////////
+// Learning path
+/////////////////////////////////////////////////////////
+
+// Lessons build on each other, and so do the exercises on the lessons. Each one declares the
+// vocabulary terms it relies on, and whichever lesson defines a term first (its vocabulary in
+// locales/en.txt) is a prerequisite of everything that requires the term. `path` orders the
+// lessons and exercises so each comes after what it builds on, shows the shortest way to a
+// given lesson, and warns a learner who has done a lesson before the ones it builds on.
+//
+// The declarations are checked against the prose too: a lesson whose introduction, vocabulary
+// or example headings mention a term defined by another lesson has to build on that lesson,
+// directly or through its prerequisites.
+
+use std::fmt;
+
+use crate::exercises::Exercise;
+use crate::i18n::{self, Messages};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Lesson,
+    Exercise,
+}
+
+#[derive(Debug)]
+pub struct Step {
+    pub id: &'static str,
+    pub kind: Kind,
+    /// The lessons this step builds on directly, each with the required terms it defines.
+    pub after: Vec<(&'static str, Vec<&'static str>)>,
+}
+
+/// The lessons and exercises in the order to take them.
+#[derive(Debug)]
+pub struct Path {
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// A step requires a term no other lesson's vocabulary defines.
+    UnknownTerm {
+        step: &'static str,
+        term: &'static str,
+    },
+    /// Steps that each wait for another of them.
+    Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::UnknownTerm { step, term } => {
+                write!(
+                    f,
+                    "{} requires {}, which no other lesson defines",
+                    step, term
+                )
+            }
+            PathError::Cycle(steps) => {
+                write!(f, "{} each wait for another of them", steps.join(", "))
+            }
+        }
+    }
+}
+
+/// A step whose prose mentions a term before anything it builds on defines it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EarlyUse {
+    pub step: &'static str,
+    pub term: &'static str,
+    /// Every lesson defining the term.
+    pub defined_in: Vec<&'static str>,
+}
+
+impl fmt::Display for EarlyUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} uses {} but does not build on {}, which defines it",
+            self.step,
+            self.term,
+            self.defined_in.join(" or ")
+        )
+    }
+}
+
+// A term as a lesson's vocabulary spells it, such as "Borrowed" from
+// "Borrow, Borrowed, Borrowing". Terms are the same whatever their case, so the "move"
+// keyword of lesson 10 is the "Move" of lesson 1.
+struct Definition {
+    term: &'static str,
+    lesson: &'static str,
+}
+
+fn definitions(lessons: &[&'static str]) -> Vec<Definition> {
+    let english = Messages::new(i18n::ENGLISH);
+    let mut found = Vec::new();
+    for lesson in lessons {
+        for (terms, _) in english.terms(lesson) {
+            for term in terms.split(", ") {
+                found.push(Definition { term, lesson });
+            }
+        }
+    }
+    found
+}
+
+impl Path {
+    /// Lessons are given in their usual order with the terms each requires. An exercise
+    /// also builds on the lesson it is handed out after.
+    pub fn new(
+        lessons: &[(&'static str, &'static [&'static str])],
+        exercises: &[Exercise],
+    ) -> Result<Path, PathError> {
+        let ids: Vec<&'static str> = lessons.iter().map(|(id, _)| *id).collect();
+        let definitions = definitions(&ids);
+        let units = lessons
+            .iter()
+            .map(|(id, requires)| (*id, Kind::Lesson, *requires, None))
+            .chain(exercises.iter().map(|exercise| {
+                let lesson = Some(exercise.lesson);
+                (exercise.id, Kind::Exercise, exercise.requires, lesson)
+            }));
+        let mut pending = Vec::new();
+        for (id, kind, requires, lesson) in units {
+            let mut after: Vec<(&'static str, Vec<&'static str>)> = Vec::new();
+            if let Some(lesson) = lesson {
+                after.push((lesson, Vec::new()));
+            }
+            for &term in requires {
+                // The first lesson to define a term is where it is learned.
+                let definer = definitions
+                    .iter()
+                    .find(|d| d.lesson != id && d.term.eq_ignore_ascii_case(term))
+                    .ok_or(PathError::UnknownTerm { step: id, term })?;
+                match after
+                    .iter_mut()
+                    .find(|(lesson, _)| *lesson == definer.lesson)
+                {
+                    Some((_, terms)) => terms.push(term),
+                    None => after.push((definer.lesson, vec![term])),
+                }
+            }
+            after.sort_by_key(|(lesson, _)| ids.iter().position(|id| id == lesson));
+            pending.push(Step { id, kind, after });
+        }
+
+        // Exercises as soon as they can be done, otherwise lessons in their usual order.
+        let mut steps: Vec<Step> = Vec::new();
+        while !pending.is_empty() {
+            let ready = |step: &Step| {
+                step.after
+                    .iter()
+                    .all(|(lesson, _)| steps.iter().any(|done| done.id == *lesson))
+            };
+            let next = pending
+                .iter()
+                .position(|step| step.kind == Kind::Exercise && ready(step))
+                .or_else(|| pending.iter().position(ready));
+            match next {
+                Some(index) => steps.push(pending.remove(index)),
+                None => return Err(PathError::Cycle(pending.iter().map(|s| s.id).collect())),
+            }
+        }
+        Ok(Path { steps })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Step> {
+        self.steps.iter().find(|step| step.id == id)
+    }
+
+    /// Every lesson a step builds on, directly or not, in the order of the path.
+    pub fn before(&self, id: &str) -> Vec<&'static str> {
+        let mut wanted: Vec<&'static str> = Vec::new();
+        let mut queue: Vec<&str> = vec![id];
+        while let Some(id) = queue.pop() {
+            for (lesson, _) in self.get(id).map(|step| &step.after[..]).unwrap_or_default() {
+                if !wanted.contains(lesson) {
+                    wanted.push(lesson);
+                    queue.push(lesson);
+                }
+            }
+        }
+        self.steps
+            .iter()
+            .map(|step| step.id)
+            .filter(|id| wanted.contains(id))
+            .collect()
+    }
+
+    /// The steps done ahead of lessons they build on, each with the lessons still missing.
+    pub fn jumps(&self, done: &[&str]) -> Vec<(&'static str, Vec<&'static str>)> {
+        self.steps
+            .iter()
+            .filter(|step| done.contains(&step.id))
+            .map(|step| {
+                let missing = self.before(step.id);
+                (
+                    step.id,
+                    missing.into_iter().filter(|l| !done.contains(l)).collect(),
+                )
+            })
+            .filter(|(_, missing): &(_, Vec<_>)| !missing.is_empty())
+            .collect()
+    }
+
+    /// The first step not done yet whose lessons all are, on the way to the target when
+    /// there is one.
+    pub fn next(&self, target: Option<&str>, done: &[&str]) -> Option<&Step> {
+        let way = target.map(|target| {
+            let mut way = self.before(target);
+            way.push(target);
+            way
+        });
+        self.steps.iter().find(|step| {
+            way.as_ref().is_none_or(|way| way.contains(&step.id))
+                && !done.contains(&step.id)
+                && step.after.iter().all(|(lesson, _)| done.contains(lesson))
+        })
+    }
+
+    /// Terms the prose of a step mentions although nothing it builds on defines them. For a
+    /// lesson that is everything the English catalog says about it, for an exercise its
+    /// title and prompt.
+    pub fn early_uses(&self, exercises: &[Exercise]) -> Vec<EarlyUse> {
+        let lessons: Vec<&'static str> = self
+            .steps
+            .iter()
+            .filter(|step| step.kind == Kind::Lesson)
+            .map(|step| step.id)
+            .collect();
+        let definitions = definitions(&lessons);
+        let english = Messages::new(i18n::ENGLISH);
+        let mut found: Vec<EarlyUse> = Vec::new();
+        for step in &self.steps {
+            let text = match step.kind {
+                Kind::Lesson => english.lesson_text(step.id).join("\n"),
+                Kind::Exercise => exercises
+                    .iter()
+                    .find(|exercise| exercise.id == step.id)
+                    .map(|exercise| format!("{}\n{}", exercise.title, exercise.prompt))
+                    .unwrap_or_default(),
+            };
+            let mut known = self.before(step.id);
+            known.push(step.id);
+            for definition in &definitions {
+                if !mentions(&text, definition.term) {
+                    continue;
+                }
+                let defined_in: Vec<&'static str> = lessons
+                    .iter()
+                    .copied()
+                    .filter(|lesson| {
+                        definitions.iter().any(|d| {
+                            d.lesson == *lesson && d.term.eq_ignore_ascii_case(definition.term)
+                        })
+                    })
+                    .collect();
+                let reported = found
+                    .iter()
+                    .any(|e| e.step == step.id && e.term.eq_ignore_ascii_case(definition.term));
+                if !reported && !defined_in.iter().any(|lesson| known.contains(lesson)) {
+                    found.push(EarlyUse {
+                        step: step.id,
+                        term: definition.term,
+                        defined_in,
+                    });
+                }
+            }
+        }
+        found
+    }
+}
+
+// Whether the text uses the term as a word of its own. Qualified names such as
+// thread::spawn and method calls such as x.drop() name something else.
+fn mentions(text: &str, term: &str) -> bool {
+    text.match_indices(term).any(|(at, _)| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + term.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || "_':.".contains(c))
+            && !after.is_some_and(|c| c.is_alphanumeric() || "_-".contains(c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercises::EXERCISES;
+
+    fn path() -> Path {
+        let lessons: Vec<_> = crate::LESSONS.iter().map(|l| (l.id, l.requires)).collect();
+        Path::new(&lessons, EXERCISES).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    #[test]
+    fn test_lessons_keep_their_order() {
+        let path = path();
+        let lessons: Vec<&str> = path
+            .steps
+            .iter()
+            .filter(|step| step.kind == Kind::Lesson)
+            .map(|step| step.id)
+            .collect();
+        let usual: Vec<&str> = crate::LESSONS.iter().map(|lesson| lesson.id).collect();
+        assert_eq!(usual, lessons);
+        assert_eq!(
+            vec![
+                "lesson_1_scope",
+                "lesson_2_drop_cc",
+                "lesson_3_borrow",
+                "lesson_4_bonus",
+                "lesson_5_lifetimes",
+                "lesson_6_shared_ownership",
+                "lesson_7_threads",
+                "lesson_8_interior_mutability",
+                "lesson_13_pin",
+            ],
+            path.before("lesson_14_async")
+        );
+    }
+
+    // Every term a lesson or exercise mentions comes from a lesson it builds on.
+    #[test]
+    fn test_no_step_uses_a_term_before_it_is_defined() {
+        let early: Vec<String> = path()
+            .early_uses(EXERCISES)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert!(early.is_empty(), "\n{}", early.join("\n"));
+    }
+
+    // An exercise is handed out after a lesson that prepares for all of it.
+    #[test]
+    fn test_exercises_follow_their_lessons() {
+        let path = path();
+        for exercise in EXERCISES {
+            let mut prepared = path.before(exercise.lesson);
+            prepared.push(exercise.lesson);
+            let missing: Vec<&str> = path
+                .before(exercise.id)
+                .into_iter()
+                .filter(|lesson| !prepared.contains(lesson))
+                .collect();
+            assert!(
+                missing.is_empty(),
+                "{} is handed out after {} but builds on {}",
+                exercise.id,
+                exercise.lesson,
+                missing.join(", ")
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_terms_and_cycles_are_errors() {
+        let lessons: &[(&str, &[&str])] = &[("lesson_1_scope", &["Monad"])];
+        assert_eq!(
+            Some(PathError::UnknownTerm {
+                step: "lesson_1_scope",
+                term: "Monad"
+            }),
+            Path::new(lessons, &[]).err()
+        );
+        let lessons: &[(&str, &[&str])] = &[
+            ("lesson_1_scope", &["reference"]),
+            ("lesson_3_borrow", &["Ownership"]),
+        ];
+        assert_eq!(
+            Some(PathError::Cycle(vec!["lesson_1_scope", "lesson_3_borrow"])),
+            Path::new(lessons, &[]).err()
+        );
+    }
+
+    #[test]
+    fn test_jumping_ahead() {
+        let path = path();
+        let done = [
+            "lesson_1_scope",
+            "lesson_2_drop_cc",
+            "lesson_8_interior_mutability",
+        ];
+        assert_eq!(
+            vec![(
+                "lesson_8_interior_mutability",
+                vec![
+                    "lesson_3_borrow",
+                    "lesson_4_bonus",
+                    "lesson_6_shared_ownership"
+                ]
+            )],
+            path.jumps(&done)
+        );
+        assert_eq!(
+            Some("lesson_3_borrow"),
+            path.next(None, &done).map(|step| step.id)
+        );
+        let done = ["lesson_1_scope", "lesson_2_drop_cc", "lesson_3_borrow"];
+        assert!(path.jumps(&done).is_empty());
+        assert_eq!(
+            Some("borrow_instead_of_moving"),
+            path.next(None, &done).map(|step| step.id)
+        );
+        assert_eq!(
+            Some("lesson_4_bonus"),
+            path.next(Some("lesson_14_async"), &done)
+                .map(|step| step.id)
+        );
+    }
+
+    #[test]
+    fn test_mentions() {
+        assert!(mentions("the Box<[T]> it returns", "Box"));
+        assert!(mentions("across .await points", ".await"));
+        assert!(mentions("a &'a str", "'a"));
+        assert!(!mentions("thread::spawn with move", "spawn"));
+        assert!(!mentions("Self-Referential types", "Self"));
+        assert!(!mentions("a Boxed value", "Box"));
+        assert!(!mentions("&'static str", "&str"));
+    }
+}
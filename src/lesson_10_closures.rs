@@ -0,0 +1,297 @@
+/*****************************************************/
+/* Lesson 10: Closures and Capture Modes             */
+/*****************************************************/
+
+// Each helper accepts the weakest trait it needs, so we can see which closures fit.
+pub fn call_fn<F: Fn() -> String>(f: F) -> String {
+    f() + &f() // Called twice through a shared reference
+}
+
+pub fn call_fn_mut<R, F: FnMut() -> R>(mut f: F) -> R {
+    f();
+    f() // Called twice, but needs the closure to be mutable
+}
+
+pub fn call_fn_once<F: FnOnce() -> String>(f: F) -> String {
+    f() // Called once, the closure is consumed
+}
+
+// move is required, n would otherwise be borrowed from a stack frame that is about to end.
+pub fn make_adder(n: i32) -> impl Fn(i32) -> i32 {
+    move |x| x + n
+}
+
+// A counter that carries its own state, each call mutates the captured count.
+pub fn make_counter() -> impl FnMut() -> u32 {
+    let mut count = 0;
+    move || {
+        count += 1;
+        count
+    }
+}
+
+// Different closures have different types, boxing them erases that difference.
+pub fn make_op(name: &str, step: i32) -> Box<dyn Fn(i32) -> i32> {
+    match name {
+        "add" => Box::new(move |x| x + step),
+        "sub" => Box::new(move |x| x - step),
+        _ => Box::new(|x| x),
+    }
+}
+
+// A boxed closure may borrow too, as long as its type says for how long.
+pub fn make_prefixer<'a>(prefix: &'a str) -> Box<dyn Fn(&str) -> String + 'a> {
+    Box::new(move |text| format!("{}{}", prefix, text))
+}
+
+#[derive(Debug)]
+pub struct Player {
+    pub name: String,
+    pub score: u32,
+}
+
+pub(crate) fn examples() {
+    // 1) Capture by reference: Fn
+    println!(" --------------- lesson 10 example 1 ---------------");
+    {
+        let greeting = String::from("Hello");
+        let greet = || format!("{}!", greeting); // Only holds a & to greeting, so it is Copy
+        println!("call_fn: {}", call_fn(greet)); // Passing greet copies it
+        println!("call_fn_mut: {}", call_fn_mut(greet)); // Fn closures are also FnMut
+        println!("call_fn_once: {}", call_fn_once(greet)); // and FnOnce
+        println!("greeting is still ours: {}", greeting);
+    }
+
+    // 2) Capture by mutable reference: FnMut
+    println!(" --------------- lesson 10 example 2 ---------------");
+    {
+        let mut count = 0;
+        // Mutates count, so borrows it as &mut for as long as increment is used
+        let mut increment = || count += 1;
+        // println!("{}", count); // Uncommenting this line will cause a compilation error (E0502)
+        increment();
+        increment();
+        println!("count after increment: {}", count); // The &mut borrow ended with the last call
+        call_fn_mut(|| count += 10);
+        println!("count after call_fn_mut: {}", count);
+        // call_fn(|| { count += 1; String::new() }); // Uncommenting this line will cause a compilation error (E0594)
+    }
+
+    // 3) Capture by value: FnOnce
+    println!(" --------------- lesson 10 example 3 ---------------");
+    {
+        let name = String::from("consumed");
+        let give_away = || name; // Returns name, so it must own it, and can only do that once
+        println!("call_fn_once: {}", call_fn_once(give_away));
+        // give_away(); // Uncommenting this line will cause a compilation error (E0382)
+        // println!("{}", name); // Uncommenting this line will cause a compilation error (E0382)
+
+        let label = String::from("label");
+        let once = move || drop(label);
+        once();
+        // call_fn_mut(once); // Uncommenting this line will cause a compilation error (E0525)
+    }
+
+    // 4) move forces capture by value
+    println!(" --------------- lesson 10 example 4 ---------------");
+    {
+        let data = String::from("heap data");
+        let borrowing = || data.len(); // Borrows data
+        println!("borrowing: {}", borrowing());
+        let owning = move || data.len(); // Takes data, even though reading would be enough
+        println!("owning: {}", owning());
+        // println!("{}", data); // Uncommenting this line will cause a compilation error (E0382)
+
+        let count = 5;
+        let copying = move || count * 2; // Copy types are copied in, the original stays usable
+        println!("copying: {}, count: {}", copying(), count);
+    }
+
+    // 5) Rust 2021 disjoint field capture
+    println!(" --------------- lesson 10 example 5 ---------------");
+    {
+        let mut player = Player {
+            name: String::from("ferris"),
+            score: 0,
+        };
+        let show_name = || println!("name: {}", player.name); // Captures only &player.name
+        player.score += 10; // So this field is free to change, since Rust 2021 (E0506 in 2018)
+        show_name();
+        let take_name = move || player.name; // Moves only player.name out of player
+        println!(
+            "taken: {}, score still readable: {}",
+            take_name(),
+            player.score
+        );
+        // println!("{:?}", player); // Uncommenting this line will cause a compilation error (E0382), player.name is gone
+    }
+
+    // 6) Returning closures with impl Fn
+    println!(" --------------- lesson 10 example 6 ---------------");
+    {
+        let add_five = make_adder(5);
+        println!("add_five(10): {}", add_five(10));
+        // Without move: fn make_adder(n: i32) -> impl Fn(i32) -> i32 { |x| x + n } // error[E0373]
+        let mut counter = make_counter();
+        println!("counter: {} {} {}", counter(), counter(), counter());
+        // Two closures never share a type, so impl Fn cannot pick between them
+        // if up { move |x| x + step } else { move |x| x - step } // error[E0308]
+    }
+
+    // 7) Returning closures with Box<dyn Fn>
+    println!(" --------------- lesson 10 example 7 ---------------");
+    {
+        let ops: Vec<Box<dyn Fn(i32) -> i32>> =
+            vec![make_op("add", 3), make_op("sub", 1), make_op("none", 0)];
+        let results: Vec<i32> = ops.iter().map(|op| op(10)).collect();
+        println!("ops applied to 10: {:?}", results);
+
+        let prefix = String::from(">> ");
+        let prefixer = make_prefixer(&prefix); // Borrows prefix for as long as prefixer lives
+        println!("{}", prefixer("boxed closures can borrow"));
+        // Without + 'a the box is 'static, so the prefix has to be too, and &prefix is not:
+        // fn make_prefixer(prefix: &'static str) -> Box<dyn Fn(&str) -> String> // error[E0597]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, assert_all_rejected_in, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_10_read_while_mut_captured",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut count = 0;
+    let mut increment = || count += 1;
+    println!("{}", count);
+    increment();
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_fn_mut_where_fn_expected",
+            code: "E0594",
+            source: r#"
+fn call_fn<F: Fn() -> String>(f: F) -> String { f() + &f() }
+pub fn demo() {
+    let mut count = 0;
+    call_fn(|| { count += 1; String::new() });
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_fn_once_called_twice",
+            code: "E0382",
+            source: r#"
+pub fn demo() {
+    let name = String::from("consumed");
+    let give_away = || name;
+    give_away();
+    give_away();
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_fn_once_where_fn_mut_expected",
+            code: "E0525",
+            source: r#"
+fn call_fn_mut<F: FnMut()>(mut f: F) { f(); f(); }
+pub fn demo() {
+    let label = String::from("label");
+    let once = move || drop(label);
+    call_fn_mut(once);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_use_after_move_closure",
+            code: "E0382",
+            source: r#"
+pub fn demo() {
+    let data = String::from("heap data");
+    let owning = move || data.len();
+    owning();
+    println!("{}", data);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_returned_closure_without_move",
+            code: "E0373",
+            source: r#"
+pub fn make_adder(n: i32) -> impl Fn(i32) -> i32 {
+    |x| x + n
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_closures_have_distinct_types",
+            code: "E0308",
+            source: r#"
+pub fn pick(up: bool, step: i32) -> impl Fn(i32) -> i32 {
+    if up { move |x| x + step } else { move |x| x - step }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_10_boxed_closure_without_lifetime",
+            code: "E0597",
+            source: r#"
+pub fn make_prefixer(prefix: &'static str) -> Box<dyn Fn(&str) -> String> {
+    Box::new(move |text| format!("{}{}", prefix, text))
+}
+pub fn demo() {
+    let prefix = String::from(">> ");
+    let prefixer = make_prefixer(&prefix);
+    println!("{}", prefixer("boxed closures can borrow"));
+}
+"#,
+        },
+    ];
+
+    // The same code as example 5, which only compiles since Rust 2021.
+    const COMPILE_FAIL_2018: &[CompileFail] = &[CompileFail {
+        id: "lesson_10_whole_struct_capture_2018",
+        code: "E0506",
+        source: r#"
+pub struct Player { pub name: String, pub score: u32 }
+pub fn demo() {
+    let mut player = Player { name: String::from("ferris"), score: 0 };
+    let show_name = || println!("name: {}", player.name);
+    player.score += 10;
+    show_name();
+}
+"#,
+    }];
+
+    #[test]
+    fn test_capture_traits() {
+        let greeting = String::from("Hi");
+        assert_eq!("HiHi", call_fn(|| greeting.clone()));
+        let mut calls = 0;
+        call_fn_mut(|| calls += 1);
+        assert_eq!(2, calls);
+        assert_eq!("Hi", call_fn_once(move || greeting));
+    }
+
+    #[test]
+    fn test_returned_closures() {
+        assert_eq!(15, make_adder(5)(10));
+        let mut counter = make_counter();
+        assert_eq!((1, 2), (counter(), counter()));
+        assert_eq!(13, make_op("add", 3)(10));
+        assert_eq!(9, make_op("sub", 1)(10));
+        let prefix = String::from("> ");
+        assert_eq!("> x", make_prefixer(&prefix)("x"));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+        assert_all_rejected_in("2018", COMPILE_FAIL_2018);
+    }
+}
@@ -0,0 +1,267 @@
+/*****************************************************/
+/* Lesson 11: Iterators and Borrowing                */
+/*****************************************************/
+
+use itertools::Itertools;
+
+// Borrows the names, the returned references point into the caller's Vec.
+pub fn longest_name(names: &[String]) -> Option<&String> {
+    names.iter().max_by_key(|name| name.len())
+}
+
+// Mutably borrows the scores and changes them in place.
+pub fn add_bonus(scores: &mut [u32], bonus: u32) {
+    for score in scores.iter_mut() {
+        *score += bonus;
+    }
+}
+
+// Consumes the names, so it can hand the Strings on without cloning them.
+pub fn shout_all(names: Vec<String>) -> Vec<String> {
+    names
+        .into_iter()
+        .map(|mut name| {
+            name.make_ascii_uppercase();
+            name
+        })
+        .collect()
+}
+
+// Modifying while iterating: collect what to add first, then extend.
+pub fn append_doubles(numbers: &mut Vec<i32>) {
+    let doubles: Vec<i32> = numbers.iter().map(|n| n * 2).collect(); // The shared borrow ends here
+    numbers.extend(doubles);
+}
+
+pub(crate) fn examples() {
+    // 1) iter: shared borrows
+    println!(" --------------- lesson 11 example 1 ---------------");
+    {
+        let names = vec![String::from("ferris"), String::from("corro")];
+        for name in names.iter() {
+            // name is &String
+            println!("iter: {}", name);
+        }
+        for name in &names {
+            // The same thing, written as a for loop over &names
+            println!("&names: {}", name);
+        }
+        // for name in &names { name.push('!'); } // Uncommenting this line will cause a compilation error (E0596)
+        println!("longest: {:?}", longest_name(&names));
+        println!("names is still ours: {:?}", names);
+    }
+
+    // 2) iter_mut: mutable borrows
+    println!(" --------------- lesson 11 example 2 ---------------");
+    {
+        let mut scores = vec![10, 20, 30];
+        for score in scores.iter_mut() {
+            *score += 1; // score is &mut u32, so we write through it
+        }
+        for score in &mut scores {
+            *score *= 2; // The same thing, written as a for loop over &mut scores
+        }
+        add_bonus(&mut scores, 100);
+        println!("scores: {:?}", scores);
+    }
+
+    // 3) into_iter: moving the values out
+    println!(" --------------- lesson 11 example 3 ---------------");
+    {
+        let names = vec![String::from("ferris"), String::from("corro")];
+        // names is moved into shout_all
+        let shouted = shout_all(names);
+        // println!("{:?}", names); // Uncommenting this line will cause a compilation error (E0382)
+        for name in shouted {
+            // The same as shouted.into_iter(), name is a String we own
+            println!("owned: {}", name);
+        }
+        // println!("{:?}", shouted); // Uncommenting this line will cause a compilation error (E0382)
+    }
+
+    // 4) Borrowed items cannot outlive the collection
+    println!(" --------------- lesson 11 example 4 ---------------");
+    {
+        let names = vec![String::from("ferris"), String::from("corro")];
+        let short_names: Vec<&String> = names.iter().filter(|n| n.len() < 6).collect();
+        println!("short names borrow from names: {:?}", short_names);
+        // drop(names); // Uncommenting this line will cause a compilation error (E0505)
+        let owned_copies: Vec<String> = names.iter().filter(|n| n.len() < 6).cloned().collect();
+        drop(names); // cloned gave us our own Strings, so names may go
+        println!("owned copies outlive names: {:?}", owned_copies);
+    }
+
+    // 5) Modifying a collection while iterating it
+    println!(" --------------- lesson 11 example 5 ---------------");
+    {
+        let mut numbers = vec![1, 2, 3];
+        // for n in &numbers { numbers.push(*n * 2); } // Uncommenting this line will cause a compilation error (E0502)
+        // for n in numbers.iter_mut() { numbers.push(*n); } // Uncommenting this line will cause a compilation error (E0499)
+        append_doubles(&mut numbers); // Fix 1: collect first, then change
+        println!("append_doubles: {:?}", numbers);
+
+        for i in 0..numbers.len() {
+            // Fix 2: indexes are plain numbers, no borrow is held between iterations
+            if numbers[i] % 2 == 0 {
+                numbers.push(numbers[i] + 1);
+            }
+        }
+        println!("index loop: {:?}", numbers);
+
+        numbers.retain(|n| n % 3 != 0); // Fix 3: let the collection do it
+        println!("retain: {:?}", numbers);
+    }
+
+    // 6) itertools adaptors that only borrow the data
+    println!(" --------------- lesson 11 example 6 ---------------");
+    {
+        let words = [
+            String::from("own"),
+            String::from("borrow"),
+            String::from("borrow"),
+            String::from("move"),
+            String::from("drop"),
+        ];
+        // Every adaptor below starts from words.iter(), so the items are &String
+        println!("join: {}", words.iter().join(", "));
+        let pairs: Vec<(&String, &String)> = words.iter().tuple_windows().collect();
+        println!("tuple_windows: {:?}", pairs);
+        let unique: Vec<&String> = words.iter().unique().collect(); // Remembers &String, not copies
+        println!("unique: {:?}", unique);
+        let positions: Vec<usize> = words.iter().positions(|w| w == "borrow").collect();
+        println!("positions of borrow: {:?}", positions);
+        let counts = words.iter().counts(); // HashMap<&String, usize>, it borrows from words
+        let sorted_counts: Vec<(&&String, &usize)> = counts.iter().sorted().collect();
+        println!("counts: {:?}", sorted_counts);
+        println!("words is still ours: {}", words.len());
+    }
+
+    // 7) itertools adaptors that consume the data
+    println!(" --------------- lesson 11 example 7 ---------------");
+    {
+        let words = vec![
+            String::from("move"),
+            String::from("own"),
+            String::from("drop"),
+            String::from("borrow"),
+        ];
+        let lengths = vec![4, 3, 4, 6];
+        // sorted collects the items into a new Vec, here the Strings themselves
+        let sorted: Vec<String> = words.clone().into_iter().sorted().collect();
+        println!("sorted: {:?}", sorted);
+        // into_group_map moves every String into the map, words is gone afterwards
+        let by_length = lengths.into_iter().zip(words).into_group_map();
+        // println!("{:?}", words); // Uncommenting this line will cause a compilation error (E0382)
+        for (length, group) in by_length.iter().sorted() {
+            println!("length {}: {:?}", length, group);
+        }
+        // chunk_by borrows from the iterator it groups, so it must be stored before use
+        let readings = [1, 1, 2, 2, 2, 3];
+        let chunks = readings.iter().chunk_by(|r| **r);
+        for (key, group) in &chunks {
+            println!("chunk {}: {}", key, group.count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_11_push_while_iterating",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2, 3];
+    for n in &numbers {
+        numbers.push(*n * 2);
+    }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_11_push_while_iter_mut",
+            code: "E0499",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2, 3];
+    for n in numbers.iter_mut() {
+        numbers.push(*n);
+    }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_11_use_after_into_iter",
+            code: "E0382",
+            source: r#"
+pub fn demo() {
+    let names = vec![String::from("ferris")];
+    for name in names {
+        println!("{}", name);
+    }
+    println!("{:?}", names);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_11_write_through_iter",
+            code: "E0596",
+            source: r#"
+pub fn demo() {
+    let names = vec![String::from("ferris")];
+    for name in &names {
+        name.push('!');
+    }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_11_drop_while_items_borrowed",
+            code: "E0505",
+            source: r#"
+pub fn demo() {
+    let names = vec![String::from("ferris"), String::from("corro")];
+    let short_names: Vec<&String> = names.iter().filter(|n| n.len() < 6).collect();
+    drop(names);
+    println!("{:?}", short_names);
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_three_ways_to_iterate() {
+        let names = vec![String::from("ab"), String::from("abc")];
+        assert_eq!(Some(&names[1]), longest_name(&names));
+        let mut scores = vec![1, 2];
+        add_bonus(&mut scores, 10);
+        assert_eq!(vec![11, 12], scores);
+        assert_eq!(vec!["AB", "ABC"], shout_all(names));
+    }
+
+    #[test]
+    fn test_modify_after_collect() {
+        let mut numbers = vec![1, 2];
+        append_doubles(&mut numbers);
+        assert_eq!(vec![1, 2, 2, 4], numbers);
+    }
+
+    #[test]
+    fn test_itertools_borrow_or_consume() {
+        let words = vec![String::from("a"), String::from("b"), String::from("a")];
+        let unique: Vec<&String> = words.iter().unique().collect();
+        assert_eq!(2, unique.len());
+        assert_eq!(2, words.iter().counts()[&words[0]]);
+        let grouped = vec![1, 1, 2].into_iter().zip(words).into_group_map();
+        assert_eq!(vec!["a", "b"], grouped[&1]);
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
@@ -0,0 +1,246 @@
+/*****************************************************/
+/* Lesson 12: Slices and Borrowed Views              */
+/*****************************************************/
+
+// Lesson 3 example 9, unchanged: only a &String can be passed in.
+#[allow(clippy::ptr_arg)] // This is the signature we are about to improve
+pub fn print_data_string(data: &String) -> String {
+    format!("Data: {}", data)
+}
+
+// Refactored: any text can be passed in, a String, a literal or part of either.
+pub fn print_data(data: &str) -> String {
+    format!("Data: {}", data)
+}
+
+// Lesson 3 example 10: appending grows the buffer, so &mut String is the right type here.
+pub fn append_data(data: &mut String) {
+    data.push_str(", Rust!");
+}
+
+// Changing text in place without growing it only needs a &mut str.
+pub fn shout_in_place(data: &mut str) {
+    data.make_ascii_uppercase();
+}
+
+// &[i32] instead of &Vec<i32> accepts arrays, Vecs and parts of either.
+pub fn total(numbers: &[i32]) -> i32 {
+    numbers.iter().sum()
+}
+
+// Two mutable views into one slice, the borrow checker knows they cannot overlap.
+pub fn swap_halves(numbers: &mut [i32]) {
+    let middle = numbers.len() / 2;
+    let (left, right) = numbers.split_at_mut(middle);
+    for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+        std::mem::swap(l, r);
+    }
+}
+
+// Each chunk is its own &mut [i32], so each may be changed independently.
+pub fn normalize_chunks(readings: &mut [i32], chunk_size: usize) {
+    for chunk in readings.chunks_mut(chunk_size) {
+        let min = chunk.iter().copied().min().unwrap_or(0);
+        for reading in chunk.iter_mut() {
+            *reading -= min;
+        }
+    }
+}
+
+// Slice patterns take views of parts of a slice without indexing.
+pub fn describe(numbers: &[i32]) -> String {
+    match numbers {
+        [] => String::from("empty"),
+        [only] => format!("one: {}", only),
+        [first, .., last] => format!("first: {}, last: {}", first, last),
+    }
+}
+
+pub(crate) fn examples() {
+    // 1) &String vs &str
+    println!(" --------------- lesson 12 example 1 ---------------");
+    {
+        let data = String::from("Hello, Rust!");
+        println!("{}", print_data_string(&data));
+        // print_data_string("literal"); // Uncommenting this line will cause a compilation error (E0308)
+        println!("{}", print_data(&data)); // &String coerces to &str
+        println!("{}", print_data("literal")); // &'static str works as is
+        println!("{}", print_data(&data[..5])); // and so does part of a String
+    }
+
+    // 2) &mut String vs &mut str
+    println!(" --------------- lesson 12 example 2 ---------------");
+    {
+        let mut data = String::from("Hello");
+        append_data(&mut data); // Growing needs the String itself
+        shout_in_place(&mut data[..5]); // Changing bytes in place only needs a view
+        println!("data: {}", data);
+        // fn grow(s: &mut str) { s.push_str("!"); } // error[E0599]: &mut str cannot grow
+    }
+
+    // 3) &Vec<T> vs &[T]
+    println!(" --------------- lesson 12 example 3 ---------------");
+    {
+        let vector = vec![1, 2, 3, 4];
+        let array = [10, 20, 30];
+        println!("total of Vec: {}", total(&vector));
+        println!("total of array: {}", total(&array));
+        println!("total of part: {}", total(&vector[1..3]));
+        println!(
+            "describe: {}, {}, {}",
+            describe(&[]),
+            describe(&[7]),
+            describe(&vector)
+        );
+    }
+
+    // 4) str ranges must fall on character boundaries
+    println!(" --------------- lesson 12 example 4 ---------------");
+    {
+        let text = String::from("héllo"); // é takes two bytes in UTF-8
+        println!(
+            "len in bytes: {}, chars: {}",
+            text.len(),
+            text.chars().count()
+        );
+        println!("is_char_boundary(2): {}", text.is_char_boundary(2));
+        println!("get(0..2): {:?}", text.get(0..2)); // None instead of a panic
+        println!("get(0..3): {:?}", text.get(0..3));
+        // println!("{}", &text[0..2]); // Uncommenting this line will panic at runtime, byte 2 is inside 'é'
+    }
+
+    // 5) split_at_mut: two mutable views of one slice
+    println!(" --------------- lesson 12 example 5 ---------------");
+    {
+        let mut numbers = vec![1, 2, 3, 4, 5, 6];
+        // let a = &mut numbers[0];
+        // let b = &mut numbers[1]; // Uncommenting these lines will cause a compilation error (E0499)
+        // std::mem::swap(a, b);
+        let (left, right) = numbers.split_at_mut(1);
+        std::mem::swap(&mut left[0], &mut right[0]); // The compiler knows left and right do not overlap
+        println!("after swapping the first two: {:?}", numbers);
+        swap_halves(&mut numbers);
+        println!("after swap_halves: {:?}", numbers);
+    }
+
+    // 6) chunks_mut: many mutable views of one slice
+    println!(" --------------- lesson 12 example 6 ---------------");
+    {
+        let mut readings = vec![10, 12, 11, 50, 55, 52, 7];
+        normalize_chunks(&mut readings, 3);
+        println!("normalized per chunk of 3: {:?}", readings);
+        for (i, chunk) in readings.chunks_exact(3).enumerate() {
+            println!("chunk {}: {:?}", i, chunk);
+        }
+        println!("left over: {:?}", readings.chunks_exact(3).remainder());
+    }
+
+    // 7) Holding a slice across a push
+    println!(" --------------- lesson 12 example 7 ---------------");
+    {
+        let mut numbers = vec![1, 2, 3];
+        let first_two = &numbers[..2];
+        println!("first_two: {:?}", first_two);
+        // A push may move the Vec's buffer, which would leave first_two pointing at freed memory.
+        // It is allowed here only because the line above was the last use of the slice.
+        numbers.push(4);
+        // println!("first_two: {:?}", first_two); // Uncommenting this line will cause a compilation error (E0502)
+        let first_two = numbers[..2].to_vec(); // An owned copy is not tied to numbers
+        numbers.push(5);
+        println!("numbers: {:?}, copy: {:?}", numbers, first_two);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_12_string_param_rejects_literal",
+            code: "E0308",
+            source: r#"
+#[allow(clippy::ptr_arg)]
+pub fn print_data(data: &String) -> usize { data.len() }
+pub fn demo() -> usize {
+    print_data("literal")
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_12_mut_str_cannot_grow",
+            code: "E0599",
+            source: r#"
+pub fn grow(data: &mut str) {
+    data.push_str("!");
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_12_two_mut_indexes",
+            code: "E0499",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2, 3];
+    let a = &mut numbers[0];
+    let b = &mut numbers[1];
+    std::mem::swap(a, b);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_12_slice_across_push",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2, 3];
+    let first_two = &numbers[..2];
+    numbers.push(4);
+    println!("{:?}", first_two);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_12_str_slice_across_clear",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut text = String::from("hello");
+    let word = &text[..2];
+    text.clear();
+    println!("{}", word);
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_refactored_signatures() {
+        let data = String::from("Hello");
+        assert_eq!(print_data_string(&data), print_data(&data));
+        assert_eq!("Data: He", print_data(&data[..2]));
+        let mut data = data;
+        append_data(&mut data);
+        shout_in_place(&mut data);
+        assert_eq!("HELLO, RUST!", data);
+        assert_eq!(6, total(&[1, 2, 3]));
+        assert_eq!(5, total(&vec![1, 2, 3][1..]));
+    }
+
+    #[test]
+    fn test_mutable_views() {
+        let mut numbers = vec![1, 2, 3, 4, 5];
+        swap_halves(&mut numbers);
+        assert_eq!(vec![3, 4, 1, 2, 5], numbers);
+        let mut readings = vec![5, 6, 10, 12];
+        normalize_chunks(&mut readings, 2);
+        assert_eq!(vec![0, 1, 0, 2], readings);
+        assert_eq!("first: 3, last: 5", describe(&numbers));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
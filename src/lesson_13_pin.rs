@@ -0,0 +1,285 @@
+/*****************************************************/
+/* Lesson 13: Pin and Self-Referential Types         */
+/*****************************************************/
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::ops::Range;
+use std::pin::{pin, Pin};
+use std::ptr;
+
+// The safe alternative: remember where the first word is, not a reference to it.
+pub struct ParsedText {
+    text: String,
+    first_word: Range<usize>,
+}
+
+impl ParsedText {
+    pub fn new(text: &str) -> Self {
+        let end = text.find(' ').unwrap_or(text.len());
+        Self {
+            text: text.to_string(),
+            first_word: 0..end,
+        }
+    }
+
+    pub fn first_word(&self) -> &str {
+        &self.text[self.first_word.clone()]
+    }
+}
+
+// Points at its own field, but nothing stops it from being moved.
+pub struct UnpinnedTracker {
+    value: i32,
+    value_ptr: *const i32,
+}
+
+impl UnpinnedTracker {
+    pub fn new(value: i32) -> Self {
+        Self {
+            value,
+            value_ptr: ptr::null(),
+        }
+    }
+
+    pub fn init(&mut self) {
+        self.value_ptr = &self.value;
+    }
+
+    // Compares addresses only, following a stale pointer would be undefined behavior.
+    pub fn points_to_self(&self) -> bool {
+        ptr::eq(self.value_ptr, &self.value)
+    }
+}
+
+// Points at its own field, and PhantomPinned makes Pin hold it in place. The value sits in an
+// UnsafeCell so that writing it through a reference does not invalidate value_ptr.
+pub struct Tracker {
+    value: UnsafeCell<i32>,
+    value_ptr: *const i32,
+    _pinned: PhantomPinned,
+}
+
+impl Tracker {
+    // Not yet self-referential, so it may still be moved into place before pinning.
+    pub fn unset(value: i32) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            value_ptr: ptr::null(),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    pub fn new(value: i32) -> Pin<Box<Tracker>> {
+        let mut boxed = Box::pin(Tracker::unset(value));
+        boxed.as_mut().init();
+        boxed
+    }
+
+    pub fn init(self: Pin<&mut Self>) {
+        // SAFETY: we only write a field in place, the Tracker is never moved out of the Pin
+        let this = unsafe { self.get_unchecked_mut() };
+        this.value_ptr = this.value.get();
+    }
+
+    pub fn points_to_self(&self) -> bool {
+        ptr::eq(self.value_ptr, self.value.get())
+    }
+
+    pub fn value_through_pointer(self: Pin<&Self>) -> i32 {
+        assert!(
+            self.points_to_self(),
+            "Tracker is not initialized or was moved"
+        );
+        // SAFETY: the assertion shows value_ptr points at our own value, and Pin keeps it there
+        unsafe { *self.value_ptr }
+    }
+
+    pub fn set_value(self: Pin<&mut Self>, value: i32) {
+        // SAFETY: we hold the only &mut, so no other reference reads the value while we write it
+        unsafe { *self.value.get() = value };
+    }
+}
+
+pub(crate) fn examples() {
+    // 1) The borrow checker refuses self references
+    println!(" --------------- lesson 13 example 1 ---------------");
+    {
+        // struct SelfRef<'a> { text: String, first: &'a str }
+        // let text = String::from("hello world");
+        // let s = SelfRef { first: &text[..5], text }; // Uncommenting this line will cause a compilation error (E0505)
+        // text is borrowed by first, so it cannot also be moved into the struct
+        let parsed = ParsedText::new("hello world");
+        println!("first word by range: {}", parsed.first_word());
+        let moved = parsed; // Ranges are just numbers, moving is harmless
+        println!("after a move: {}", moved.first_word());
+    }
+
+    // 2) Moving leaves a self pointer behind
+    println!(" --------------- lesson 13 example 2 ---------------");
+    {
+        let mut tracker = UnpinnedTracker::new(42);
+        tracker.init();
+        println!(
+            "before the move, points to self: {}",
+            tracker.points_to_self()
+        );
+        let moved = Box::new(tracker); // The bytes are copied to the heap, value_ptr is not updated
+        println!("after the move, points to self: {}", moved.points_to_self());
+        // Reading *moved.value_ptr now would read a stack slot that no longer belongs to us
+    }
+
+    // 3) Pin<Box<T>>: a self-referential type that cannot move
+    println!(" --------------- lesson 13 example 3 ---------------");
+    {
+        let mut tracker = Tracker::new(7);
+        println!("points to self: {}", tracker.points_to_self());
+        println!(
+            "value through pointer: {}",
+            tracker.as_ref().value_through_pointer()
+        );
+        tracker.as_mut().set_value(8);
+        println!(
+            "after set_value: {}",
+            tracker.as_ref().value_through_pointer()
+        );
+        let moved_box = tracker; // Moving the Box moves the pointer, not the Tracker on the heap
+        println!("after moving the box: {}", moved_box.points_to_self());
+        // Taking a &mut Tracker back out would allow std::mem::replace or swap, so it is refused:
+        // Pin::get_mut(moved_box.as_mut()) is error[E0277] and swap(&mut *a, &mut *b) is error[E0596]
+    }
+
+    // 4) pin!: pinning on the stack
+    println!(" --------------- lesson 13 example 4 ---------------");
+    {
+        let mut tracker = pin!(Tracker::unset(99)); // Moved into place once, then pinned
+        tracker.as_mut().init();
+        println!("stack pinned, points to self: {}", tracker.points_to_self());
+        println!(
+            "value through pointer: {}",
+            tracker.as_ref().value_through_pointer()
+        );
+        // tracker can no longer be moved out, the pin! macro shadowed the original value
+    }
+
+    // 5) Unpin: Pin does nothing for ordinary types
+    println!(" --------------- lesson 13 example 5 ---------------");
+    {
+        let mut number = 5;
+        let mut pinned = Pin::new(&mut number); // Only allowed because i32: Unpin
+        *pinned = 6; // DerefMut is available for Unpin types
+        let unpinned: &mut i32 = Pin::get_mut(pinned); // and we may take the &mut back out
+        *unpinned += 1;
+        println!("number: {}", number);
+        // let mut tracker = Tracker::unset(1);
+        // Pin::new(&mut tracker); // Uncommenting this line will cause a compilation error (E0277)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+    use crate::miri;
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_13_self_reference_in_struct",
+            code: "E0505",
+            source: r#"
+pub struct SelfRef<'a> { pub text: String, pub first: &'a str }
+pub fn demo() {
+    let text = String::from("hello world");
+    let s = SelfRef { first: &text[..5], text };
+    println!("{}", s.first);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_13_return_self_reference",
+            code: "E0515",
+            source: r#"
+pub struct SelfRef<'a> { pub text: String, pub first: &'a str }
+pub fn make(text: String) -> SelfRef<'static> {
+    let first = text.split(' ').next().unwrap();
+    SelfRef { text, first }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_13_get_mut_needs_unpin",
+            code: "E0277",
+            source: r#"
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+pub struct Tracker { pub value: i32, _pinned: PhantomPinned }
+pub fn demo(mut tracker: Pin<Box<Tracker>>) {
+    let inner: &mut Tracker = Pin::get_mut(tracker.as_mut());
+    inner.value = 1;
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_13_swap_pinned",
+            code: "E0596",
+            source: r#"
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+pub struct Tracker { pub value: i32, _pinned: PhantomPinned }
+pub fn demo(mut a: Pin<Box<Tracker>>, mut b: Pin<Box<Tracker>>) {
+    std::mem::swap(&mut *a, &mut *b);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_13_pin_new_needs_unpin",
+            code: "E0277",
+            source: r#"
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+pub struct Tracker { pub value: i32, _pinned: PhantomPinned }
+pub fn demo(tracker: &mut Tracker) {
+    let _pinned = Pin::new(tracker);
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_unpinned_pointer_goes_stale() {
+        let mut tracker = UnpinnedTracker::new(1);
+        tracker.init();
+        assert!(tracker.points_to_self());
+        let moved = Box::new(tracker);
+        assert!(!moved.points_to_self());
+    }
+
+    #[test]
+    fn test_pinned_tracker() {
+        let mut tracker = Tracker::new(3);
+        assert_eq!(3, tracker.as_ref().value_through_pointer());
+        tracker.as_mut().set_value(4);
+        assert_eq!(4, tracker.as_ref().value_through_pointer());
+        let trackers = [tracker, Tracker::new(5)]; // Moving the boxes keeps the Trackers still
+        assert!(trackers.iter().all(|t| t.points_to_self()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Tracker is not initialized or was moved")]
+    fn test_uninitialized_tracker_is_caught() {
+        let tracker = pin!(Tracker::unset(1));
+        tracker.as_ref().value_through_pointer();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Miri cannot start rustc
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_lesson_passes_miri() {
+        miri::assert_tests_pass("lesson_13_pin::tests");
+    }
+}
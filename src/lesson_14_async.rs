@@ -0,0 +1,304 @@
+/*****************************************************/
+/* Lesson 14: Async and Borrowing Across .await      */
+/*****************************************************/
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+// Returns Pending once, so other tasks get a turn before this one continues.
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref(); // Ask to be polled again
+            Poll::Pending
+        }
+    }
+}
+
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+// Polls one future until it is done. It does not return before then, which is why the
+// future is allowed to borrow from the caller.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+// A round robin executor. Spawned tasks are stored until run is called, long after the
+// spawning function may have returned, which is why spawn demands 'static.
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: VecDeque<Pin<Box<dyn Future<Output = ()>>>>,
+}
+
+impl LocalExecutor {
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + 'static) {
+        self.tasks.push_back(Box::pin(task));
+    }
+
+    // Polls each task in turn until all are done, returning how many polls it took.
+    pub fn run(&mut self) -> usize {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut polls = 0;
+        while let Some(mut task) = self.tasks.pop_front() {
+            polls += 1;
+            if task.as_mut().poll(&mut cx).is_pending() {
+                self.tasks.push_back(task);
+            }
+        }
+        polls
+    }
+}
+
+// Borrows words from the caller for as long as the returned future lives.
+pub async fn total_len(words: &[String]) -> usize {
+    let mut total = 0;
+    for word in words {
+        total += word.len();
+        yield_now().await; // words is still borrowed while we are paused here
+    }
+    total
+}
+
+// Owns its data, so the future it returns is not tied to any caller.
+#[allow(clippy::manual_async_fn)] // Written out to show the async move block an async fn hides
+pub fn owned_total_len(words: Vec<String>) -> impl Future<Output = usize> {
+    async move { total_len(&words).await }
+}
+
+// Writes two lines with a pause between them, releasing the borrow before pausing.
+pub async fn log_twice(log: Rc<RefCell<Vec<String>>>, name: &'static str) {
+    log.borrow_mut().push(format!("{} first", name)); // The RefMut is dropped at the ;
+    yield_now().await;
+    log.borrow_mut().push(format!("{} second", name));
+}
+
+// The same, but the RefMut lives across the .await.
+#[allow(clippy::await_holding_refcell_ref)] // This is the mistake we are demonstrating
+pub async fn log_twice_holding(log: Rc<RefCell<Vec<String>>>, name: &'static str) {
+    let mut entries = log.borrow_mut();
+    entries.push(format!("{} first", name));
+    yield_now().await; // Paused while still holding the RefMut
+    entries.push(format!("{} second", name));
+}
+
+// Tries to write without panicking, and records whether it could.
+pub async fn try_log(log: Rc<RefCell<Vec<String>>>, name: &'static str) {
+    let outcome = match log.try_borrow_mut() {
+        Ok(mut entries) => {
+            entries.push(format!("{} wrote", name));
+            "wrote"
+        }
+        Err(_) => "found the log already borrowed",
+    };
+    println!("{}: {}", name, outcome);
+}
+
+pub(crate) fn examples() {
+    // 1) Futures are lazy
+    println!(" --------------- lesson 14 example 1 ---------------");
+    {
+        let future = async {
+            println!("inside the future");
+            42
+        };
+        println!("future created, nothing has run yet");
+        let answer = block_on(future);
+        println!("block_on returned: {}", answer);
+    }
+
+    // 2) Futures that borrow from the caller
+    println!(" --------------- lesson 14 example 2 ---------------");
+    {
+        let words = vec![String::from("borrow"), String::from("await")];
+        // The future holds &words until it is done
+        let future = total_len(&words);
+        // drop(words); // Uncommenting this line will cause a compilation error (E0505)
+        println!("total_len: {}", block_on(future));
+        println!("words is still ours: {:?}", words); // The borrow ended with the future
+    }
+
+    // 3) Returning futures that own their data
+    println!(" --------------- lesson 14 example 3 ---------------");
+    {
+        let future = {
+            let words = vec![String::from("owned"), String::from("future")];
+            owned_total_len(words) // words moved into the future
+        }; // Without async move, the future would borrow words past this point (E0373)
+        println!("owned_total_len: {}", block_on(future));
+    }
+
+    // 4) Spawned tasks must be 'static
+    println!(" --------------- lesson 14 example 4 ---------------");
+    {
+        let mut executor = LocalExecutor::default();
+        let text = String::from("spawned");
+        let shared = Rc::new(String::from("shared with Rc"));
+        // let borrowed = &text;
+        // executor.spawn(async move { println!("{}", borrowed); }); // Uncommenting this line will cause a compilation error (E0597)
+        executor.spawn(async move { println!("task owns: {}", text) }); // text moved in
+        let for_task = Rc::clone(&shared);
+        executor.spawn(async move { println!("task shares: {}", for_task) });
+        let polls = executor.run();
+        println!(
+            "tasks done after {} polls, main still has: {}",
+            polls, shared
+        );
+    }
+
+    // 5) Tasks take turns at each .await
+    println!(" --------------- lesson 14 example 5 ---------------");
+    {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut executor = LocalExecutor::default();
+        executor.spawn(log_twice(Rc::clone(&log), "a"));
+        executor.spawn(log_twice(Rc::clone(&log), "b"));
+        executor.run();
+        println!("log: {:?}", log.borrow());
+    }
+
+    // 6) Holding a RefCell borrow across .await
+    println!(" --------------- lesson 14 example 6 ---------------");
+    {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut executor = LocalExecutor::default();
+        executor.spawn(log_twice_holding(Rc::clone(&log), "holder"));
+        executor.spawn(try_log(Rc::clone(&log), "visitor")); // With borrow_mut this would panic
+        executor.run();
+        println!("log with the borrow held: {:?}", log.borrow());
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        executor.spawn(log_twice(Rc::clone(&log), "holder"));
+        executor.spawn(try_log(Rc::clone(&log), "visitor"));
+        executor.run();
+        println!("log with the borrow released: {:?}", log.borrow());
+    }
+
+    // 7) Holding non-Send values across .await
+    println!(" --------------- lesson 14 example 7 ---------------");
+    {
+        // Our executor runs everything on one thread, so Rc and RefCell are fine. Executors
+        // that move tasks between threads require Send + 'static futures. A future holding an
+        // Rc across an .await is not Send, because the Rc is stored inside the future:
+        // assert_send(async { let shared = Rc::new(1); yield_now().await; shared });
+        // error: future cannot be sent between threads safely (this error has no code)
+        let future = async {
+            let count = {
+                let shared = Rc::new(1); // Dropped before the .await, so not stored in the future
+                *shared
+            };
+            yield_now().await;
+            count
+        };
+        println!("Rc dropped before .await: {}", block_on(future));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_14_drop_while_future_borrows",
+            code: "E0505",
+            source: r#"
+async fn total_len(words: &[String]) -> usize {
+    words.iter().map(|w| w.len()).sum()
+}
+pub fn demo() {
+    let words = vec![String::from("borrow")];
+    let future = total_len(&words);
+    drop(words);
+    let _ = future;
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_14_returned_future_borrows_local",
+            code: "E0373",
+            source: r#"
+use std::future::Future;
+pub fn owned_total_len(words: Vec<String>) -> impl Future<Output = usize> {
+    async { words.len() }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_14_spawn_needs_static",
+            code: "E0597",
+            source: r#"
+use std::future::Future;
+use std::pin::Pin;
+#[derive(Default)]
+pub struct LocalExecutor { tasks: Vec<Pin<Box<dyn Future<Output = ()>>>> }
+impl LocalExecutor {
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + 'static) {
+        self.tasks.push(Box::pin(task));
+    }
+}
+pub fn demo(executor: &mut LocalExecutor) {
+    let text = String::from("spawned");
+    let borrowed = &text;
+    executor.spawn(async move { println!("{}", borrowed); });
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_block_on_borrows() {
+        let words = vec![String::from("ab"), String::from("cde")];
+        assert_eq!(5, block_on(total_len(&words)));
+        assert_eq!(5, block_on(owned_total_len(words)));
+    }
+
+    #[test]
+    fn test_executor_interleaves_tasks() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut executor = LocalExecutor::default();
+        executor.spawn(log_twice(Rc::clone(&log), "a"));
+        executor.spawn(log_twice(Rc::clone(&log), "b"));
+        assert_eq!(4, executor.run());
+        assert_eq!(
+            vec!["a first", "b first", "a second", "b second"],
+            *log.borrow()
+        );
+    }
+
+    #[test]
+    fn test_borrow_held_across_await() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut executor = LocalExecutor::default();
+        executor.spawn(log_twice_holding(Rc::clone(&log), "holder"));
+        executor.spawn(try_log(Rc::clone(&log), "visitor"));
+        executor.run();
+        assert_eq!(vec!["holder first", "holder second"], *log.borrow());
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
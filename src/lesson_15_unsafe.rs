@@ -0,0 +1,332 @@
+/*****************************************************/
+/* Lesson 15: Unsafe and Raw Pointers vs References  */
+/*****************************************************/
+
+use std::ptr;
+use std::slice;
+
+use crate::drop_tracer::{self, Traced};
+
+// Lesson 3 example 8 leaked its Box on purpose. from_raw takes ownership back, so it is freed.
+pub fn leak_and_reclaim(text: &str) -> String {
+    let leaked: &'static mut String = Box::leak(Box::new(text.to_string()));
+    leaked.push_str(" - Extended Lifetime");
+    // leaked is not used again after this line
+    let raw: *mut String = leaked;
+    // SAFETY: raw came from Box::leak, so from a Box, and no reference to it is used afterwards
+    let reclaimed = unsafe { Box::from_raw(raw) };
+    *reclaimed
+}
+
+// Moves a value to the heap and keeps only its address. Nothing will drop it for us.
+pub fn into_raw(value: Traced) -> *mut Traced {
+    Box::into_raw(Box::new(value))
+}
+
+/// # Safety
+/// ptr must come from into_raw and must not have been reclaimed already.
+pub unsafe fn reclaim(ptr: *mut Traced) -> Box<Traced> {
+    Box::from_raw(ptr)
+}
+
+// Raw pointers may alias, as long as no reference to the value is used in between.
+pub fn add_twice_through_raw(value: &mut i32) {
+    let first: *mut i32 = value;
+    let second = first;
+    // SAFETY: both pointers come from a live &mut, and value is not used until we return
+    unsafe {
+        *first += 1;
+        *second += 1;
+    }
+}
+
+// The same signature as slice::split_at_mut. The borrow checker cannot see that the two
+// halves do not overlap, so we check it ourselves and promise it with unsafe.
+pub fn split_at_mut_raw(values: &mut [i32], mid: usize) -> (&mut [i32], &mut [i32]) {
+    assert!(mid <= values.len(), "mid is out of bounds");
+    let len = values.len();
+    let ptr = values.as_mut_ptr();
+    // SAFETY: [0, mid) and [mid, len) lie inside values and do not overlap, and the returned
+    // slices borrow values, so it stays alive and untouched for as long as they do
+    unsafe {
+        (
+            slice::from_raw_parts_mut(ptr, mid),
+            slice::from_raw_parts_mut(ptr.add(mid), len - mid),
+        )
+    }
+}
+
+// Compiles, because raw pointers carry no lifetime. The value it points at is already gone.
+pub fn dangling() -> *const i32 {
+    let value = 5;
+    ptr::addr_of!(value)
+}
+
+pub(crate) fn examples() {
+    // 1) Creating raw pointers is safe, dereferencing them is not
+    println!(" --------------- lesson 15 example 1 ---------------");
+    {
+        let mut number = 10;
+        // A reference coerces to a raw pointer
+        let exclusive: *mut i32 = &mut number;
+        // SAFETY: exclusive points at number, which is alive and not borrowed elsewhere
+        unsafe { *exclusive += 1 };
+        // Taken after the write: a new &mut number would have invalidated it, even unused
+        let shared: *const i32 = &number;
+        let nothing: *const i32 = ptr::null();
+        // println!("{}", *shared); // Uncommenting this line will cause a compilation error (E0133)
+        // SAFETY: shared points at number, which is alive and only read
+        println!("read through shared: {}", unsafe { *shared });
+        println!("nothing is null: {}", nothing.is_null());
+        // SAFETY: as_ref returns None for null, otherwise the pointer must be valid, as shared is
+        let (some, none) = unsafe { (shared.as_ref(), nothing.as_ref()) };
+        println!("as_ref: {:?}, {:?}", some, none);
+    }
+
+    // 2) Box::into_raw and Box::from_raw: ownership leaves the type system and comes back
+    println!(" --------------- lesson 15 example 2 ---------------");
+    {
+        let ptr = into_raw(Traced::new("raw box"));
+        // SAFETY: ptr came from into_raw and has not been reclaimed
+        println!("reading through ptr: {}", unsafe { (*ptr).name() });
+        println!("dropped while raw: {}", drop_tracer::was_dropped("raw box"));
+        // SAFETY: ptr came from into_raw and this is the only time we reclaim it
+        let boxed = unsafe { reclaim(ptr) };
+        drop(boxed); // A Box again, so dropping it frees the value
+        println!(
+            "dropped after from_raw: {}",
+            drop_tracer::was_dropped("raw box")
+        );
+        // unsafe { reclaim(ptr) }; // Uncommenting this line frees the value twice, Miri reports Undefined Behavior
+    }
+
+    // 3) Lesson 3 example 8 revisited: reclaiming a leaked Box
+    println!(" --------------- lesson 15 example 3 ---------------");
+    {
+        let text = leak_and_reclaim("Hello");
+        println!("reclaimed: {}", text); // Owned again, so it is freed at the end of this scope
+    }
+
+    // 4) References may not alias, raw pointers may
+    println!(" --------------- lesson 15 example 4 ---------------");
+    {
+        let mut number = 1;
+        // let a = &mut number;
+        // let b = &mut number; // Uncommenting these lines will cause a compilation error (E0499)
+        // *a += 1;
+        add_twice_through_raw(&mut number);
+        println!("after add_twice_through_raw: {}", number);
+        // The borrow checker does not look inside unsafe blocks, so this compiles:
+        // let p: *mut i32 = &mut number;
+        // let (a, b) = unsafe { (&mut *p, &mut *p) }; // Two live &mut to one value
+        // *b += 1; *a += 1; // Uncommenting these lines is Undefined Behavior, reported by Miri
+    }
+
+    // 5) A safe function built on unsafe code
+    println!(" --------------- lesson 15 example 5 ---------------");
+    {
+        let mut numbers = [1, 2, 3, 4, 5];
+        let (left, right) = split_at_mut_raw(&mut numbers, 2);
+        left[0] = 10; // Two &mut into one array, both usable at once
+        right[0] = 30;
+        println!("left: {:?}, right: {:?}", left, right);
+        // numbers[1] = 0; println!("{:?}", left); // Uncommenting this line will cause a compilation error (E0506)
+        println!("numbers: {:?}", numbers); // The halves borrow numbers, as the signature says
+    }
+
+    // 6) What the borrow checker no longer catches
+    println!(" --------------- lesson 15 example 6 ---------------");
+    {
+        // fn dangling_ref() -> &'static i32 { let value = 5; &value } // error[E0515]
+        // The raw pointer version compiles. It is not null, and still not valid
+        let ptr = dangling();
+        println!("dangling is null: {}", ptr.is_null());
+        // println!("{}", unsafe { *ptr }); // Uncommenting this line is Undefined Behavior, reported by Miri
+
+        let value = 1;
+        let shared = &value;
+        // Allowed by the compiler
+        let _writable = shared as *const i32 as *mut i32;
+        // unsafe { *_writable = 2 }; // Uncommenting this line is Undefined Behavior, shared promised no writes
+        println!("value: {}", value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+    use crate::miri::{self, UndefinedBehavior};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_15_deref_outside_unsafe",
+            code: "E0133",
+            source: r#"
+pub fn demo() {
+    let number = 10;
+    let shared: *const i32 = &number;
+    println!("{}", *shared);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_15_two_mut_references",
+            code: "E0499",
+            source: r#"
+pub fn demo() {
+    let mut number = 1;
+    let a = &mut number;
+    let b = &mut number;
+    *b += 1;
+    *a += 1;
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_15_write_while_split",
+            code: "E0506",
+            source: r#"
+pub fn demo() {
+    let mut numbers = [1, 2, 3, 4, 5];
+    let (left, _right) = numbers.split_at_mut(2);
+    numbers[1] = 0;
+    println!("{:?}", left);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_15_dangling_reference",
+            code: "E0515",
+            source: r#"
+pub fn dangling_ref() -> &'static i32 {
+    let value = 5;
+    &value
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_15_call_unsafe_fn",
+            code: "E0133",
+            source: r#"
+pub unsafe fn reclaim(ptr: *mut String) -> Box<String> { Box::from_raw(ptr) }
+pub fn demo() -> Box<String> {
+    let ptr = Box::into_raw(Box::new(String::new()));
+    reclaim(ptr)
+}
+"#,
+        },
+    ];
+
+    // The commented-out lines of examples 2, 4 and 6, which compile but are not sound.
+    const UNDEFINED_BEHAVIOR: &[UndefinedBehavior] = &[
+        UndefinedBehavior {
+            id: "lesson_15_double_free",
+            source: r#"
+fn main() {
+    let ptr = Box::into_raw(Box::new(String::from("raw box")));
+    unsafe {
+        drop(Box::from_raw(ptr));
+        drop(Box::from_raw(ptr));
+    }
+}
+"#,
+        },
+        UndefinedBehavior {
+            id: "lesson_15_aliased_mut_references",
+            source: r#"
+fn main() {
+    let mut number = 1;
+    let p: *mut i32 = &mut number;
+    let (a, b) = unsafe { (&mut *p, &mut *p) };
+    *b += 1;
+    *a += 1;
+    println!("{}", number);
+}
+"#,
+        },
+        UndefinedBehavior {
+            id: "lesson_15_dangling_deref",
+            source: r#"
+fn dangling() -> *const i32 {
+    let value = 5;
+    std::ptr::addr_of!(value)
+}
+fn main() {
+    let ptr = dangling();
+    println!("{}", unsafe { *ptr });
+}
+"#,
+        },
+        UndefinedBehavior {
+            id: "lesson_15_write_through_shared",
+            source: r#"
+fn main() {
+    let value = 1;
+    let shared = &value;
+    let writable = shared as *const i32 as *mut i32;
+    unsafe { *writable = 2 };
+    println!("{}", value);
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_into_raw_and_reclaim() {
+        let ptr = into_raw(Traced::new("test raw box"));
+        assert!(!drop_tracer::was_dropped("test raw box"));
+        // SAFETY: ptr came from into_raw and is reclaimed once
+        let boxed = unsafe { reclaim(ptr) };
+        assert_eq!("test raw box", boxed.name());
+        drop(boxed);
+        assert!(drop_tracer::was_dropped("test raw box"));
+    }
+
+    #[test]
+    fn test_leak_and_reclaim() {
+        assert_eq!("Hi - Extended Lifetime", leak_and_reclaim("Hi"));
+    }
+
+    #[test]
+    fn test_raw_pointers_may_alias() {
+        let mut number = 1;
+        add_twice_through_raw(&mut number);
+        assert_eq!(3, number);
+    }
+
+    #[test]
+    fn test_split_at_mut_raw() {
+        let mut numbers = [1, 2, 3];
+        let (left, right) = split_at_mut_raw(&mut numbers, 1);
+        std::mem::swap(&mut left[0], &mut right[1]);
+        assert_eq!([3, 2, 1], numbers);
+        let (left, right) = split_at_mut_raw(&mut numbers, 3);
+        assert_eq!((3, 0), (left.len(), right.len()));
+        assert_eq!(numbers.split_at_mut(1), split_at_mut_raw(&mut [3, 2, 1], 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "mid is out of bounds")]
+    fn test_split_at_mut_raw_checks_bounds() {
+        split_at_mut_raw(&mut [1, 2], 3);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Miri cannot start rustc
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_miri_reports_undefined_behavior() {
+        miri::assert_all_undefined(UNDEFINED_BEHAVIOR);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_lesson_passes_miri() {
+        miri::assert_tests_pass("lesson_15_unsafe::tests");
+    }
+}
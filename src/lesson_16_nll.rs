@@ -0,0 +1,343 @@
+/*****************************************************/
+/* Lesson 16: Non-Lexical Lifetimes and Two-Phase    */
+/*            Borrows                                */
+/*****************************************************/
+
+use std::collections::HashMap;
+
+// A reference with a Drop impl is used again when it is dropped, at the end of its scope.
+pub struct Guard<'a> {
+    pub text: &'a String,
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        println!("Dropping Guard of: {}", self.text);
+    }
+}
+
+// Compiles today: the borrow from get_mut is not live in the None arm.
+pub fn bump(counts: &mut HashMap<String, u32>, word: &str) {
+    match counts.get_mut(word) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(word.to_string(), 1);
+        }
+    }
+}
+
+// The same with the entry API: one lookup, but entry wants an owned key every call.
+pub fn bump_entry(counts: &mut HashMap<String, u32>, word: &str) {
+    *counts.entry(word.to_string()).or_insert(0) += 1;
+}
+
+// Problem case #3 worked around: look twice, so no borrow is returned from the first look.
+#[allow(clippy::map_entry)] // The double lookup is the workaround we are showing
+pub fn name_or_insert_twice(names: &mut HashMap<u32, String>, id: u32) -> &String {
+    if !names.contains_key(&id) {
+        names.insert(id, format!("user {}", id));
+    }
+    &names[&id]
+}
+
+// Problem case #3 solved: entry returns the place, whether it was there or not.
+pub fn name_or_insert(names: &mut HashMap<u32, String>, id: u32) -> &String {
+    names.entry(id).or_insert_with(|| format!("user {}", id))
+}
+
+// A cache that hands out borrows of what it computed, as a method.
+#[derive(Default)]
+pub struct SquareCache {
+    squares: HashMap<u64, String>,
+    pub misses: u32,
+}
+
+impl SquareCache {
+    pub fn get(&mut self, n: u64) -> &str {
+        let misses = &mut self.misses; // A disjoint borrow, squares stays free
+        self.squares.entry(n).or_insert_with(|| {
+            *misses += 1;
+            (n * n).to_string()
+        })
+    }
+}
+
+pub(crate) fn examples() {
+    // 1) Borrows end at their last use
+    println!(" --------------- lesson 16 example 1 ---------------");
+    {
+        let mut data = String::from("Hello");
+        let reference = &data;
+        println!("reference: {}", reference); // Last use of reference, the borrow ends here
+        data.push_str(", NLL"); // Rejected before Rust 2018 (E0502), reference was still in scope
+        println!("data: {}", data);
+        println!("compiles today: yes, since NLL in Rust 2018");
+    }
+
+    // 2) Unless the reference is used again by a Drop impl
+    println!(" --------------- lesson 16 example 2 ---------------");
+    {
+        let mut data = String::from("guarded");
+        {
+            let guard = Guard { text: &data };
+            // Looks like the last use, but drop uses guard.text too
+            println!("guard: {}", guard.text);
+            // data.push('!'); // Uncommenting this line will cause a compilation error (E0502)
+        } // guard is dropped here, so the borrow lasts until here
+        data.push('!');
+        println!("data: {}", data);
+        println!("push while the Guard is in scope compiles today: no");
+    }
+
+    // 3) Two-phase borrows: v.push(v.len())
+    println!(" --------------- lesson 16 example 3 ---------------");
+    {
+        let mut numbers = vec![1, 2];
+        numbers.push(numbers.len()); // &mut numbers is reserved, numbers.len() reads, then push runs
+        println!("numbers: {:?}", numbers);
+        println!("method call compiles today: yes, since two-phase borrows in Rust 2018");
+        // Two-phase borrows only apply to autoref method calls and a few operators:
+        // Vec::push(&mut numbers, numbers.len()); // Uncommenting this line will cause a compilation error (E0502)
+        // let r = &mut numbers; r.push(numbers.len()); // Uncommenting this line will cause a compilation error (E0502)
+        // let mut rows = vec![vec![1]]; rows[0].push(rows.len()); // Uncommenting this line will cause a compilation error (E0502)
+        // numbers.push(numbers.pop().unwrap()); // Uncommenting this line will cause a compilation error (E0499)
+        let len = numbers.len(); // Fix: read first, into a local
+        Vec::push(&mut numbers, len);
+        println!("explicit call with the length read first: {:?}", numbers);
+        println!("explicit &mut argument compiles today: no");
+    }
+
+    // 4) Mutating in the branch where the borrow is not live
+    println!(" --------------- lesson 16 example 4 ---------------");
+    {
+        let mut counts = HashMap::new();
+        for word in ["own", "borrow", "own"] {
+            bump(&mut counts, word); // get_mut in one arm, insert in the other
+        }
+        let mut entry_counts = HashMap::new();
+        for word in ["own", "borrow", "own"] {
+            bump_entry(&mut entry_counts, word);
+        }
+        println!(
+            "match get_mut: own={}, borrow={}",
+            counts["own"], counts["borrow"]
+        );
+        println!("entry: equal={}", counts == entry_counts);
+        println!("compiles today: yes, since NLL in Rust 2018 (E0499 before)");
+    }
+
+    // 5) NLL problem case #3: returning a borrow from one branch
+    println!(" --------------- lesson 16 example 5 ---------------");
+    {
+        // fn name_or_insert(names: &mut HashMap<u32, String>, id: u32) -> &String {
+        //     if let Some(name) = names.get(&id) { return name; }
+        //     names.insert(id, format!("user {}", id)); // Uncommenting this function will cause a compilation error (E0502)
+        //     names.get(&id).unwrap()
+        // }
+        // The returned borrow must last as long as the function's output, so the checker
+        // treats names as borrowed on every path, even the one where nothing was returned.
+        println!("returning from one branch compiles today: no, it needs Polonius (nightly)");
+        let mut names = HashMap::from([(1, String::from("ferris"))]);
+        println!("two lookups: {}", name_or_insert_twice(&mut names, 1));
+        println!("two lookups: {}", name_or_insert_twice(&mut names, 2));
+        println!("entry: {}", name_or_insert(&mut names, 3));
+        println!("names known: {}", names.len());
+    }
+
+    // 6) get_or_insert patterns
+    println!(" --------------- lesson 16 example 6 ---------------");
+    {
+        let mut cache = SquareCache::default();
+        println!("square of 12: {}", cache.get(12));
+        println!("square of 12: {}", cache.get(12));
+        println!("misses: {}", cache.misses);
+        // let a = cache.get(3); let b = cache.get(4); println!("{} {}", a, b); // Uncommenting this line will cause a compilation error (E0499)
+        let a = cache.get(3).to_string(); // Copy out what we need to keep
+        let b = cache.get(4);
+        println!("a: {}, b: {}", a, b);
+
+        let mut label: Option<String> = None;
+        let first = label
+            .get_or_insert_with(|| String::from("computed once"))
+            .clone();
+        let again = label.get_or_insert_with(|| String::from("never computed"));
+        println!("Option::get_or_insert_with: {}, {}", first, again);
+        println!("compiles today: yes");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{
+        assert_all_accepted_in, assert_all_rejected, CompileFail, CompilePass,
+    };
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_16_drop_extends_borrow",
+            code: "E0502",
+            source: r#"
+pub struct Guard<'a> { pub text: &'a String }
+impl Drop for Guard<'_> { fn drop(&mut self) {} }
+pub fn demo() {
+    let mut data = String::from("guarded");
+    let guard = Guard { text: &data };
+    println!("{}", guard.text);
+    data.push('!');
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_explicit_mut_argument",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2];
+    Vec::push(&mut numbers, numbers.len());
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_reference_then_method",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2];
+    let r = &mut numbers;
+    r.push(numbers.len());
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_index_mut_not_two_phase",
+            code: "E0502",
+            source: r#"
+pub fn demo() {
+    let mut rows = vec![vec![1]];
+    rows[0].push(rows.len());
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_two_phase_needs_shared_argument",
+            code: "E0499",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2];
+    numbers.push(numbers.pop().unwrap());
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_problem_case_3",
+            code: "E0502",
+            source: r#"
+use std::collections::HashMap;
+pub fn name_or_insert(names: &mut HashMap<u32, String>, id: u32) -> &String {
+    if let Some(name) = names.get(&id) { return name; }
+    names.insert(id, format!("user {}", id));
+    names.get(&id).unwrap()
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_16_two_cache_borrows",
+            code: "E0499",
+            source: r#"
+use std::collections::HashMap;
+pub struct Cache { squares: HashMap<u64, String> }
+impl Cache {
+    pub fn get(&mut self, n: u64) -> &str {
+        self.squares.entry(n).or_insert_with(|| (n * n).to_string())
+    }
+}
+pub fn demo(cache: &mut Cache) {
+    let a = cache.get(3);
+    let b = cache.get(4);
+    println!("{} {}", a, b);
+}
+"#,
+        },
+    ];
+
+    // The examples that print "compiles today: yes". NLL replaced the old borrow checker for
+    // Rust 2015 code too, so they compile in every edition.
+    const COMPILE_PASS: &[CompilePass] = &[
+        CompilePass {
+            id: "lesson_16_borrow_ends_at_last_use",
+            source: r#"
+pub fn demo() {
+    let mut data = String::from("Hello");
+    let reference = &data;
+    println!("{}", reference);
+    data.push_str(", NLL");
+}
+"#,
+        },
+        CompilePass {
+            id: "lesson_16_two_phase_push",
+            source: r#"
+pub fn demo() {
+    let mut numbers = vec![1, 2];
+    numbers.push(numbers.len());
+}
+"#,
+        },
+        CompilePass {
+            id: "lesson_16_get_mut_or_insert",
+            source: r#"
+use std::collections::HashMap;
+pub fn bump(counts: &mut HashMap<String, u32>, word: &str) {
+    match counts.get_mut(word) {
+        Some(count) => *count += 1,
+        None => { counts.insert(word.to_string(), 1); }
+    }
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_bump_variants_agree() {
+        let mut counts = HashMap::new();
+        let mut entry_counts = HashMap::new();
+        for word in ["a", "b", "a"] {
+            bump(&mut counts, word);
+            bump_entry(&mut entry_counts, word);
+        }
+        assert_eq!(counts, entry_counts);
+        assert_eq!(2, counts["a"]);
+    }
+
+    #[test]
+    fn test_name_or_insert() {
+        let mut names = HashMap::from([(1, String::from("ferris"))]);
+        assert_eq!("ferris", name_or_insert_twice(&mut names, 1));
+        assert_eq!("user 2", name_or_insert_twice(&mut names, 2));
+        assert_eq!("ferris", name_or_insert(&mut names, 1));
+        assert_eq!("user 3", name_or_insert(&mut names, 3));
+        assert_eq!(3, names.len());
+    }
+
+    #[test]
+    fn test_square_cache_computes_once() {
+        let mut cache = SquareCache::default();
+        assert_eq!("9", cache.get(3));
+        assert_eq!("9", cache.get(3));
+        assert_eq!("16", cache.get(4));
+        assert_eq!(2, cache.misses);
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+
+    #[test]
+    fn test_compile_pass_in_every_edition() {
+        for edition in ["2015", "2018", "2021"] {
+            assert_all_accepted_in(edition, COMPILE_PASS);
+        }
+    }
+}
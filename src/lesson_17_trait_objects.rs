@@ -0,0 +1,398 @@
+/*****************************************************/
+/* Lesson 17: Trait Objects and Generics             */
+/*****************************************************/
+
+use std::mem::size_of;
+
+use crate::teaching_types::{
+    LargeStruct, MyCloneableStruct, MyCopyableStruct, MyRefStruct, MyStruct, Point, SimpleStruct,
+};
+
+// The one trait the whole zoo implements.
+pub trait Describe {
+    fn describe(&self) -> String;
+
+    // How many bytes of data the value holds, the work our benchmarks measure.
+    fn size(&self) -> usize;
+}
+
+impl Describe for SimpleStruct {
+    fn describe(&self) -> String {
+        format!("SimpleStruct({})", self.data)
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl Describe for MyStruct {
+    fn describe(&self) -> String {
+        format!("MyStruct({})", self.data)
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl Describe for MyCopyableStruct {
+    fn describe(&self) -> String {
+        format!("MyCopyableStruct({})", self.data)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>()
+    }
+}
+
+impl Describe for MyCloneableStruct {
+    fn describe(&self) -> String {
+        format!("MyCloneableStruct({})", self.data)
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl Describe for Point {
+    fn describe(&self) -> String {
+        format!("Point({}, {})", self.x, self.y)
+    }
+
+    fn size(&self) -> usize {
+        2 * size_of::<i32>()
+    }
+}
+
+impl Describe for LargeStruct {
+    fn describe(&self) -> String {
+        format!("LargeStruct({} items)", self.data.len())
+    }
+
+    fn size(&self) -> usize {
+        self.data.len() * size_of::<i32>()
+    }
+}
+
+// MyRefStruct borrows its data, so trait objects holding it carry a lifetime.
+impl Describe for MyRefStruct<'_> {
+    fn describe(&self) -> String {
+        format!("MyRefStruct({})", self.data)
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+// Generic: one copy per T, every item must be the same T.
+pub fn total_size<T: Describe>(items: &[T]) -> usize {
+    items.iter().map(|item| item.size()).sum()
+}
+
+// impl Trait in argument position is a generic with a shorter spelling.
+pub fn shout(item: impl Describe) -> String {
+    item.describe().to_uppercase()
+}
+
+// impl Trait in return position: one hidden type, chosen here.
+pub fn origin() -> impl Describe {
+    Point { x: 0, y: 0 }
+}
+
+// Box<dyn Trait>: a different type in every branch, each one owned by its box.
+pub fn make(kind: &str) -> Box<dyn Describe> {
+    match kind {
+        "point" => Box::new(Point { x: 1, y: 2 }),
+        "copy" => Box::new(MyCopyableStruct { data: 7 }),
+        _ => Box::new(SimpleStruct {
+            data: kind.to_string(),
+        }),
+    }
+}
+
+// Dynamic dispatch over owned trait objects.
+pub fn total_size_boxed(items: &[Box<dyn Describe>]) -> usize {
+    items.iter().map(|item| item.size()).sum()
+}
+
+// Dynamic dispatch over borrowed trait objects, the caller keeps ownership.
+pub fn total_size_dyn(items: &[&dyn Describe]) -> usize {
+    items.iter().map(|item| item.size()).sum()
+}
+
+// Box<dyn Describe> alone would mean + 'static and refuse MyRefStruct<'a>.
+pub fn describe_boxed<'a>(item: Box<dyn Describe + 'a>) -> String {
+    item.describe()
+}
+
+// Returning Self needs to know Self's size, so this method opts out of trait objects.
+pub trait Duplicate {
+    fn name(&self) -> String;
+
+    fn duplicate(&self) -> Self
+    where
+        Self: Sized;
+}
+
+impl Duplicate for MyCloneableStruct {
+    fn name(&self) -> String {
+        self.data.clone()
+    }
+
+    fn duplicate(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Duplicate for Point {
+    fn name(&self) -> String {
+        format!("({}, {})", self.x, self.y)
+    }
+
+    fn duplicate(&self) -> Self {
+        *self
+    }
+}
+
+pub(crate) fn examples() {
+    // 1) One trait, six structs
+    println!(" --------------- lesson 17 example 1 ---------------");
+    {
+        let simple = SimpleStruct {
+            data: String::from("simple"),
+        };
+        let cloneable = MyCloneableStruct {
+            data: String::from("cloneable"),
+        };
+        let large = LargeStruct {
+            data: vec![0; 1000],
+        };
+        println!("{}", simple.describe());
+        println!("{}", MyCopyableStruct { data: 42 }.describe());
+        println!("{}", cloneable.describe());
+        println!("{}", Point { x: 1, y: 2 }.describe());
+        println!("{}", large.describe());
+        let my_struct = MyStruct {
+            data: String::from("droppable"),
+        };
+        println!("{}", my_struct.describe());
+    } // my_struct is dropped here, Drop still runs as usual
+
+    // 2) Generics: static dispatch, one type at a time
+    println!(" --------------- lesson 17 example 2 ---------------");
+    {
+        let points = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+        let numbers = [MyCopyableStruct { data: 1 }];
+        println!("total_size::<Point>: {}", total_size(&points));
+        println!("total_size::<MyCopyableStruct>: {}", total_size(&numbers));
+        // total_size(&[Point { x: 1, y: 2 }, MyCopyableStruct { data: 1 }]); // Uncommenting this line will cause a compilation error (E0308)
+    }
+
+    // 3) impl Trait: a generic argument or a single hidden return type
+    println!(" --------------- lesson 17 example 3 ---------------");
+    {
+        println!("shout: {}", shout(Point { x: 5, y: 6 }));
+        println!(
+            "shout: {}",
+            shout(MyCloneableStruct {
+                data: String::from("any type")
+            })
+        );
+        println!("origin: {}", origin().describe());
+        // fn pick(up: bool) -> impl Describe { if up { Point { x: 0, y: 0 } } else { MyCopyableStruct { data: 0 } } }
+        // Uncommenting the function above will cause a compilation error (E0308), impl Trait is one type
+    }
+
+    // 4) Box<dyn Trait>: different types in one Vec, owned
+    println!(" --------------- lesson 17 example 4 ---------------");
+    {
+        let zoo: Vec<Box<dyn Describe>> = vec![
+            make("point"),
+            make("copy"),
+            make("simple"),
+            Box::new(MyStruct {
+                data: String::from("boxed"),
+            }),
+            Box::new(LargeStruct { data: vec![0; 10] }),
+        ];
+        for animal in &zoo {
+            println!("{}", animal.describe()); // Looked up in each value's vtable
+        }
+        println!("total_size_boxed: {}", total_size_boxed(&zoo));
+        println!(
+            "pointer sizes in words, &Point: {}, &dyn Describe: {}, Box<dyn Describe>: {}",
+            size_of::<&Point>() / size_of::<usize>(),
+            size_of::<&dyn Describe>() / size_of::<usize>(),
+            size_of::<Box<dyn Describe>>() / size_of::<usize>()
+        );
+    } // The Vec owns the boxes, so the MyStruct inside is dropped with it
+
+    // 5) &dyn Trait: different types in one slice, borrowed
+    println!(" --------------- lesson 17 example 5 ---------------");
+    {
+        let point = Point { x: 1, y: 2 };
+        let cloneable = MyCloneableStruct {
+            data: String::from("borrowed"),
+        };
+        let large = LargeStruct { data: vec![1, 2] };
+        let borrowed: [&dyn Describe; 3] = [&point, &cloneable, &large]; // No boxes, no moves
+        println!("total_size_dyn: {}", total_size_dyn(&borrowed));
+        println!("still ours: {}", cloneable.data);
+        // drop(cloneable); println!("{}", borrowed[1].describe()); // Uncommenting this line will cause a compilation error (E0505)
+    }
+
+    // 6) dyn Trait + 'a: trait objects that borrow
+    println!(" --------------- lesson 17 example 6 ---------------");
+    {
+        let text = String::from("borrowed text");
+        let item = MyRefStruct { data: &text };
+        println!("describe_boxed: {}", describe_boxed(Box::new(item)));
+        // fn describe_static(item: Box<dyn Describe>) -> String { item.describe() }
+        // describe_static(Box::new(MyRefStruct { data: &text })); // Uncommenting this line will cause a compilation error (E0597)
+        // '_ lets the compiler infer the lifetime, and 'static types such as Point fit any + 'a
+        let shelf: Vec<Box<dyn Describe + '_>> = vec![
+            Box::new(MyRefStruct { data: &text }),
+            Box::new(Point { x: 0, y: 1 }),
+        ];
+        println!("shelf: {}", shelf.len());
+    }
+
+    // 7) Dyn compatibility (object safety)
+    println!(" --------------- lesson 17 example 7 ---------------");
+    {
+        let items: [&dyn Duplicate; 2] = [
+            &Point { x: 1, y: 2 },
+            &MyCloneableStruct {
+                data: String::from("cloneable"),
+            },
+        ];
+        for item in items {
+            println!("name through &dyn Duplicate: {}", item.name());
+            // item.duplicate(); // Not callable on a trait object, the where Self: Sized opted it out
+        }
+        let copy = Point { x: 3, y: 4 }.duplicate(); // Fine on a concrete type
+        println!("duplicate: {}", copy.describe());
+        // Without where Self: Sized, or with a generic method, &dyn Duplicate is error[E0038]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, with_teaching_types, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_17_generic_needs_one_type",
+            code: "E0308",
+            source: with_teaching_types!(
+                lesson_17_trait_objects,
+                r#"
+use lesson_17_trait_objects::*;
+pub fn demo() -> usize {
+    total_size(&[Point { x: 1, y: 2 }, MyCopyableStruct { data: 1 }])
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "lesson_17_impl_trait_is_one_type",
+            code: "E0308",
+            source: with_teaching_types!(
+                lesson_17_trait_objects,
+                r#"
+use lesson_17_trait_objects::*;
+pub fn pick(up: bool) -> impl Describe {
+    if up { Point { x: 0, y: 0 } } else { MyCopyableStruct { data: 0 } }
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "lesson_17_drop_while_dyn_borrowed",
+            code: "E0505",
+            source: with_teaching_types!(
+                lesson_17_trait_objects,
+                r#"
+use lesson_17_trait_objects::*;
+pub fn demo() {
+    let cloneable = MyCloneableStruct { data: String::from("borrowed") };
+    let borrowed: [&dyn Describe; 1] = [&cloneable];
+    drop(cloneable);
+    println!("{}", borrowed[0].describe());
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "lesson_17_box_dyn_defaults_to_static",
+            code: "E0597",
+            source: with_teaching_types!(
+                lesson_17_trait_objects,
+                r#"
+use lesson_17_trait_objects::*;
+pub fn describe_static(item: Box<dyn Describe>) -> String { item.describe() }
+pub fn demo() -> String {
+    let text = String::from("borrowed text");
+    describe_static(Box::new(MyRefStruct { data: &text }))
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "lesson_17_returning_self_not_dyn_compatible",
+            code: "E0038",
+            source: r#"
+pub trait Duplicate { fn duplicate(&self) -> Self; }
+pub fn demo(_item: &dyn Duplicate) {}
+"#,
+        },
+        CompileFail {
+            id: "lesson_17_generic_method_not_dyn_compatible",
+            code: "E0038",
+            source: r#"
+pub trait Visit { fn visit<T>(&self, value: T); }
+pub fn demo(_item: Box<dyn Visit>) {}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_static_and_dynamic_dispatch_agree() {
+        let points = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+        let boxed: Vec<Box<dyn Describe>> = points.iter().map(|p| make_boxed(*p)).collect();
+        let borrowed: Vec<&dyn Describe> = points.iter().map(|p| p as &dyn Describe).collect();
+        assert_eq!(total_size(&points), total_size_boxed(&boxed));
+        assert_eq!(total_size(&points), total_size_dyn(&borrowed));
+    }
+
+    fn make_boxed(point: Point) -> Box<dyn Describe> {
+        Box::new(point)
+    }
+
+    #[test]
+    fn test_trait_objects_are_fat_pointers() {
+        assert_eq!(2 * size_of::<usize>(), size_of::<&dyn Describe>());
+        assert_eq!(2 * size_of::<usize>(), size_of::<Box<dyn Describe>>());
+        assert_eq!(size_of::<usize>(), size_of::<&Point>());
+    }
+
+    #[test]
+    fn test_boxed_trait_object_may_borrow() {
+        let text = String::from("abc");
+        assert_eq!(
+            "MyRefStruct(abc)",
+            describe_boxed(Box::new(MyRefStruct { data: &text }))
+        );
+        assert_eq!("SimpleStruct(other)", make("other").describe());
+        assert_eq!("POINT(0, 0)", shout(origin()));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
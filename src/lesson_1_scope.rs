@@ -2,37 +2,10 @@
 /* Lesson 1: Ownership and Dropping - It's All About Scope */
 /***********************************************************/
 
-/// Rust fundamentally emphasizes scope management, moving beyond traditional
-/// object-oriented (OO) or functional paradigms, though it supports both. The real
-/// power of Rust comes from understanding and leveraging its ownership model, the
-/// concept of borrowing, and the lifetimes of variables. By focusing on when resources
-/// are dropped, who owns what, and the scope of lifetimes, you can unlock
-/// the full potential of Rust. Using scopes is how Rust ensures memory safety guarantees.
-
-/********************/
-/*   Vocabulary     */
-/********************/
-
-/// Own, Owned, Ownership: The concept of a scope owning a resource and being responsible
-///                        for cleaning it up. The location of the variable holding the resource
-///                        up to its last use determines the scope.
-/// Lifetime:              A scope of time during which a variable is valid.
-/// From:                  A method which allows for type conversion from one type to another
-///                        by consuming the original variable. Once converted, the original
-///                        is no longer available.
-/// Into:                  A reciprocal of From, used for consuming self to convert into
-///                        another type. The original variable is consumed and becomes
-///                        unavailable after conversion.
-/// As:                    Used for cheap reference-to-reference conversions or explicit
-///                        primitive type casting.
-/// Move:                  A keyword used to transfer ownership of a resource to another
-///                        scope.
-/// Drop:                  To clean up and free resources when a resource goes out of scope.
-////////////////////////////////////////////////////////////////
-// Lesson 1: Ownership and Dropping - It's All About the Scope
-////////////////////////////////////////////////////////////////
 use std::collections::VecDeque;
 
+use crate::trace::trace;
+
 pub(crate) fn examples() {
     // 1) Scope and Ownership
     println!(" --------------- lesson 1 example 1 ---------------");
@@ -45,8 +18,11 @@ pub(crate) fn examples() {
 
     // 2) Passing Ownership
     println!(" --------------- lesson 1 example 2 ---------------");
+    // The trace! lines record what happens to my_data2, see `trace 1 2`
     let my_data2 = vec![1, 2, 3, 4, 5];
+    trace!(my_data2 created);
     println!("data2: {:?}", my_data2);
+    trace!(my_data2 moved into consume());
     consume(my_data2);
     // Uncommenting the next line will cause a compilation error because my_data2 has been moved
     // println!("data: {:?}", my_data2);
@@ -146,4 +122,6 @@ pub fn pass_thru(data: Vec<i32>) -> Vec<i32> {
 }
 
 // Helper method consuming the data
-pub fn consume(_data: Vec<i32>) {}
+pub fn consume(data: Vec<i32>) {
+    trace!(data dropped); // Recorded when data goes out of scope below
+}
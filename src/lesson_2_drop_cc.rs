@@ -2,25 +2,6 @@
 /* Lesson 2: Drop, Clone, and Copy Traits */
 /******************************************/
 
-/// In this lesson, we delve deeper into Rust's memory management features by exploring
-/// the Drop, Clone, and Copy traits. These traits are fundamental for understanding
-/// how Rust handles resource cleanup, duplication, and efficient value transfers.
-/// We'll also touch on the mut keyword and the definition and usage of structs.
-/// Grasping these concepts is essential for managing resource lifetimes and ensuring
-/// memory safety, which are cornerstones of Rust's design philosophy.
-
-/********************/
-/*   Vocabulary     */
-/********************/
-
-/// Trait:   A set of methods that a type must implement. Traits are similar to interfaces.
-/// Drop:    A trait that allows for custom cleanup code to be run when a resource
-///          goes out of scope, similar to Java's finally or dispose methods.
-/// Clone:   A trait that allows for copying a value, providing deep copies when necessary.
-/// Copy:    A trait that allows for copying a value, suitable for types that are simple
-///          and can be copied bitwise.
-/// Mut:     A keyword used to indicate mutable access to a variable.
-/// Struct:  A keyword used to define a structure.
 /*
 //////////////////////////////////////////////////////////////////////
 // On derived traits:
@@ -53,17 +34,17 @@
 /////////////////////////////////////////////////////////
 // Lesson 2: Drop, Clone, and Copy Traits
 /////////////////////////////////////////////////////////
-use std::fmt::Debug;
+use std::env;
 use std::fs::File;
 use std::io::Write;
 
+use crate::teaching_types::{
+    self, LargeStruct, MyCloneableStruct, MyCopyableStruct, MyStruct, Point, SimpleStruct,
+};
+
 // Example function to demonstrate usage
 pub(crate) fn examples() {
-    #[derive(Debug)]
-    struct SimpleStruct {
-        data: String,
-    }
-
+    // The structs used below are defined once, in teaching_types.rs
     // 1) Immutability by default
     println!(" --------------- lesson 2 example 1 ---------------");
     {
@@ -101,15 +82,7 @@ pub(crate) fn examples() {
 
     // 3) Drop trait
     println!(" --------------- lesson 2 example 3 ---------------");
-    #[derive(Debug)]
-    struct MyStruct {
-        data: String,
-    }
-    impl Drop for MyStruct {
-        fn drop(&mut self) {
-            println!("Dropping MyStruct with data: {}", self.data);
-        }
-    }
+    // MyStruct implements Drop in teaching_types.rs, printing its data
     {
         let my_struct = MyStruct {
             data: String::from("Hello, Rust!"),
@@ -120,7 +93,7 @@ pub(crate) fn examples() {
 
     // 3.1) Demonstrating file close when it leaves scope
     {
-        let file = File::create("./src/lesson_2.tmp"); // Open file for writing, truncating if it exists
+        let file = File::create(env::temp_dir().join("lesson_2.tmp")); // Open file for writing, truncating if it exists
 
         // Write a byte to the file
         match file {
@@ -170,10 +143,6 @@ pub(crate) fn examples() {
 
     // 4) Copy trait
     println!(" --------------- lesson 2 example 4 ---------------");
-    #[derive(Debug, Copy, Clone)]
-    struct MyCopyableStruct {
-        data: i32,
-    }
     {
         let original = MyCopyableStruct { data: 42 };
         let copied = original; // Copies the value bitwise
@@ -189,17 +158,20 @@ pub(crate) fn examples() {
 
     // 5) Clone trait
     println!(" --------------- lesson 2 example 5 ---------------");
-    #[derive(Debug, Clone)]
-    struct MyCloneableStruct {
-        data: String,
-    }
+    // MyCloneableStruct implements Clone by cloning its String, and prints when dropped
     {
         let original = MyCloneableStruct {
             data: String::from("Clone me!"),
         };
+        let clones_before = teaching_types::clone_count();
         let cloned = original.clone(); // Creates a deep copy
         println!("Original: {:?}", original.data);
         println!("Cloned: {:?}", cloned.data);
+        println!(
+            "Clones made: {}",
+            teaching_types::clone_count() - clones_before
+        );
+        // Two drops follow, one for each copy of the String
     }
     let s1 = String::from("hello");
     let s2 = s1; // s1 is moved to s2
@@ -223,11 +195,6 @@ pub(crate) fn examples() {
 
     // 8) Combining traits with struct
     println!(" --------------- lesson 2 example 8 ---------------");
-    #[derive(Debug, Clone, Copy)]
-    struct Point {
-        x: i32,
-        y: i32,
-    }
     {
         let point1 = Point { x: 1, y: 2 };
         let point2 = point1; // Copy trait allows for bitwise copy
@@ -243,10 +210,7 @@ pub(crate) fn examples() {
     // 9) Performance Implications of Cloning Large Data
     println!(" --------------- lesson 2 example 9 ---------------");
     use std::time::Instant;
-    #[derive(Clone)]
-    struct LargeStruct {
-        data: Vec<i32>,
-    }
+    // LargeStruct holds a Vec<i32> and implements Clone by cloning it
     {
         let large_data = LargeStruct {
             data: vec![0; 1_000_000],
@@ -2,28 +2,17 @@
 /* Lesson 3: Borrow Checking in Rust      */
 /******************************************/
 
-/// Rust's borrow checker is what makes it stand out among modern programming languages.
-/// By strictly enforcing ownership and borrowing rules, Rust guarantees memory safety
-/// without a garbage collector. This means developers can write high-performance,
-/// concurrent applications with confidence. In this lesson, we will explore the intricacies
-/// of borrowing, mutable and immutable references, and the common pitfalls you may encounter.
-/// Mastering these concepts is essential for writing efficient and safe Rust code.
-
-/********************/
-/*   Vocabulary     */
-/********************/
-
-/// Borrow, Borrowed, Borrowing: The concept of a scope temporarily using a resource
-///                              without taking ownership.
-/// Reference:                   A pointer to a resource. Does not own the resource.
-/// Mutable Reference:           A reference to a value that allows mutation.
-/// Immutable Reference:         A reference to a value that does not allow mutation.
-/// Box:                         A heap-allocated pointer type that provides ownership
-///                              and moves values off the stack.
-
-/////////////////////////////////////////////////////////
-// Lesson 3: Borrow Checking in Rust
-/////////////////////////////////////////////////////////
+use crate::ownership_graph::{self, Graph};
+use crate::teaching_types::{MyCloneableStruct, MyCopyableStruct};
+use crate::trace::trace;
+
+// The ownership snapshot example 7 takes for `graph 3`.
+#[allow(clippy::borrowed_box)] // The Box is what gets drawn
+fn box_and_reference(data: &Box<MyCopyableStruct>) -> Graph {
+    Graph::new("lesson 3 example 7: a Box and a reference to it")
+        .boxed("data", data)
+        .borrows("reference", "data", false)
+}
 
 pub(crate) fn examples() {
     // 1) Immutable References
@@ -69,34 +58,31 @@ pub(crate) fn examples() {
         println!("reference2: {}", reference2);
         // println!("reference3: {}", reference3); // reference3 cannot coexist with reference1 and reference2
         let reference4 = &mut data; // Mutable borrow after immutable references go out of scope
+        #[allow(clippy::single_char_add_str)] // the same push_str the borrows above use
         reference4.push_str("!");
         println!("reference4: {}", reference4);
     }
 
     // 4) Using Scopes for References
     println!(" --------------- lesson 3 example 4 ---------------");
+    // The trace! lines record the borrows as they are made, see `trace 3 4`
     {
         let mut data = String::from("Hello");
+        trace!(data created);
         {
             let reference1 = &data;
+            trace!(data borrowed by reference1);
             println!("reference1: {}", reference1);
         } // reference1 goes out of scope here
         let reference2 = &mut data;
+        trace!(data borrowed mut by reference2);
         reference2.push_str(", Rust!");
         println!("reference2: {}", reference2);
     }
 
     // 5) Using Clone with Borrowing
     println!(" --------------- lesson 3 example 5 ---------------");
-    #[derive(Debug, Clone)]
-    struct MyCloneableStruct {
-        data: String,
-    }
-    impl Drop for MyCloneableStruct {
-        fn drop(&mut self) {
-            println!("Dropping MyCloneableStruct with data: {}", self.data);
-        }
-    }
+    // MyCloneableStruct, from teaching_types.rs, prints "Dropping MyCloneableStruct with data: ..."
     {
         let original = MyCloneableStruct {
             data: String::from("Hello"),
@@ -111,37 +97,34 @@ pub(crate) fn examples() {
 
     // 6) Using Copy with Borrowing
     println!(" --------------- lesson 3 example 6 ---------------");
-    #[derive(Debug, Copy, Clone)]
-    struct MyCopyableStruct {
-        my_number: i32,
-    }
     // impl Drop for MyCopyableStruct { // This would cause a compile error, as a type cannot implement both Copy and Drop.
     //     fn drop(&mut self) {
     //         println!("Dropping MyCopyableStruct with data: {}", self.data);
     //     }
     // }
     {
-        let original = MyCopyableStruct { my_number: 42 };
+        let original = MyCopyableStruct { data: 42 };
         let borrowed = &original; // what if it is mut?
         let copied = original; // original can still be used because it's copied, not moved
-        println!("original: {:?}", original.my_number);
-        println!("borrowed: {:?}", borrowed.my_number);
-        println!("copied: {:?}", copied.my_number);
+        println!("original: {:?}", original.data);
+        println!("borrowed: {:?}", borrowed.data);
+        println!("copied: {:?}", copied.data);
     }
 
     // 7) Using Box to Move Data to the Heap
     println!(" --------------- lesson 3 example 7 ---------------");
     {
-        let data = Box::new(MyCopyableStruct { my_number: 42 });
+        let data = Box::new(MyCopyableStruct { data: 42 });
         let reference = &data;
         println!("reference: {:?}", reference);
+        ownership_graph::record(|| box_and_reference(&data));
         // Box moves data to the heap, useful for large data structures
     }
 
     // 8) Using Box::leak to Extend Lifetime
     println!(" --------------- lesson 3 example 8 ---------------");
     {
-        let s = {
+        let s: &'static mut MyCloneableStruct = {
             let data = Box::new(MyCloneableStruct {
                 data: String::from("Hello"),
             });
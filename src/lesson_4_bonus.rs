@@ -4,78 +4,213 @@
 /*       large reorganizations should be expected            */
 /*************************************************************/
 
-/// In this lesson, we will delve into advanced concepts related to borrowing and references in Rust.
-/// We'll explore traits such as AsRef, AsMut, Deref, and DerefMut, which provide powerful ways to
-/// work with references in a flexible and idiomatic manner. We'll also introduce smart pointers like
-/// Ref and RefMut from the RefCell type, enabling interior mutability and dynamic borrow checking.
-/// Additionally, we'll cover the Self keyword and lifetime specifiers ('a), which are essential for
-/// writing generic and reusable Rust code. Understanding these advanced borrowing and reference traits
-/// will equip you with the skills to write more efficient, safe, and idiomatic Rust programs.
-
-/********************/
-/*   Vocabulary     */
-/********************/
-
-/// Self:     A keyword that refers to the current type or instance of a type.
-/// 'a:       A lifetime specifier that indicates the lifetime of a reference.
-/// AsRef:    A trait that allows for cheap reference-to-reference conversions.
-/// AsMut:    A trait that allows for cheap mutable reference-to-mutable reference conversions.
-/// Deref:    A trait that allows for implicit dereferencing of a value.
-/// DerefMut: A trait that allows for implicit mutable dereferencing of a value.
-/// Ref:      A smart pointer to a value that is borrowed.
-/// RefMut:   A smart pointer to a value that is mutably borrowed.
 /*
 Suggestions: This lesson might be challenging for beginners. It’s important to ensure
 that you are comfortable with the earlier concepts before diving into this lesson.
+`cargo run -- path 4` lists the earlier lessons it builds on, and the terms it needs
+from each.
  */
 /////////////////////////////////////////////////////////
 // lesson 4 advanced borrowing and reference traits in Rust
 /////////////////////////////////////////////////////////
+use std::alloc::{self, Layout};
+use std::borrow::{Borrow, BorrowMut};
 use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
 
-#[derive(Debug)]
-struct MyStruct<'a> {
-    data: &'a str,
-}
+use crate::alloc_counter::count_allocations;
+use crate::drop_tracer::Traced;
+use crate::ownership_graph::{self, Graph};
+use crate::teaching_types::MyRefStruct;
 
-impl<'a> MyStruct<'a> {
-    fn new(data: &'a str) -> Self {
-        Self { data }
-    }
+// A teaching version of Box<T>. It allocates its value on the heap itself, frees it in
+// Drop, and implements the same traits as Box so the two can be compared side by side.
+// T: ?Sized lets it also hold a str, a slice or a dyn Trait, as Box can.
+pub struct MyBox<T: ?Sized> {
+    ptr: NonNull<T>,
+    _owns: PhantomData<T>, // Tells the drop checker that we own a T and will drop it
+}
 
-    fn display(&self) {
-        println!("Data: {}", self.data);
+impl<T> MyBox<T> {
+    pub fn new(x: T) -> MyBox<T> {
+        let layout = Layout::new::<T>();
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling() // Zero sized values need no memory, any aligned pointer will do
+        } else {
+            // SAFETY: layout has a non-zero size
+            let raw = unsafe { alloc::alloc(layout) } as *mut T;
+            NonNull::new(raw).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+        // SAFETY: ptr is aligned and valid for a T, and nothing has been written there yet
+        unsafe { ptr.as_ptr().write(x) };
+        MyBox {
+            ptr,
+            _owns: PhantomData,
+        }
     }
 }
 
-struct MyBox<T>(T);
+impl<T: ?Sized> MyBox<T> {
+    // Gives up ownership without dropping, like Box::into_raw.
+    pub fn into_raw(this: MyBox<T>) -> *mut T {
+        ManuallyDrop::new(this).ptr.as_ptr()
+    }
 
-impl<T> MyBox<T> {
-    fn new(x: T) -> MyBox<T> {
-        MyBox(x)
+    /// # Safety
+    /// raw must come from MyBox::into_raw or Box::into_raw, possibly unsized since, and must
+    /// not be owned by anything else.
+    pub unsafe fn from_raw(raw: *mut T) -> MyBox<T> {
+        MyBox {
+            ptr: NonNull::new_unchecked(raw),
+            _owns: PhantomData,
+        }
     }
 }
 
-impl<T> Deref for MyBox<T> {
+// Box<i32> turns into Box<dyn Display> by itself because Box implements CoerceUnsized,
+// which is unstable. Raw pointers coerce on stable, so we unsize the pointer instead.
+// The `let` only allows a coercion, never a cast, which is what keeps the unsafe sound.
+macro_rules! unsize {
+    ($boxed:expr => $target:ty) => {{
+        let sized = MyBox::into_raw($boxed);
+        let raw: *mut $target = sized;
+        // SAFETY: raw is the same allocation, only its type gained a length or a vtable
+        unsafe { MyBox::from_raw(raw) }
+    }};
+}
+
+impl<T: ?Sized> Deref for MyBox<T> {
     type Target = T;
 
     fn deref(&self) -> &T {
-        &self.0
+        // SAFETY: ptr points at a live T that we own, and &self keeps it borrowed
+        unsafe { self.ptr.as_ref() }
     }
 }
 
-impl<T> DerefMut for MyBox<T> {
+impl<T: ?Sized> DerefMut for MyBox<T> {
     fn deref_mut(&mut self) -> &mut T {
-        &mut self.0
+        // SAFETY: as in deref, and &mut self makes this the only borrow
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: ?Sized> Drop for MyBox<T> {
+    fn drop(&mut self) {
+        let layout = Layout::for_value::<T>(self);
+        // SAFETY: we own the value, drop it once, then free the memory it was allocated with
+        unsafe {
+            ptr::drop_in_place(self.ptr.as_ptr());
+            if layout.size() != 0 {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
+        }
+    }
+}
+
+// A deep clone: a new allocation holding a clone of the value, as Box does.
+impl<T: Clone> Clone for MyBox<T> {
+    fn clone(&self) -> Self {
+        MyBox::new((**self).clone())
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for MyBox<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsMut<T> for MyBox<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+// Borrow promises that Eq and Hash agree with T's, so a MyBox can be looked up by &T.
+impl<T: ?Sized> Borrow<T> for MyBox<T> {
+    fn borrow(&self) -> &T {
+        self
     }
 }
 
+impl<T: ?Sized> BorrowMut<T> for MyBox<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T> From<T> for MyBox<T> {
+    fn from(value: T) -> Self {
+        MyBox::new(value)
+    }
+}
+
+// Takes over a Box's allocation, so str, slices and dyn Trait values can be moved in.
+impl<T: ?Sized> From<Box<T>> for MyBox<T> {
+    fn from(boxed: Box<T>) -> Self {
+        // SAFETY: the pointer comes from Box::into_raw and we are its only owner
+        unsafe { MyBox::from_raw(Box::into_raw(boxed)) }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MyBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f) // Like Box, we print the value and not the pointer
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MyBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+// Compares the values, not the addresses, as Box does.
+impl<T: ?Sized + PartialEq> PartialEq for MyBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for MyBox<T> {}
+
+impl<T: ?Sized + Hash> Hash for MyBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+// NonNull is neither Send nor Sync, but owning a T is as thread safe as T itself.
+// SAFETY: MyBox<T> gives the same access to its T as a T stored inline would
+unsafe impl<T: ?Sized + Send> Send for MyBox<T> {}
+// SAFETY: &MyBox<T> only gives out &T
+unsafe impl<T: ?Sized + Sync> Sync for MyBox<T> {}
+
+// The ownership snapshots the Ref and RefMut example takes for `graph 4`.
+fn while_borrowed(data: &RefCell<String>) -> Graph {
+    Graph::new("lesson 4 Ref and RefMut: while borrowed")
+        .ref_cell("data", data)
+        .borrows("borrowed", "data", false)
+}
+
+fn while_borrowed_mutably(data: &RefCell<String>) -> Graph {
+    Graph::new("lesson 4 Ref and RefMut: while borrowed mutably")
+        .ref_cell("data", data)
+        .borrows("borrowed_mut", "data", true)
+}
+
 pub(crate) fn examples() {
     // Self keyword and Lifetime specifier
     {
-        let my_struct = MyStruct::new("Hello, Rust!");
-        my_struct.display();
+        let my_ref_struct = MyRefStruct::new("Hello, Rust!"); // Self and 'a are in teaching_types.rs
+        my_ref_struct.display();
     }
 
     // AsRef trait
@@ -103,6 +238,86 @@ pub(crate) fn examples() {
         println!("Mutably Dereferenced: {}", *y);
     }
 
+    // MyBox allocates on the heap and frees in Drop, like Box
+    {
+        let (_boxed, box_allocations) = count_allocations(|| Box::new(7));
+        let (_my_boxed, my_box_allocations) = count_allocations(|| MyBox::new(7));
+        println!(
+            "Allocations, Box: {}, MyBox: {}",
+            box_allocations, my_box_allocations
+        );
+        drop(Box::new(Traced::new("in a Box")));
+        drop(MyBox::new(Traced::new("in a MyBox"))); // Drop runs the value's Drop, then frees
+    }
+
+    // Clone is deep: a new allocation holding a clone of the value
+    {
+        let original = MyBox::new(String::from("Hello"));
+        let mut copy = original.clone();
+        copy.push_str(", clone");
+        println!("original: {}, copy: {}", original, copy);
+        println!("Same value address: {}", ptr::eq(&*original, &*copy));
+    }
+
+    // AsRef, AsMut and Borrow: the value behind the pointer, for generic code
+    {
+        fn shout<S: AsMut<String>>(mut text: S) -> S {
+            text.as_mut().make_ascii_uppercase();
+            text
+        }
+        fn length<S: AsRef<String>>(text: S) -> usize {
+            text.as_ref().len()
+        }
+        println!(
+            "Box: {}, MyBox: {}",
+            shout(Box::new(String::from("as mut"))),
+            shout(MyBox::new(String::from("as mut")))
+        );
+        println!("Lengths: {}", length(MyBox::new(String::from("as ref"))));
+        // Borrow lets a set of MyBox<String> be searched with a &String
+        let names: HashSet<MyBox<String>> = [MyBox::new(String::from("ferris"))].into();
+        println!(
+            "Contains ferris: {}",
+            names.contains(&String::from("ferris"))
+        );
+    }
+
+    // From: converting into a MyBox
+    {
+        let from_value: MyBox<i32> = 5.into();
+        let from_box: MyBox<str> = MyBox::from(Box::<str>::from("from a Box<str>"));
+        let from_slice: MyBox<[i32]> = vec![1, 2, 3].into_boxed_slice().into();
+        println!("{}, {}, {:?}", from_value, from_box, from_slice);
+        println!(
+            "Debug like Box: {:?} {:?}",
+            MyBox::new("text"),
+            Box::new("text")
+        );
+    }
+
+    // PartialEq compares the values, not the pointers
+    {
+        let a = MyBox::new(String::from("same"));
+        let b = MyBox::new(String::from("same"));
+        println!("a == b: {}, Box: {}", a == b, Box::new(1) == Box::new(1));
+    }
+
+    // CoerceUnsized-style conversions to dyn Trait and slices
+    {
+        // Box coerces by itself
+        let boxed: Box<dyn fmt::Display> = Box::new(42);
+        // let my_boxed: MyBox<dyn fmt::Display> = MyBox::new(42); // Uncommenting this line will cause a compilation error (E0308)
+        let my_boxed: MyBox<dyn fmt::Display> = unsize!(MyBox::new(42) => dyn fmt::Display);
+        let array: MyBox<[i32]> = unsize!(MyBox::new([1, 2, 3]) => [i32]);
+        println!(
+            "Box<dyn Display>: {}, MyBox<dyn Display>: {}",
+            boxed, my_boxed
+        );
+        println!("MyBox<[i32]> of length {}: {:?}", array.len(), array);
+        let text: &str = &MyBox::new(String::from("deref coercion")); // &MyBox<String> -> &String -> &str
+        println!("{}", text);
+    }
+
     // Ref and RefMut smart pointers
     {
         let data = RefCell::new(String::from("Hello, Rust!"));
@@ -112,11 +327,138 @@ pub(crate) fn examples() {
             //comment this bracket and see we have no compiler error
             let borrowed: Ref<String> = data.borrow();
             println!("Borrowed: {}", borrowed);
+            ownership_graph::record(|| while_borrowed(&data));
         } //comment this bracket and see we have no compiler error
           // Borrow as mutable ONLY works because our borrow above was dropped.
           // NOTE this one gets past the compiler because we used RefCell which is checked at runtime.
+          // Lesson 8 example 4 shows the panic we get when the bracket above is commented out.
         let mut borrowed_mut: RefMut<String> = data.borrow_mut();
         borrowed_mut.push_str(" How are you?");
         println!("Mutably Borrowed: {}", borrowed_mut);
+        ownership_graph::record(|| while_borrowed_mutably(&data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+    use crate::drop_tracer;
+    use crate::miri;
+
+    const COMPILE_FAIL: &[CompileFail] = &[CompileFail {
+        id: "lesson_4_no_coerce_unsized_on_stable",
+        code: "E0308",
+        source: r#"
+use std::fmt::Display;
+pub struct MyBox<T: ?Sized>(Box<T>);
+impl<T> MyBox<T> {
+    pub fn new(x: T) -> MyBox<T> { MyBox(Box::new(x)) }
+}
+pub fn demo() {
+    let _my_boxed: MyBox<dyn Display> = MyBox::new(42);
+}
+"#,
+    }];
+
+    #[test]
+    fn test_allocates_and_drops_like_box() {
+        assert_eq!(
+            count_allocations(|| Box::new(1u64)).1,
+            count_allocations(|| MyBox::new(1u64)).1
+        );
+        assert_eq!(
+            count_allocations(|| Box::new(())).1,
+            count_allocations(|| MyBox::new(())).1
+        );
+        drop(Box::new(Traced::new("test Box drop")));
+        drop(MyBox::new(Traced::new("test MyBox drop")));
+        assert!(drop_tracer::was_dropped("test Box drop"));
+        assert!(drop_tracer::was_dropped("test MyBox drop"));
+    }
+
+    #[test]
+    fn test_clone_is_deep_like_box() {
+        let boxed = Box::new(String::from("a"));
+        let my_boxed = MyBox::new(String::from("a"));
+        let (box_clone, box_allocations) = count_allocations(|| boxed.clone());
+        let (my_box_clone, my_box_allocations) = count_allocations(|| my_boxed.clone());
+        assert_eq!(box_allocations, my_box_allocations);
+        assert_eq!(*box_clone, *my_box_clone);
+        assert!(!ptr::eq(&*boxed, &*box_clone));
+        assert!(!ptr::eq(&*my_boxed, &*my_box_clone));
+    }
+
+    #[test]
+    fn test_as_ref_as_mut_borrow_like_box() {
+        let mut boxed = Box::new(String::from("a"));
+        let mut my_boxed = MyBox::new(String::from("a"));
+        AsMut::<String>::as_mut(&mut boxed).push('b');
+        AsMut::<String>::as_mut(&mut my_boxed).push('b');
+        assert_eq!(
+            AsRef::<String>::as_ref(&boxed),
+            AsRef::<String>::as_ref(&my_boxed)
+        );
+        BorrowMut::<String>::borrow_mut(&mut my_boxed).push('c');
+        let names: HashSet<MyBox<String>> = [my_boxed].into();
+        let box_names: HashSet<Box<String>> = [Box::new(String::from("abc"))].into();
+        assert_eq!(
+            box_names.contains(&String::from("abc")),
+            names.contains(&String::from("abc"))
+        );
+    }
+
+    #[test]
+    fn test_from_like_box() {
+        let boxed: Box<i32> = 5.into();
+        let my_boxed: MyBox<i32> = 5.into();
+        assert_eq!(*boxed, *my_boxed);
+        let text: MyBox<str> = Box::<str>::from("text").into();
+        assert_eq!("text", &*text);
+        let slice: MyBox<[i32]> = vec![1, 2].into_boxed_slice().into();
+        assert_eq!([1, 2], *slice);
+    }
+
+    #[test]
+    fn test_formatting_like_box() {
+        let boxed = Box::new(vec!["a"]);
+        let my_boxed = MyBox::new(vec!["a"]);
+        assert_eq!(format!("{:?}", boxed), format!("{:?}", my_boxed));
+        assert_eq!(
+            format!("{:>4}", Box::new(7)),
+            format!("{:>4}", MyBox::new(7))
+        );
+    }
+
+    #[test]
+    fn test_partial_eq_like_box() {
+        assert_eq!(Box::new(1) == Box::new(1), MyBox::new(1) == MyBox::new(1));
+        assert_eq!(Box::new(1) == Box::new(2), MyBox::new(1) == MyBox::new(2));
+    }
+
+    #[test]
+    fn test_unsize_like_box() {
+        let boxed: Box<dyn fmt::Debug> = Box::new(Traced::new("test unsized Box"));
+        let my_boxed: MyBox<dyn fmt::Debug> =
+            unsize!(MyBox::new(Traced::new("test unsized MyBox")) => dyn fmt::Debug);
+        drop(boxed);
+        drop(my_boxed); // Drops through the vtable and frees with the value's real size
+        assert!(drop_tracer::was_dropped("test unsized Box"));
+        assert!(drop_tracer::was_dropped("test unsized MyBox"));
+        let array: MyBox<[String]> =
+            unsize!(MyBox::new([String::from("a"), String::from("b")]) => [String]);
+        assert_eq!(2, array.len());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Miri cannot start rustc
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_my_box_passes_miri() {
+        miri::assert_tests_pass("lesson_4_bonus::tests");
     }
 }
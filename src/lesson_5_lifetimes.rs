@@ -0,0 +1,294 @@
+/******************************************/
+/* Lesson 5: Lifetimes in Depth           */
+/******************************************/
+
+use std::fmt::{Debug, Display};
+
+// Elision rule 1 and 2: one input reference, so the output borrows from it.
+// Written out in full this is: fn first_word<'a>(text: &'a str) -> &'a str
+pub fn first_word(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+// Two input references and no self, elision cannot pick one so we must say which.
+pub fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {
+    if x.len() >= y.len() {
+        x
+    } else {
+        y
+    }
+}
+
+// Two independent lifetimes: the result only depends on x, so y may die early.
+#[allow(clippy::needless_lifetimes)] // 'b could be elided, we spell it out to teach it
+pub fn first_of<'a, 'b>(x: &'a str, _y: &'b str) -> &'a str {
+    x
+}
+
+// T: 'static means "owns everything it holds", not "lives forever".
+pub fn describe_static<T: 'static + Debug>(value: T) -> String {
+    format!("{:?}", value)
+}
+
+#[derive(Debug)]
+pub struct Highlighter<'a> {
+    text: &'a str,
+}
+
+impl<'a> Highlighter<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    // Elision rule 3: with &self, the output borrows from self (the Highlighter).
+    pub fn peek(&self) -> &str {
+        self.text
+    }
+
+    // Naming 'a ties the output to the original text instead, so it outlives the Highlighter.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+}
+
+// Lifetime bound on an impl: T itself may hold references, and they must outlive 'a.
+pub struct Labeled<'a, T: 'a> {
+    label: &'a str,
+    value: &'a T,
+}
+
+impl<'a, T: Display + 'a> Labeled<'a, T> {
+    pub fn new(label: &'a str, value: &'a T) -> Self {
+        Self { label, value }
+    }
+
+    pub fn render(&self) -> String {
+        format!("{} = {}", self.label, self.value)
+    }
+}
+
+// Outlives bound: anything living for 'b can be handed out for the shorter 'a.
+pub fn shorten<'a, 'b: 'a>(long_lived: &'b str) -> &'a str {
+    long_lived
+}
+
+pub(crate) fn examples() {
+    // 1) Elision: one input reference
+    println!(" --------------- lesson 5 example 1 ---------------");
+    {
+        let sentence = String::from("borrow checker rules");
+        let word = first_word(&sentence); // word borrows from sentence
+        println!("first word: {}", word);
+        // drop(sentence); // Uncommenting this line will cause a compilation error (E0505)
+        println!("still usable: {}", word);
+    }
+
+    // 2) Elision fails: two input references
+    println!(" --------------- lesson 5 example 2 ---------------");
+    {
+        // Without 'a the compiler cannot know which input the result borrows from
+        // fn longest(x: &str, y: &str) -> &str { ... } // error[E0106]: missing lifetime specifier
+        let string1 = String::from("long string is long");
+        let string2 = String::from("xyz");
+        let result = longest(string1.as_str(), string2.as_str());
+        println!("longest: {}", result);
+    }
+
+    // 3) A single 'a means "the shorter of the two"
+    println!(" --------------- lesson 5 example 3 ---------------");
+    {
+        let string1 = String::from("long string is long");
+        let result;
+        {
+            let string2 = String::from("xyz");
+            result = longest(string1.as_str(), string2.as_str());
+            println!("longest inside: {}", result);
+        } // string2 is dropped here, so result may not be used past this point
+          // println!("longest outside: {}", result); // Uncommenting this line will cause a compilation error (E0597)
+    }
+
+    // 4) Multiple lifetime parameters
+    println!(" --------------- lesson 5 example 4 ---------------");
+    {
+        let string1 = String::from("kept");
+        let result;
+        {
+            let string2 = String::from("short lived");
+            result = first_of(string1.as_str(), string2.as_str());
+        } // string2 is dropped here, but result only borrows from string1
+        println!("first_of: {}", result);
+    }
+
+    // 5) &'static references
+    println!(" --------------- lesson 5 example 5 ---------------");
+    {
+        let literal: &'static str = "I live in the binary"; // String literals are baked into the program
+        let leaked: &'static str = Box::leak(String::from("I was leaked").into_boxed_str()); // see lesson 3 example 8
+        println!("literal: {}", literal);
+        println!("leaked: {}", leaked);
+    }
+
+    // 6) T: 'static is not the same as &'static T
+    println!(" --------------- lesson 5 example 6 ---------------");
+    {
+        let owned = String::from("owned values are 'static");
+        println!("{}", describe_static(owned)); // moved in, nothing borrowed, so T: 'static holds
+        println!("{}", describe_static("literals too")); // &'static str is also fine
+        let local = String::from("local");
+        println!("{}", describe_static(local.clone()));
+        // describe_static(&local); // Uncommenting this line will cause a compilation error (E0597)
+    }
+
+    // 7) Lifetime bounds on impls
+    println!(" --------------- lesson 5 example 7 ---------------");
+    {
+        let count = 42;
+        let labeled = Labeled::new("count", &count);
+        println!("labeled: {}", labeled.render());
+
+        let long_lived = String::from("outlives");
+        let short: &str = shorten(&long_lived);
+        println!("shortened: {}", short);
+    }
+
+    // 8) Returning references from methods
+    println!(" --------------- lesson 5 example 8 ---------------");
+    {
+        let text = String::from("highlight me");
+        let from_self;
+        let from_text;
+        {
+            let highlighter = Highlighter::new(&text);
+            from_self = highlighter.peek(); // borrows the highlighter
+            println!("peek: {}", from_self);
+            from_text = highlighter.text(); // borrows the text directly
+        } // highlighter is dropped here
+          // println!("peek: {}", from_self); // Uncommenting this line will cause a compilation error (E0597)
+        println!("text: {}", from_text); // fine, text is still alive
+    }
+
+    // 9) Returning a reference to a local value
+    println!(" --------------- lesson 5 example 9 ---------------");
+    {
+        // No lifetime annotation can make this work, the String is dropped at the end of the function
+        // fn dangle() -> &'static str { let s = String::from("hi"); &s } // error[E0515]
+        fn no_dangle() -> String {
+            String::from("return ownership instead")
+        }
+        println!("no_dangle: {}", no_dangle());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_5_missing_lifetime",
+            code: "E0106",
+            source: r#"
+pub fn longest(x: &str, y: &str) -> &str {
+    if x.len() > y.len() { x } else { y }
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_5_shorter_lifetime_wins",
+            code: "E0597",
+            source: r#"
+fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {
+    if x.len() > y.len() { x } else { y }
+}
+pub fn demo() {
+    let string1 = String::from("long string is long");
+    let result;
+    {
+        let string2 = String::from("xyz");
+        result = longest(string1.as_str(), string2.as_str());
+    }
+    println!("{}", result);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_5_static_bound",
+            code: "E0597",
+            source: r#"
+fn describe_static<T: 'static + std::fmt::Debug>(value: T) -> String {
+    format!("{:?}", value)
+}
+pub fn demo() {
+    let local = String::from("local");
+    describe_static(&local);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_5_method_borrows_self",
+            code: "E0597",
+            source: r#"
+struct Highlighter<'a> { text: &'a str }
+impl<'a> Highlighter<'a> {
+    fn peek(&self) -> &str { self.text }
+}
+pub fn demo() {
+    let text = String::from("highlight me");
+    let from_self;
+    {
+        let highlighter = Highlighter { text: &text };
+        from_self = highlighter.peek();
+    }
+    println!("{}", from_self);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_5_dangle",
+            code: "E0515",
+            source: r#"
+pub fn dangle() -> &'static str {
+    let s = String::from("hi");
+    &s
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_elided_lifetimes() {
+        let sentence = String::from("borrow checker");
+        assert_eq!("borrow", first_word(&sentence));
+        assert_eq!("", first_word(""));
+    }
+
+    #[test]
+    fn test_multiple_lifetimes() {
+        let kept = String::from("kept");
+        let result;
+        {
+            let gone = String::from("gone");
+            assert_eq!("gone", longest("gone", &gone));
+            result = first_of(&kept, &gone);
+        }
+        assert_eq!("kept", result);
+    }
+
+    #[test]
+    fn test_method_lifetimes() {
+        let text = String::from("highlight me");
+        let from_text = {
+            let highlighter = Highlighter::new(&text);
+            assert_eq!("highlight me", highlighter.peek());
+            highlighter.text()
+        };
+        assert_eq!("highlight me", from_text);
+        assert_eq!("count = 42", Labeled::new("count", &42).render());
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
@@ -0,0 +1,316 @@
+/*****************************************************/
+/* Lesson 6: Shared Ownership with Rc, Weak and Arc  */
+/*****************************************************/
+
+use crate::drop_tracer::{self, Traced};
+use crate::ownership_graph::{self, Graph};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+use std::thread;
+
+// A node that owns its neighbor, this is how cycles get built.
+pub struct StrongNode {
+    pub tracer: Traced,
+    pub next: RefCell<Option<Rc<StrongNode>>>,
+}
+
+// A tree node: parents own their children, children only watch their parent.
+pub struct TreeNode {
+    pub tracer: Traced,
+    pub parent: RefCell<Weak<TreeNode>>,
+    pub children: RefCell<Vec<Rc<TreeNode>>>,
+}
+
+impl TreeNode {
+    pub fn new(name: &str) -> Rc<TreeNode> {
+        Rc::new(TreeNode {
+            tracer: Traced::new(name),
+            parent: RefCell::new(Weak::new()),
+            children: RefCell::new(Vec::new()),
+        })
+    }
+
+    pub fn adopt(parent: &Rc<TreeNode>, child: Rc<TreeNode>) {
+        *child.parent.borrow_mut() = Rc::downgrade(parent);
+        parent.children.borrow_mut().push(child);
+    }
+}
+
+// Builds two nodes that own each other and returns their names.
+pub fn build_cycle() -> (String, String) {
+    let a = Rc::new(StrongNode {
+        tracer: Traced::new("cycle a"),
+        next: RefCell::new(None),
+    });
+    let b = Rc::new(StrongNode {
+        tracer: Traced::new("cycle b"),
+        next: RefCell::new(Some(Rc::clone(&a))),
+    });
+    *a.next.borrow_mut() = Some(Rc::clone(&b));
+    println!(
+        "a strong: {}, b strong: {}",
+        Rc::strong_count(&a),
+        Rc::strong_count(&b)
+    );
+    (a.tracer.name().to_string(), b.tracer.name().to_string())
+} // a and b go out of scope here, but each count only drops from 2 to 1
+
+// The ownership snapshots examples 1 and 4 take for `graph 6`.
+fn after_clone(first: &Rc<String>, second: &Rc<String>) -> Graph {
+    Graph::new("lesson 6 example 1: after clone")
+        .rc("first", first)
+        .rc("second", second)
+}
+
+fn inner_scope(first: &Rc<String>, second: &Rc<String>, third: &Rc<String>) -> Graph {
+    Graph::new("lesson 6 example 1: inner scope")
+        .rc("first", first)
+        .rc("second", second)
+        .rc("third", third)
+}
+
+fn while_alive(owner: &Rc<String>, watcher: &Weak<String>) -> Graph {
+    Graph::new("lesson 6 example 4: while alive")
+        .rc("owner", owner)
+        .weak("watcher", watcher)
+}
+
+fn after_drop(watcher: &Weak<String>) -> Graph {
+    Graph::new("lesson 6 example 4: after drop").weak("watcher", watcher)
+}
+
+pub(crate) fn examples() {
+    // 1) Rc: several owners of one value
+    println!(" --------------- lesson 6 example 1 ---------------");
+    {
+        let first = Rc::new(String::from("shared"));
+        println!("after new: strong = {}", Rc::strong_count(&first));
+        let second = Rc::clone(&first); // Copies the pointer, not the String
+        println!("after clone: strong = {}", Rc::strong_count(&first));
+        ownership_graph::record(|| after_clone(&first, &second));
+        {
+            let third = Rc::clone(&first);
+            println!(
+                "inner scope: strong = {} ({})",
+                Rc::strong_count(&third),
+                third
+            );
+            ownership_graph::record(|| inner_scope(&first, &second, &third));
+        } // third is dropped here
+        println!("after inner scope: strong = {}", Rc::strong_count(&first));
+        println!("same allocation: {}", Rc::ptr_eq(&first, &second));
+    }
+
+    // 2) The value is dropped with the last owner
+    println!(" --------------- lesson 6 example 2 ---------------");
+    {
+        let first = Rc::new(Traced::new("rc value"));
+        let second = Rc::clone(&first);
+        drop(first);
+        println!(
+            "first dropped, strong = {}, value dropped: {}",
+            Rc::strong_count(&second),
+            drop_tracer::was_dropped("rc value")
+        );
+        drop(second); // Last owner, Traced is dropped now
+        println!("value dropped: {}", drop_tracer::was_dropped("rc value"));
+        drop_tracer::take_dropped(); // Start the next examples with a clean record
+    }
+
+    // 3) Shared means immutable
+    println!(" --------------- lesson 6 example 3 ---------------");
+    {
+        let mut shared = Rc::new(String::from("Hello"));
+        // shared.push_str(", Rust!"); // Uncommenting this line will cause a compilation error (E0596)
+        Rc::make_mut(&mut shared).push_str(", Rust!"); // Only one owner, so no copy is made
+        let other = Rc::clone(&shared);
+        Rc::make_mut(&mut shared).push('!'); // Two owners, so make_mut clones first
+        println!("shared: {}, other: {}", shared, other);
+        println!("same allocation: {}", Rc::ptr_eq(&shared, &other));
+    }
+
+    // 4) Weak pointers do not keep values alive
+    println!(" --------------- lesson 6 example 4 ---------------");
+    {
+        let owner = Rc::new(String::from("watched"));
+        let watcher: Weak<String> = Rc::downgrade(&owner);
+        println!(
+            "strong = {}, weak = {}",
+            Rc::strong_count(&owner),
+            Rc::weak_count(&owner)
+        );
+        ownership_graph::record(|| while_alive(&owner, &watcher));
+        match watcher.upgrade() {
+            Some(value) => println!("upgrade while alive: {}", value),
+            None => println!("upgrade while alive: gone"),
+        }
+        drop(owner);
+        ownership_graph::record(|| after_drop(&watcher));
+        println!("upgrade after drop: {:?}", watcher.upgrade());
+    }
+
+    // 5) A reference cycle leaks
+    println!(" --------------- lesson 6 example 5 ---------------");
+    {
+        let (a, b) = build_cycle();
+        // Neither node printed a drop message: each one is still owned by the other
+        println!(
+            "{} dropped: {}, {} dropped: {}",
+            a,
+            drop_tracer::was_dropped(&a),
+            b,
+            drop_tracer::was_dropped(&b)
+        );
+    }
+
+    // 6) Breaking the cycle with Weak
+    println!(" --------------- lesson 6 example 6 ---------------");
+    {
+        let root = TreeNode::new("tree root");
+        let leaf = TreeNode::new("tree leaf");
+        TreeNode::adopt(&root, Rc::clone(&leaf));
+        println!(
+            "root strong = {}, weak = {}",
+            Rc::strong_count(&root),
+            Rc::weak_count(&root)
+        );
+        println!(
+            "leaf strong = {}, weak = {}",
+            Rc::strong_count(&leaf),
+            Rc::weak_count(&leaf)
+        );
+        if let Some(parent) = leaf.parent.borrow().upgrade() {
+            println!("leaf's parent: {}", parent.tracer.name());
+        }
+        drop(leaf); // root still owns the leaf through its children
+        drop(root); // root goes, then the leaf it owned
+        println!("dropped in order: {:?}", drop_tracer::take_dropped());
+    }
+
+    // 7) Arc: the fix for lesson 3 example 11
+    println!(" --------------- lesson 6 example 7 ---------------");
+    {
+        let data = Arc::new(String::from("Hello"));
+        let reference1 = &data;
+        let for_thread = Arc::clone(&data); // A second owner that can move into the thread
+        println!("before spawn: strong = {}", Arc::strong_count(&data));
+        let handle = thread::spawn(move || {
+            let reference2 = &for_thread;
+            println!("Thread reference: {}", reference2);
+        });
+        handle.join().unwrap(); // The thread's owner was dropped when its closure finished
+        println!("after join: strong = {}", Arc::strong_count(&data));
+        println!("Main thread reference: {}", reference1);
+    }
+
+    // 8) Rc cannot cross threads
+    println!(" --------------- lesson 6 example 8 ---------------");
+    {
+        let shared = Rc::new(String::from("single threaded"));
+        // Rc updates its counts without atomics, so the compiler keeps it on one thread
+        // let for_thread = Rc::clone(&shared);
+        // thread::spawn(move || println!("{}", for_thread)); // Uncommenting this line will cause a compilation error (E0277)
+        println!("shared: {}", shared);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_6_lesson_3_example_11",
+            code: "E0505",
+            source: r#"
+use std::thread;
+pub fn demo() {
+    let data = String::from("Hello");
+    let reference1 = &data;
+    let handle = thread::spawn(move || {
+        let reference2 = &data;
+        println!("Thread reference: {}", reference2);
+    });
+    handle.join().unwrap();
+    println!("Main thread reference: {}", reference1);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_6_mutate_through_rc",
+            code: "E0596",
+            source: r#"
+use std::rc::Rc;
+pub fn demo() {
+    let shared = Rc::new(String::from("Hello"));
+    shared.push_str(", Rust!");
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_6_rc_not_send",
+            code: "E0277",
+            source: r#"
+use std::rc::Rc;
+use std::thread;
+pub fn demo() {
+    let shared = Rc::new(String::from("single threaded"));
+    let for_thread = Rc::clone(&shared);
+    thread::spawn(move || println!("{}", for_thread));
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_strong_and_weak_counts() {
+        let first = Rc::new(Traced::new("counted"));
+        let second = Rc::clone(&first);
+        let watcher = Rc::downgrade(&first);
+        assert_eq!(2, Rc::strong_count(&first));
+        assert_eq!(1, Rc::weak_count(&first));
+        drop(first);
+        assert!(watcher.upgrade().is_some());
+        drop(second);
+        assert!(watcher.upgrade().is_none());
+        assert!(drop_tracer::was_dropped("counted"));
+    }
+
+    #[test]
+    fn test_cycle_leaks() {
+        let (a, b) = build_cycle();
+        assert!(!drop_tracer::was_dropped(&a));
+        assert!(!drop_tracer::was_dropped(&b));
+    }
+
+    #[test]
+    fn test_weak_breaks_cycle() {
+        let root = TreeNode::new("root");
+        TreeNode::adopt(&root, TreeNode::new("leaf"));
+        assert_eq!(1, Rc::strong_count(&root));
+        drop(root);
+        assert_eq!(vec!["root", "leaf"], drop_tracer::take_dropped());
+    }
+
+    #[test]
+    fn test_arc_across_threads() {
+        let data = Arc::new(String::from("Hello"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let for_thread = Arc::clone(&data);
+                thread::spawn(move || for_thread.len())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(5, handle.join().unwrap());
+        }
+        assert_eq!(1, Arc::strong_count(&data));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
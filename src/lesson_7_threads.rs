@@ -0,0 +1,258 @@
+/*****************************************************/
+/* Lesson 7: Threads, Scoped Threads and Send/Sync   */
+/*****************************************************/
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+// Splits the slice between scoped threads, each borrowing only its own chunk.
+pub fn sum_in_chunks(numbers: &[i32], chunk_size: usize) -> i32 {
+    thread::scope(|scope| {
+        let handles: Vec<_> = numbers
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().sum::<i32>()))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
+
+// Each scoped thread gets its own &mut chunk, the borrows never overlap.
+pub fn double_in_chunks(numbers: &mut [i32], chunk_size: usize) {
+    thread::scope(|scope| {
+        for chunk in numbers.chunks_mut(chunk_size) {
+            scope.spawn(move || chunk.iter_mut().for_each(|n| *n *= 2));
+        }
+    });
+}
+
+// Shares one counter between several threads, each adding to it under the lock.
+pub fn count_with_mutex(threads: usize, increments: usize) -> usize {
+    let counter = Arc::new(Mutex::new(0));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..increments {
+                    *counter.lock().unwrap() += 1; // The guard is dropped at the end of the statement
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let total = *counter.lock().unwrap();
+    total
+}
+
+// Compile time checks, these functions only accept types with the marker trait.
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+pub(crate) fn examples() {
+    // 1) thread::spawn with move
+    println!(" --------------- lesson 7 example 1 ---------------");
+    {
+        let data = vec![1, 2, 3];
+        // Without move the closure would only borrow data, and the thread may outlive it
+        // let handle = thread::spawn(|| data.len()); // Uncommenting this line will cause a compilation error (E0373)
+        let handle = thread::spawn(move || {
+            let total: i32 = data.iter().sum(); // data is owned by the thread now
+            (data, total)
+        });
+        let (data, total) = handle.join().unwrap(); // Ownership comes back through join
+        println!("data: {:?}, total: {}", data, total);
+    }
+
+    // 2) Scoped threads may borrow from the stack
+    println!(" --------------- lesson 7 example 2 ---------------");
+    {
+        let numbers = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let total = sum_in_chunks(&numbers, 3); // numbers is only borrowed
+        println!("numbers: {:?}, total: {}", numbers, total);
+
+        let mut to_double = numbers.clone();
+        double_in_chunks(&mut to_double, 3);
+        println!("doubled: {:?}", to_double);
+        // Two threads may not push to the same Vec, that would be two &mut at once
+        // thread::scope(|s| {
+        //     s.spawn(|| to_double.push(4));
+        //     s.spawn(|| to_double.push(5)); // Uncommenting these lines will cause a compilation error (E0499)
+        // });
+    }
+
+    // 3) Mutex guards behave like borrows
+    println!(" --------------- lesson 7 example 3 ---------------");
+    {
+        let total = count_with_mutex(4, 1000);
+        println!("count with mutex: {}", total);
+
+        let data = Mutex::new(String::from("Hello"));
+        {
+            let mut guard = data.lock().unwrap(); // Like &mut, only one at a time
+            guard.push_str(", Rust!");
+            println!("try_lock while guard alive: {}", data.try_lock().is_ok());
+        } // guard is dropped here, which unlocks the mutex
+        println!("try_lock after guard dropped: {}", data.try_lock().is_ok());
+        println!("data: {}", data.lock().unwrap());
+        // A reference into the data cannot outlive its guard
+        // let value: &String = { let guard = data.lock().unwrap(); &guard }; // Uncommenting this line will cause a compilation error (E0597)
+    }
+
+    // 4) RwLock: many readers or one writer
+    println!(" --------------- lesson 7 example 4 ---------------");
+    {
+        let config = RwLock::new(String::from("v1"));
+        {
+            let reader1 = config.read().unwrap(); // Like &
+            let reader2 = config.read().unwrap(); // Many readers are fine
+            println!("readers: {} {}", *reader1, *reader2);
+            println!("try_write while reading: {}", config.try_write().is_ok());
+        } // both readers are dropped here
+        {
+            let mut writer = config.write().unwrap(); // Like &mut
+            writer.push_str(" -> v2");
+            println!("try_read while writing: {}", config.try_read().is_ok());
+        }
+        println!("config: {}", config.read().unwrap());
+    }
+
+    // 5) Channels transfer ownership
+    println!(" --------------- lesson 7 example 5 ---------------");
+    {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let producer = thread::spawn(move || {
+            for word in ["borrow", "move", "drop"] {
+                let message = String::from(word);
+                // message is moved into the channel
+                sender.send(message).unwrap();
+                // println!("{}", message); // Uncommenting this line will cause a compilation error (E0382)
+            }
+        }); // sender is dropped when the thread ends, which closes the channel
+        let received: Vec<String> = receiver.iter().collect(); // The receiver owns each message now
+        producer.join().unwrap();
+        println!("received: {:?}", received);
+    }
+
+    // 6) Send and Sync
+    println!(" --------------- lesson 7 example 6 ---------------");
+    {
+        assert_send::<String>(); // Owned data may move to another thread
+        assert_send::<Arc<Mutex<String>>>();
+        assert_sync::<Mutex<String>>(); // &Mutex may be shared, the lock guards access
+        assert_sync::<RwLock<String>>();
+        // assert_send::<std::rc::Rc<String>>(); // Uncommenting this line will cause a compilation error (E0277)
+        // assert_sync::<std::cell::RefCell<String>>(); // Uncommenting this line will cause a compilation error (E0277)
+        println!(
+            "String: Send, Arc<Mutex<String>>: Send, Mutex<String>: Sync, RwLock<String>: Sync"
+        );
+        println!("Rc<String>: not Send, RefCell<String>: not Sync");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_7_spawn_without_move",
+            code: "E0373",
+            source: r#"
+use std::thread;
+pub fn demo() {
+    let data = vec![1, 2, 3];
+    let handle = thread::spawn(|| data.len());
+    handle.join().unwrap();
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_7_two_scoped_writers",
+            code: "E0499",
+            source: r#"
+use std::thread;
+pub fn demo() {
+    let mut numbers = vec![1, 2, 3];
+    thread::scope(|s| {
+        s.spawn(|| numbers.push(4));
+        s.spawn(|| numbers.push(5));
+    });
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_7_reference_outlives_guard",
+            code: "E0597",
+            source: r#"
+use std::sync::Mutex;
+pub fn demo() -> usize {
+    let data = Mutex::new(String::from("Hello"));
+    let value: &String = {
+        let guard = data.lock().unwrap();
+        &guard
+    };
+    value.len()
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_7_use_after_send",
+            code: "E0382",
+            source: r#"
+use std::sync::mpsc;
+pub fn demo() {
+    let (sender, _receiver) = mpsc::channel();
+    let message = String::from("hello");
+    sender.send(message).unwrap();
+    println!("{}", message);
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_7_rc_not_send",
+            code: "E0277",
+            source: r#"
+fn assert_send<T: Send>() {}
+pub fn demo() {
+    assert_send::<std::rc::Rc<String>>();
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_7_refcell_not_sync",
+            code: "E0277",
+            source: r#"
+use std::cell::RefCell;
+use std::thread;
+pub fn demo() {
+    let cell = RefCell::new(0);
+    thread::scope(|s| {
+        s.spawn(|| *cell.borrow_mut() += 1);
+    });
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_scoped_threads() {
+        let numbers: Vec<i32> = (1..=10).collect();
+        assert_eq!(55, sum_in_chunks(&numbers, 3));
+        let mut to_double = numbers.clone();
+        double_in_chunks(&mut to_double, 4);
+        assert_eq!(numbers.iter().map(|n| n * 2).collect::<Vec<_>>(), to_double);
+    }
+
+    #[test]
+    fn test_mutex_counter() {
+        assert_eq!(8 * 500, count_with_mutex(8, 500));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
@@ -0,0 +1,290 @@
+/*********************************************************/
+/* Lesson 8: Interior Mutability with Cell and RefCell   */
+/*********************************************************/
+
+use std::cell::{Cell, OnceCell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+// Asks the RefCell, without panicking, what kind of borrow is active right now.
+pub fn borrow_state<T>(cell: &RefCell<T>) -> &'static str {
+    if cell.try_borrow_mut().is_ok() {
+        "unborrowed"
+    } else if cell.try_borrow().is_ok() {
+        "shared (Ref alive)"
+    } else {
+        "exclusive (RefMut alive)"
+    }
+}
+
+// Runs f and turns a panic into an Err with its message. The panic hook still reports the
+// panic on stderr, the lesson's own output goes to stdout.
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            String::from("unknown panic")
+        }
+    })
+}
+
+// A graph node that several parents may share and any of them may update.
+#[derive(Debug)]
+pub struct GraphNode {
+    pub name: String,
+    pub value: i32,
+    pub children: Vec<Rc<RefCell<GraphNode>>>,
+}
+
+impl GraphNode {
+    pub fn new(name: &str, value: i32) -> Rc<RefCell<GraphNode>> {
+        Rc::new(RefCell::new(GraphNode {
+            name: name.to_string(),
+            value,
+            children: Vec::new(),
+        }))
+    }
+}
+
+// Sums every path through the graph, so a shared node is counted once per parent.
+pub fn sum_paths(node: &Rc<RefCell<GraphNode>>) -> i32 {
+    let node = node.borrow(); // Each level holds a shared borrow while visiting children
+    node.value + node.children.iter().map(sum_paths).sum::<i32>()
+}
+
+pub(crate) fn examples() {
+    // 1) Cell: mutation without references
+    println!(" --------------- lesson 8 example 1 ---------------");
+    {
+        let counter = Cell::new(0);
+        let shared1 = &counter;
+        let shared2 = &counter; // Two shared references, yet both may update it
+        shared1.set(shared1.get() + 1);
+        shared2.set(shared2.get() + 1);
+        println!("counter: {}", counter.get());
+        let old = counter.replace(10); // Swap in a new value, get the old one back
+        println!("replaced {} with {}", old, counter.get());
+
+        let name = Cell::new(String::from("owned"));
+        // name.get(); // Uncommenting this line will cause a compilation error (E0599), get needs Copy
+        let taken = name.take(); // Non-Copy values can still be moved out, leaving Default behind
+        println!("taken: {:?}, left behind: {:?}", taken, name.take());
+    }
+
+    // 2) RefCell: the borrow state at each step
+    println!(" --------------- lesson 8 example 2 ---------------");
+    {
+        let data = RefCell::new(String::from("Hello"));
+        println!("start: {}", borrow_state(&data));
+        {
+            let reader1 = data.borrow();
+            let reader2 = data.borrow(); // Many Ref values may coexist, like many &
+            println!(
+                "two readers ({} {}): {}",
+                *reader1,
+                *reader2,
+                borrow_state(&data)
+            );
+        } // both Ref values are dropped here
+        println!("readers dropped: {}", borrow_state(&data));
+        {
+            let mut writer = data.borrow_mut(); // Only one RefMut, like &mut
+            writer.push_str(", Rust!");
+            println!("writer ({}): {}", *writer, borrow_state(&data));
+        } // RefMut is dropped here
+        println!("writer dropped: {}", borrow_state(&data));
+    }
+
+    // 3) try_borrow_mut: asking before taking
+    println!(" --------------- lesson 8 example 3 ---------------");
+    {
+        let data = RefCell::new(String::from("Hello"));
+        let reader = data.borrow();
+        match data.try_borrow_mut() {
+            Ok(_) => println!("try_borrow_mut: granted"),
+            Err(e) => println!("try_borrow_mut: {} ({:?})", e, e),
+        }
+        drop(reader);
+        // The semicolon matters: in edition 2021 a temporary in a block's tail expression,
+        // here the RefMut, lives until after data is dropped (E0597 without it)
+        match data.try_borrow_mut() {
+            Ok(mut writer) => {
+                writer.push('!');
+                println!("try_borrow_mut after drop: granted, {}", *writer);
+            }
+            Err(e) => println!("try_borrow_mut after drop: {}", e),
+        };
+    }
+
+    // 4) borrow_mut: the real panic from lesson 4
+    println!(" --------------- lesson 8 example 4 ---------------");
+    {
+        let data = RefCell::new(String::from("Hello, Rust!"));
+        // This is lesson 4's example with the bracket commented out. It compiles fine.
+        let result = catch_panic(|| {
+            let borrowed = data.borrow();
+            println!("Borrowed: {} ({})", borrowed, borrow_state(&data));
+            let mut borrowed_mut = data.borrow_mut(); // The Ref above is still alive
+            borrowed_mut.push_str(" How are you?");
+        });
+        match result {
+            Ok(()) => println!("no panic"),
+            Err(message) => println!("caught panic: {}", message),
+        }
+        // Unwinding dropped the Ref, so the cell is usable again
+        println!("after unwinding: {}", borrow_state(&data));
+    }
+
+    // 5) OnceCell: written once, read many times
+    println!(" --------------- lesson 8 example 5 ---------------");
+    {
+        let config: OnceCell<String> = OnceCell::new();
+        println!("before init: {:?}", config.get());
+        let value = config.get_or_init(|| String::from("loaded once"));
+        println!("get_or_init: {}", value);
+        match config.set(String::from("second value")) {
+            Ok(()) => println!("set: accepted"),
+            Err(rejected) => println!("set: rejected, we still own {:?}", rejected),
+        }
+        println!("after set: {:?}", config.get());
+    }
+
+    // 6) Rc<RefCell<T>>: a shared and mutable graph
+    println!(" --------------- lesson 8 example 6 ---------------");
+    {
+        // top -> left -> bottom, top -> right -> bottom: bottom has two owners
+        let bottom = GraphNode::new("bottom", 1);
+        let left = GraphNode::new("left", 10);
+        let right = GraphNode::new("right", 100);
+        left.borrow_mut().children.push(Rc::clone(&bottom));
+        right.borrow_mut().children.push(Rc::clone(&bottom));
+        let top = GraphNode::new("top", 1000);
+        top.borrow_mut().children.push(Rc::clone(&left));
+        top.borrow_mut().children.push(Rc::clone(&right));
+        println!(
+            "bottom owners: {}, sum of paths: {}",
+            Rc::strong_count(&bottom),
+            sum_paths(&top)
+        );
+
+        // Update bottom through the left path, read it through the right path
+        {
+            let left_ref = left.borrow();
+            let mut through_left = left_ref.children[0].borrow_mut();
+            through_left.value += 5;
+            println!(
+                "updating {} through left: bottom is {}",
+                through_left.name,
+                borrow_state(&bottom)
+            );
+        }
+        let through_right = right.borrow().children[0].borrow().value;
+        println!("read through right: {}", through_right);
+        println!("sum of paths: {}", sum_paths(&top));
+
+        // Holding a RefMut while the graph is walked is the classic way to panic
+        let writer = bottom.borrow_mut();
+        match catch_panic(|| sum_paths(&top)) {
+            Ok(sum) => println!("sum while bottom is borrowed: {}", sum),
+            Err(message) => println!("sum while bottom is borrowed: panic, {}", message),
+        }
+        drop(writer);
+        println!("sum after writer dropped: {}", sum_paths(&top));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_8_cell_get_needs_copy",
+            code: "E0599",
+            source: r#"
+use std::cell::Cell;
+pub fn demo() -> String {
+    let name = Cell::new(String::from("owned"));
+    name.get()
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_8_mutate_through_shared_ref",
+            code: "E0594",
+            source: r#"
+pub struct Counter { pub count: u32 }
+pub fn demo(counter: &Counter) {
+    counter.count += 1;
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_8_ref_outlives_refcell",
+            code: "E0597",
+            source: r#"
+use std::cell::{Ref, RefCell};
+pub fn demo() -> usize {
+    let held: Ref<String>;
+    {
+        let cell = RefCell::new(String::from("Hello"));
+        held = cell.borrow();
+    }
+    held.len()
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_borrow_state() {
+        let data = RefCell::new(1);
+        assert_eq!("unborrowed", borrow_state(&data));
+        let reader = data.borrow();
+        assert_eq!("shared (Ref alive)", borrow_state(&data));
+        assert!(data.try_borrow_mut().is_err());
+        drop(reader);
+        let _writer = data.borrow_mut();
+        assert_eq!("exclusive (RefMut alive)", borrow_state(&data));
+        assert!(data.try_borrow().is_err());
+    }
+
+    #[test]
+    fn test_borrow_mut_panics_while_borrowed() {
+        let data = RefCell::new(String::from("Hello"));
+        let result = catch_panic(|| {
+            let _reader = data.borrow();
+            let _writer = data.borrow_mut();
+        });
+        assert!(result.unwrap_err().contains("already borrowed"));
+        assert_eq!("unborrowed", borrow_state(&data));
+    }
+
+    #[test]
+    fn test_once_cell() {
+        let cell = OnceCell::new();
+        assert_eq!(&1, cell.get_or_init(|| 1));
+        assert_eq!(Err(2), cell.set(2));
+        assert_eq!(Some(&1), cell.get());
+    }
+
+    #[test]
+    fn test_shared_graph() {
+        let shared = GraphNode::new("shared", 1);
+        let top = GraphNode::new("top", 0);
+        top.borrow_mut().children.push(Rc::clone(&shared));
+        top.borrow_mut().children.push(Rc::clone(&shared));
+        assert_eq!(2, sum_paths(&top));
+        shared.borrow_mut().value = 5;
+        assert_eq!(10, sum_paths(&top));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
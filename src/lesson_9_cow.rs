@@ -0,0 +1,282 @@
+/*****************************************************/
+/* Lesson 9: Cow and Borrowed-or-Owned APIs          */
+/*****************************************************/
+
+use crate::alloc_counter::count_allocations;
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Always allocates, even when there is nothing to change.
+pub fn expand_tabs_owned(input: &str) -> String {
+    input.replace('\t', "    ")
+}
+
+// Only allocates when the input actually contains a tab.
+pub fn expand_tabs(input: &str) -> Cow<'_, str> {
+    if input.contains('\t') {
+        Cow::Owned(input.replace('\t', "    "))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+// Clamps negative readings to zero, copying the slice only if one is found.
+pub fn clamp_negatives(readings: &[i32]) -> Cow<'_, [i32]> {
+    let mut result = Cow::Borrowed(readings);
+    for (i, reading) in readings.iter().enumerate() {
+        if *reading < 0 {
+            result.to_mut()[i] = 0; // The first call clones the slice into a Vec
+        }
+    }
+    result
+}
+
+// AsRef: accepts String, &String, &str, Box<str> and anything else viewable as &str.
+pub fn shout<S: AsRef<str>>(text: S) -> String {
+    text.as_ref().to_uppercase()
+}
+
+// Borrow: the map owns K, but the caller may look up with any Q that K borrows as.
+pub fn lookup<'m, K, Q, V>(map: &'m HashMap<K, V>, key: &Q) -> Option<&'m V>
+where
+    K: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+{
+    map.get(key)
+}
+
+// A struct that can hold either borrowed or owned text.
+#[derive(Debug)]
+pub struct Message<'a> {
+    pub text: Cow<'a, str>,
+}
+
+impl Message<'_> {
+    // Cutting the tie to the borrowed data gives a Message that may live anywhere.
+    pub fn into_static(self) -> Message<'static> {
+        Message {
+            text: Cow::Owned(self.text.into_owned()),
+        }
+    }
+}
+
+#[allow(clippy::ptr_arg)] // We want the Cow itself, to see which variant it is
+fn kind<B: ?Sized + ToOwned>(cow: &Cow<B>) -> &'static str {
+    match cow {
+        Cow::Borrowed(_) => "Borrowed",
+        Cow::Owned(_) => "Owned",
+    }
+}
+
+pub(crate) fn examples() {
+    // 1) ToOwned always allocates
+    println!(" --------------- lesson 9 example 1 ---------------");
+    {
+        let borrowed: &str = "hello";
+        let (owned, allocations) = count_allocations(|| borrowed.to_owned());
+        println!("&str -> String: {:?}, allocations: {}", owned, allocations);
+
+        let slice: &[i32] = &[1, 2, 3];
+        let (owned, allocations) = count_allocations(|| slice.to_owned());
+        println!(
+            "&[i32] -> Vec<i32>: {:?}, allocations: {}",
+            owned, allocations
+        );
+    }
+
+    // 2) Always owned vs borrowed-or-owned
+    println!(" --------------- lesson 9 example 2 ---------------");
+    {
+        let clean = "no tabs here";
+        let messy = "one\ttab";
+        let (_, allocations) = count_allocations(|| expand_tabs_owned(clean));
+        println!("expand_tabs_owned(clean): allocations: {}", allocations);
+        let (result, allocations) = count_allocations(|| expand_tabs(clean));
+        println!(
+            "expand_tabs(clean): {} {:?}, allocations: {}",
+            kind(&result),
+            result,
+            allocations
+        );
+        let (result, allocations) = count_allocations(|| expand_tabs(messy));
+        println!(
+            "expand_tabs(messy): {} {:?}, allocations: {}",
+            kind(&result),
+            result,
+            allocations
+        );
+    }
+
+    // 3) to_mut clones on the first write only
+    println!(" --------------- lesson 9 example 3 ---------------");
+    {
+        let positive = [1, 2, 3];
+        let mixed = [1, -2, -3];
+        let (result, allocations) = count_allocations(|| clamp_negatives(&positive));
+        println!(
+            "clamp {:?}: {} {:?}, allocations: {}",
+            positive,
+            kind(&result),
+            result,
+            allocations
+        );
+        let (result, allocations) = count_allocations(|| clamp_negatives(&mixed));
+        println!(
+            "clamp {:?}: {} {:?}, allocations: {}",
+            mixed,
+            kind(&result),
+            result,
+            allocations
+        );
+        // Cow only derefs to &str, there is no mutable access without to_mut
+        // let mut cow: Cow<str> = Cow::Borrowed("x");
+        // cow.push_str("y"); // Uncommenting this line will cause a compilation error (E0599)
+    }
+
+    // 4) into_owned: free for Owned, a clone for Borrowed
+    println!(" --------------- lesson 9 example 4 ---------------");
+    {
+        let borrowed = expand_tabs("plain");
+        let owned = expand_tabs("\ttabbed");
+        let (_, allocations) = count_allocations(|| borrowed.into_owned());
+        println!("Borrowed.into_owned(): allocations: {}", allocations);
+        let (_, allocations) = count_allocations(|| owned.into_owned());
+        println!("Owned.into_owned(): allocations: {}", allocations);
+    }
+
+    // 5) Cow in the standard library
+    println!(" --------------- lesson 9 example 5 ---------------");
+    {
+        // Unlike String::from_utf8 in lesson 1, from_utf8_lossy borrows and returns a Cow
+        let valid = vec![104, 101, 108, 108, 111];
+        let invalid = vec![104, 101, 0xFF, 108, 111];
+        let (text, allocations) = count_allocations(|| String::from_utf8_lossy(&valid));
+        println!("{} {:?}, allocations: {}", kind(&text), text, allocations);
+        let (text, allocations) = count_allocations(|| String::from_utf8_lossy(&invalid));
+        println!("{} {:?}, allocations: {}", kind(&text), text, allocations);
+        println!("valid is still ours: {:?}", valid);
+    }
+
+    // 6) Borrow vs AsRef
+    println!(" --------------- lesson 9 example 6 ---------------");
+    {
+        let owned = String::from("as ref");
+        println!("{}", shout(&owned)); // &String
+        println!("{}", shout("literal")); // &str
+        println!("{}", shout(owned)); // String, moved in
+
+        let mut scores: HashMap<String, u32> = HashMap::new();
+        scores.insert(String::from("ferris"), 10);
+        // String: Borrow<str>, so no String has to be built just to search
+        let (found, allocations) = count_allocations(|| lookup(&scores, "ferris").copied());
+        println!("lookup by &str: {:?}, allocations: {}", found, allocations);
+        // scores.get(&42); // Uncommenting this line will cause a compilation error (E0277), String is not Borrow<i32>
+    }
+
+    // 7) Cow in structs
+    println!(" --------------- lesson 9 example 7 ---------------");
+    {
+        let static_message = {
+            let local = String::from("built at runtime");
+            let message = Message {
+                text: Cow::Borrowed(&local),
+            };
+            println!("borrowing: {:?}", message);
+            message.into_static() // Without this, message could not leave the scope of local
+        };
+        let literal = Message {
+            text: Cow::Borrowed("a literal is already 'static"),
+        };
+        println!("owned: {:?}", static_message);
+        println!("literal: {:?}", literal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, CompileFail};
+
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "lesson_9_cow_outlives_source",
+            code: "E0597",
+            source: r#"
+use std::borrow::Cow;
+pub fn demo() -> usize {
+    let cow: Cow<str>;
+    {
+        let local = String::from("built at runtime");
+        cow = Cow::Borrowed(&local);
+    }
+    cow.len()
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_9_cow_has_no_push_str",
+            code: "E0599",
+            source: r#"
+use std::borrow::Cow;
+pub fn demo() {
+    let mut cow: Cow<str> = Cow::Borrowed("x");
+    cow.push_str("y");
+}
+"#,
+        },
+        CompileFail {
+            id: "lesson_9_lookup_without_borrow",
+            code: "E0277",
+            source: r#"
+use std::collections::HashMap;
+pub fn demo() {
+    let mut scores: HashMap<String, u32> = HashMap::new();
+    scores.insert(String::from("ferris"), 10);
+    scores.get(&42);
+}
+"#,
+        },
+    ];
+
+    #[test]
+    fn test_expand_tabs_allocations() {
+        let (result, allocations) = count_allocations(|| expand_tabs("clean"));
+        assert!(matches!(result, Cow::Borrowed("clean")));
+        assert_eq!(0, allocations);
+
+        let (result, allocations) = count_allocations(|| expand_tabs("a\tb"));
+        assert_eq!("a    b", result);
+        assert!(allocations > 0);
+
+        let (_, allocations) = count_allocations(|| expand_tabs_owned("clean"));
+        assert_eq!(1, allocations);
+    }
+
+    #[test]
+    fn test_to_mut_clones_once() {
+        let (result, allocations) = count_allocations(|| clamp_negatives(&[1, 2]));
+        assert_eq!("Borrowed", kind(&result));
+        assert_eq!(0, allocations);
+
+        let (result, allocations) = count_allocations(|| clamp_negatives(&[-1, 2, -3]));
+        assert_eq!(&[0, 2, 0][..], &*result);
+        assert_eq!(1, allocations);
+    }
+
+    #[test]
+    fn test_borrow_and_as_ref() {
+        let mut scores = HashMap::new();
+        scores.insert(String::from("ferris"), 10);
+        let (found, allocations) = count_allocations(|| lookup(&scores, "ferris").copied());
+        assert_eq!(Some(10), found);
+        assert_eq!(0, allocations);
+        assert_eq!("HI", shout("hi"));
+        assert_eq!("HI", shout(String::from("hi")));
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
@@ -0,0 +1,166 @@
+/////////////////////////////////////////////////////////
+// Learn the borrow checker
+/////////////////////////////////////////////////////////
+
+// The lessons and everything that runs, checks and presents them. The binary in main.rs is
+// the command line on top, and the dispatch benchmark uses lesson 17 from here too.
+
+pub mod alloc_counter;
+pub mod classroom;
+#[cfg(test)]
+mod compile_fail;
+pub mod compile_service;
+mod diagnostics;
+mod drop_tracer;
+pub mod exercises;
+pub mod i18n;
+mod json;
+pub mod learning_path;
+mod lesson_10_closures;
+mod lesson_11_iterators;
+mod lesson_12_slices;
+mod lesson_13_pin;
+mod lesson_14_async;
+mod lesson_15_unsafe;
+mod lesson_16_nll;
+pub mod lesson_17_trait_objects;
+mod lesson_1_scope;
+mod lesson_2_drop_cc;
+mod lesson_3_borrow;
+mod lesson_4_bonus;
+mod lesson_5_lifetimes;
+mod lesson_6_shared_ownership;
+mod lesson_7_threads;
+mod lesson_8_interior_mutability;
+mod lesson_9_cow;
+pub mod matrix;
+#[cfg(test)]
+mod miri;
+pub mod ownership_graph;
+pub mod stepper;
+pub mod teaching_types;
+pub mod timeline;
+pub mod trace;
+
+// Every lesson registers its examples here so it can be run on its own with `run <lesson>`.
+// Its introduction and vocabulary are in the message catalogs, see i18n.rs.
+// The source is kept too, for the timelines drawn by `run --visualize`, and the vocabulary
+// terms it builds on, for the order `path` recommends.
+pub struct Lesson {
+    pub id: &'static str,
+    pub examples: fn(),
+    pub source: &'static str,
+    pub requires: &'static [&'static str],
+}
+
+pub const LESSONS: &[Lesson] = &[
+    Lesson {
+        id: "lesson_1_scope",
+        examples: lesson_1_scope::examples,
+        source: include_str!("lesson_1_scope.rs"),
+        requires: &[],
+    },
+    Lesson {
+        id: "lesson_2_drop_cc",
+        examples: lesson_2_drop_cc::examples,
+        source: include_str!("lesson_2_drop_cc.rs"),
+        requires: &["Ownership"],
+    },
+    Lesson {
+        id: "lesson_3_borrow",
+        examples: lesson_3_borrow::examples,
+        source: include_str!("lesson_3_borrow.rs"),
+        requires: &["Ownership", "Lifetime", "Clone", "Copy"],
+    },
+    Lesson {
+        id: "lesson_4_bonus",
+        examples: lesson_4_bonus::examples,
+        source: include_str!("lesson_4_bonus.rs"),
+        requires: &["Borrowing", "Reference", "Box"],
+    },
+    Lesson {
+        id: "lesson_5_lifetimes",
+        examples: lesson_5_lifetimes::examples,
+        source: include_str!("lesson_5_lifetimes.rs"),
+        requires: &["Lifetime", "'a", "&str"],
+    },
+    Lesson {
+        id: "lesson_6_shared_ownership",
+        examples: lesson_6_shared_ownership::examples,
+        source: include_str!("lesson_6_shared_ownership.rs"),
+        requires: &["Ownership", "Reference"],
+    },
+    Lesson {
+        id: "lesson_7_threads",
+        examples: lesson_7_threads::examples,
+        source: include_str!("lesson_7_threads.rs"),
+        requires: &["Arc", "Move"],
+    },
+    Lesson {
+        id: "lesson_8_interior_mutability",
+        examples: lesson_8_interior_mutability::examples,
+        source: include_str!("lesson_8_interior_mutability.rs"),
+        requires: &["Rc", "Ref"],
+    },
+    Lesson {
+        id: "lesson_9_cow",
+        examples: lesson_9_cow::examples,
+        source: include_str!("lesson_9_cow.rs"),
+        requires: &["Clone", "Borrowed", "'a", "&str"],
+    },
+    Lesson {
+        id: "lesson_10_closures",
+        examples: lesson_10_closures::examples,
+        source: include_str!("lesson_10_closures.rs"),
+        requires: &["Borrow", "Mutable Reference", "Box", "Thread"],
+    },
+    Lesson {
+        id: "lesson_11_iterators",
+        examples: lesson_11_iterators::examples,
+        source: include_str!("lesson_11_iterators.rs"),
+        requires: &["Borrowed", "Mutable Reference"],
+    },
+    Lesson {
+        id: "lesson_12_slices",
+        examples: lesson_12_slices::examples,
+        source: include_str!("lesson_12_slices.rs"),
+        requires: &["Borrowed", "Mutable Reference", "'static"],
+    },
+    Lesson {
+        id: "lesson_13_pin",
+        examples: lesson_13_pin::examples,
+        source: include_str!("lesson_13_pin.rs"),
+        requires: &["Box", "Move", "Reference"],
+    },
+    Lesson {
+        id: "lesson_14_async",
+        examples: lesson_14_async::examples,
+        source: include_str!("lesson_14_async.rs"),
+        requires: &[
+            "Borrowing",
+            "'static",
+            "Send",
+            "Scoped Thread",
+            "Interior Mutability",
+            "Pin",
+        ],
+    },
+    Lesson {
+        id: "lesson_15_unsafe",
+        examples: lesson_15_unsafe::examples,
+        source: include_str!("lesson_15_unsafe.rs"),
+        requires: &["Box", "'static", "Deref"],
+    },
+    Lesson {
+        id: "lesson_16_nll",
+        examples: lesson_16_nll::examples,
+        source: include_str!("lesson_16_nll.rs"),
+        requires: &["Drop", "Mutable Reference"],
+    },
+    Lesson {
+        id: "lesson_17_trait_objects",
+        examples: lesson_17_trait_objects::examples,
+        source: include_str!("lesson_17_trait_objects.rs"),
+        requires: &["Trait", "Clone", "Copy", "Box", "'a", "'static", "Rc"],
+    },
+];
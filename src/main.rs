@@ -1,15 +1,537 @@
-mod lesson_1_scope;
-mod lesson_2_drop_cc;
-mod lesson_3_borrow;
-mod lesson_4_bonus;
-
+use learn_borrow_checker::{
+    alloc_counter::CountingAllocator, classroom, compile_service, exercises, i18n, learning_path,
+    matrix, ownership_graph, stepper, timeline, trace, Lesson, LESSONS,
+};
 use rand::Rng;
 
+// Lessons 4 and 9 count the heap allocations their examples make.
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
 fn main() {
-    lesson_1_scope::examples();
-    lesson_2_drop_cc::examples();
-    lesson_3_borrow::examples();
-    lesson_4_bonus::examples();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let visualize = args.iter().any(|arg| arg == "--visualize");
+    let mut args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--visualize")
+        .collect();
+    let messages = match args.iter().position(|arg| *arg == "--lang") {
+        Some(index) if index + 1 < args.len() => {
+            let language = args.remove(index + 1);
+            args.remove(index);
+            Some(messages_for(language))
+        }
+        Some(_) => {
+            print_usage();
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let prose = messages.as_ref();
+    match args.first() {
+        None => run_all(visualize, prose),
+        Some(&"run") => match args.get(1) {
+            None => run_all(visualize, prose),
+            Some(name) => run(lesson_or_exit(name), visualize, prose),
+        },
+        Some(&"graph") => match (args.get(1), args.get(2), args.get(3)) {
+            (Some(name), None, None) => graph(lesson_or_exit(name), None),
+            (Some(name), Some(&"--out"), Some(dir)) => {
+                graph(lesson_or_exit(name), Some(std::path::Path::new(dir)))
+            }
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        },
+        Some(&"step") => match (args.get(1), args.get(2), args.get(3)) {
+            (Some(&"--trace"), Some(file), None) => replay(std::path::Path::new(file)),
+            (Some(name), Some(example), None) => step(lesson_or_exit(name), example),
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        },
+        Some(&"trace") => match (args.get(1), args.get(2), args.get(3), args.get(4)) {
+            (Some(name), Some(example), None, None) => trace(lesson_or_exit(name), example, None),
+            (Some(name), Some(example), Some(&"--out"), Some(file)) => trace(
+                lesson_or_exit(name),
+                example,
+                Some(std::path::Path::new(file)),
+            ),
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        },
+        // Not in the usage, trace and the other commands run it in a child process.
+        Some(&"record") => match (args.get(1), args.get(2), args.get(3)) {
+            (Some(name), Some(dir), None) => {
+                save_recording(lesson_or_exit(name), std::path::Path::new(dir))
+            }
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        },
+        Some(&"serve") => serve(&args[1..]),
+        Some(&"matrix") if args.len() == 1 => matrix(),
+        Some(&"path") => match (args.get(1), args.get(2), args.get(3)) {
+            (None, None, None) => path(None, None, prose),
+            (Some(&"--done"), Some(done), None) => path(None, Some(done), prose),
+            (Some(target), None, None) => path(Some(target), None, prose),
+            (Some(target), Some(&"--done"), Some(done)) => path(Some(target), Some(done), prose),
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        },
+        Some(_) => {
+            print_usage();
+            std::process::exit(2);
+        }
+    }
+}
+
+// Records one example and steps through it, showing its output a line at a time. When the
+// example traces its values the steps follow the trace, otherwise its timeline.
+fn step(lesson: &Lesson, number: &str) {
+    let example = example_or_exit(lesson, number);
+    let recorded = record_lesson(lesson);
+    let lines = stepper::example_output(&recorded.output, &example.title);
+    let base = stepper::Recording::from_timeline(&example, lines);
+    let trace = trace::Trace::of_example(lesson.id, lesson.source, &example.title, recorded.events);
+    let recording = if trace.events.is_empty() {
+        base
+    } else {
+        stepper::Recording::from_trace(&base, &trace, lesson.source)
+    };
+    if let Err(error) = stepper::present(&recording) {
+        eprintln!("Unable to step through {}: {}", recording.title, error);
+        std::process::exit(1);
+    }
+}
+
+// Steps through a trace saved by `trace --out`, over the recording of its example.
+fn replay(path: &std::path::Path) {
+    let trace = std::fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| trace::Trace::parse(&text).map_err(|error| error.to_string()));
+    let trace = trace.unwrap_or_else(|error| {
+        eprintln!("Unable to read the trace in {}: {}", path.display(), error);
+        std::process::exit(1);
+    });
+    let lesson = lesson_or_exit(&trace.lesson);
+    let Some(example) = timeline::examples(lesson.source)
+        .into_iter()
+        .find(|example| example.title == trace.example)
+    else {
+        eprintln!("{} has no {}", lesson.id, trace.example);
+        std::process::exit(2);
+    };
+    let recording =
+        stepper::Recording::from_trace(&record(lesson, &example), &trace, lesson.source);
+    if let Err(error) = stepper::present(&recording) {
+        eprintln!("Unable to step through {}: {}", recording.title, error);
+        std::process::exit(1);
+    }
+}
+
+// Runs the lesson and prints the trace of one of its examples, or writes it to a file. The
+// lesson runs in a child process, so what it prints stays out of the trace.
+fn trace(lesson: &Lesson, number: &str, out: Option<&std::path::Path>) {
+    let example = example_or_exit(lesson, number);
+    let recorded = record_lesson(lesson);
+    let trace = trace::Trace::of_example(lesson.id, lesson.source, &example.title, recorded.events);
+    if trace.events.is_empty() {
+        println!("{} records no trace", example.title);
+        return;
+    }
+    match out {
+        Some(path) => {
+            write_or_exit(path, &trace.to_string());
+            println!("wrote {}", path.display());
+        }
+        None => {
+            println!(" --------------- {} trace ---------------", example.title);
+            for event in &trace.events {
+                println!("{:>4}  {}", event.line, event);
+            }
+        }
+    }
+}
+
+fn example_or_exit<'s>(lesson: &'s Lesson, number: &str) -> timeline::Example<'s> {
+    let mut examples = timeline::examples(lesson.source);
+    let suffix = format!(" example {}", number);
+    match examples.iter().position(|e| e.title.ends_with(&suffix)) {
+        Some(index) => examples.swap_remove(index),
+        None => {
+            eprintln!("{} has no example {}, it has:", lesson.id, number);
+            for example in &examples {
+                eprintln!("  {}", example.title);
+            }
+            std::process::exit(2);
+        }
+    }
+}
+
+// Records an example from its timeline and the output it printed. The lesson runs in a
+// child process of this binary, so that output can be captured.
+fn record(lesson: &Lesson, example: &timeline::Example) -> stepper::Recording {
+    let output = lesson_output(lesson);
+    let lines = stepper::example_output(&output, &example.title);
+    stepper::Recording::from_timeline(example, lines)
+}
+
+// What the lesson prints when run on its own, from a child process of this binary.
+fn lesson_output(lesson: &Lesson) -> String {
+    let output = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .args(["run", lesson.id])
+            .output()
+    });
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(error) => {
+            eprintln!("Unable to run {}: {}", lesson.id, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// What a lesson printed, the events it traced and the ownership snapshots it took, as DOT,
+// from a child process of this binary.
+struct Recorded {
+    output: String,
+    events: Vec<trace::Event>,
+    graphs: Vec<String>,
+}
+
+fn record_lesson(lesson: &Lesson) -> Recorded {
+    let dir = std::env::temp_dir().join(format!(
+        "learn-borrow-checker-{}-{}",
+        lesson.id,
+        std::process::id()
+    ));
+    let output = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .arg("record")
+            .arg(lesson.id)
+            .arg(&dir)
+            .stderr(std::process::Stdio::null())
+            .output()
+    });
+    let output = match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(error) => {
+            eprintln!("Unable to run {}: {}", lesson.id, error);
+            std::process::exit(1);
+        }
+    };
+    let trace = std::fs::read_to_string(dir.join("trace"))
+        .map_err(|error| error.to_string())
+        .and_then(|text| trace::Trace::parse(&text).map_err(|error| error.to_string()));
+    let graphs = (1..)
+        .map_while(|number| std::fs::read_to_string(dir.join(format!("{}.dot", number))).ok())
+        .collect();
+    let _ = std::fs::remove_dir_all(&dir);
+    match trace {
+        Ok(trace) => Recorded {
+            output,
+            events: trace.events,
+            graphs,
+        },
+        Err(error) => {
+            eprintln!("Unable to record {}: {}", lesson.id, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// The child's side of record_lesson: runs the lesson and saves what it traced in dir, and
+// each snapshot it took in a numbered DOT file.
+fn save_recording(lesson: &Lesson, dir: &std::path::Path) {
+    trace::take_events();
+    ownership_graph::start_recording();
+    (lesson.examples)();
+    let trace = trace::Trace {
+        lesson: lesson.id.to_string(),
+        example: String::new(),
+        events: trace::take_events(),
+    };
+    if let Err(error) = std::fs::create_dir_all(dir) {
+        eprintln!("Unable to create {}: {}", dir.display(), error);
+        std::process::exit(1);
+    }
+    write_or_exit(&dir.join("trace"), &trace.to_string());
+    for (index, graph) in ownership_graph::take_recorded().iter().enumerate() {
+        write_or_exit(&dir.join(format!("{}.dot", index + 1)), &graph.to_dot());
+    }
+}
+
+// Languages without a catalog fall back to English, which every lesson has.
+fn messages_for(language: &str) -> i18n::Messages {
+    let languages = i18n::languages();
+    if !languages.contains(&language) {
+        eprintln!(
+            "There is no {} translation, showing English. Languages: {}",
+            language,
+            languages.join(", ")
+        );
+    }
+    i18n::Messages::new(language)
+}
+
+// Serves the lessons and exercises to the local network, see classroom.rs.
+fn serve(options: &[&str]) {
+    let mut port: u16 = 8080;
+    let mut edition = compile_service::Edition::E2021;
+    for pair in options.chunks(2) {
+        let parsed = match pair {
+            ["--port", value] => value.parse().map(|p| port = p).map_err(|_| ()),
+            ["--edition", value] => value.parse().map(|e| edition = e).map_err(|_| ()),
+            _ => Err(()),
+        };
+        if parsed.is_err() {
+            print_usage();
+            std::process::exit(2);
+        }
+    }
+    let listener = match std::net::TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("Unable to listen on port {}: {}", port, error);
+            std::process::exit(1);
+        }
+    };
+    let lessons = LESSONS.iter().map(|l| (l.id, l.source)).collect();
+    eprintln!(
+        "Warning: the server listens on every network interface, so anyone on this network \
+         can read the lessons and have code compiled on this machine."
+    );
+    match classroom::lan_address() {
+        Some(address) => println!("Attendees can open http://{}:{}/", address, port),
+        None => println!("Serving on port {}, open http://localhost:{}/", port, port),
+    }
+    println!("The leaderboard is at /leaderboard. Stop the server with Ctrl-C.");
+    if let Err(error) = classroom::serve(listener, classroom::Classroom::new(lessons, edition)) {
+        eprintln!("The server stopped: {}", error);
+        std::process::exit(1);
+    }
+}
+
+// Checks the lessons' claims with every installed toolchain in every edition, see matrix.rs.
+// It builds this crate from its sources, so it is run from a checkout.
+fn matrix() {
+    let lessons: Vec<&str> = LESSONS.iter().map(|lesson| lesson.id).collect();
+    match matrix::run(std::path::Path::new(env!("CARGO_MANIFEST_DIR")), &lessons) {
+        Ok(matrix) => print!("{}", matrix),
+        Err(error) => {
+            eprintln!("Unable to run the matrix: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Prints the order to take the lessons and exercises in, or with a target the lessons it
+// builds on. --done takes the lessons and exercises a learner has done, separated by commas,
+// marks them and warns about any done ahead of what they build on.
+fn path(target: Option<&str>, done: Option<&str>, prose: Option<&i18n::Messages>) {
+    let lessons: Vec<_> = LESSONS.iter().map(|l| (l.id, l.requires)).collect();
+    let path = match learning_path::Path::new(&lessons, exercises::EXERCISES) {
+        Ok(path) => path,
+        Err(error) => {
+            eprintln!("The prerequisites do not make a path: {}", error);
+            std::process::exit(1);
+        }
+    };
+    let step_or_exit = |name: &str| match find_lesson(name)
+        .map(|l| l.id)
+        .or_else(|| exercises::find(name).map(|exercise| exercise.id))
+    {
+        Some(id) => id,
+        None => {
+            eprintln!("Unknown lesson or exercise: {}", name);
+            print_usage();
+            std::process::exit(2);
+        }
+    };
+    let done: Vec<&str> = done
+        .map(|done| {
+            done.split(',')
+                .map(|name| step_or_exit(name.trim()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let english = i18n::Messages::new(i18n::ENGLISH);
+    let messages = prose.unwrap_or(&english);
+    let target = target.map(step_or_exit);
+    let shown: Vec<&learning_path::Step> = match target {
+        Some(target) => {
+            let mut ids = path.before(target);
+            ids.push(target);
+            println!("The way to {}:", target);
+            ids.iter().filter_map(|id| path.get(id)).collect()
+        }
+        None => {
+            println!("Recommended order:");
+            path.steps.iter().collect()
+        }
+    };
+    for (index, step) in shown.iter().enumerate() {
+        let title = match step.kind {
+            learning_path::Kind::Lesson => messages.lesson_title(step.id),
+            learning_path::Kind::Exercise => {
+                exercises::find(step.id).map(|exercise| format!("Exercise: {}", exercise.title))
+            }
+        };
+        let mark = if done.contains(&step.id) { "done" } else { "" };
+        println!(
+            "{:>4}. {:<4} {:<30} {}",
+            index + 1,
+            mark,
+            step.id,
+            title.unwrap_or_default()
+        );
+        let after: Vec<String> = step
+            .after
+            .iter()
+            .map(|(lesson, terms)| match &terms[..] {
+                [] => lesson.to_string(),
+                terms => format!("{} ({})", lesson, terms.join(", ")),
+            })
+            .collect();
+        if !after.is_empty() {
+            println!("{:>11}after {}", "", after.join(", "));
+        }
+    }
+    for (step, missing) in path.jumps(&done) {
+        println!(
+            "Jumping ahead: {} builds on {}, not done yet",
+            step,
+            missing.join(", ")
+        );
+    }
+    if !done.is_empty() {
+        match path.next(target, &done) {
+            Some(step) => println!("Next: {}", step.id),
+            None => println!("Everything is done."),
+        }
+    }
+    for early in path.early_uses(exercises::EXERCISES) {
+        println!("Warning: {}", early);
+    }
+}
+
+fn lesson_or_exit(name: &str) -> &'static Lesson {
+    find_lesson(name).unwrap_or_else(|| {
+        eprintln!("Unknown lesson: {}", name);
+        print_usage();
+        std::process::exit(2);
+    })
+}
+
+// Runs the lesson, then renders the ownership snapshots its examples took. They are printed
+// as DOT, or with --out written to <dir>/<lesson>_<n>.dot, plus .svg when Graphviz is installed.
+fn graph(lesson: &Lesson, out: Option<&std::path::Path>) {
+    let graphs = record_lesson(lesson).graphs;
+    if graphs.is_empty() {
+        eprintln!("{} takes no ownership snapshots", lesson.id);
+        return;
+    }
+    // Only the graphs go to stdout, so it can be redirected to a .dot file for Graphviz.
+    let Some(out) = out else {
+        for dot in &graphs {
+            print!("{}", dot);
+        }
+        return;
+    };
+    if let Err(error) = std::fs::create_dir_all(out) {
+        eprintln!("Unable to create {}: {}", out.display(), error);
+        std::process::exit(1);
+    }
+    let mut svg_missing = false;
+    for (index, dot) in graphs.iter().enumerate() {
+        let path = out.join(format!("{}_{}", lesson.id, index + 1));
+        let mut written = vec![path.with_extension("dot")];
+        match ownership_graph::to_svg(dot) {
+            Some(svg) => {
+                written.push(path.with_extension("svg"));
+                write_or_exit(&written[1], &svg);
+            }
+            None => svg_missing = true,
+        }
+        write_or_exit(&written[0], dot);
+        for path in written {
+            println!("wrote {}", path.display());
+        }
+    }
+    if svg_missing {
+        println!("Graphviz `dot` was not found, so only DOT files were written");
+    }
+}
+
+fn write_or_exit(path: &std::path::Path, contents: &str) {
+    if let Err(error) = std::fs::write(path, contents) {
+        eprintln!("Unable to write {}: {}", path.display(), error);
+        std::process::exit(1);
+    }
+}
+
+fn run_all(visualize: bool, prose: Option<&i18n::Messages>) {
+    LESSONS
+        .iter()
+        .for_each(|lesson| run(lesson, visualize, prose));
+}
+
+// With --visualize, each example's output is followed by its borrow timeline. With --lang,
+// the lesson is preceded by its introduction and vocabulary in that language, and the example
+// banners carry their headings. Either way the lesson runs in a child process, so its output
+// can be cut at the banners.
+fn run(lesson: &Lesson, visualize: bool, prose: Option<&i18n::Messages>) {
+    if prose.is_none() && !visualize {
+        return (lesson.examples)();
+    }
+    if let Some(messages) = prose {
+        print!("{}", messages.prose(lesson.id));
+    }
+    let mut output = lesson_output(lesson);
+    if visualize {
+        println!("{}", timeline::LEGEND);
+        output = timeline::interleave(lesson.source, &output);
+    }
+    if let Some(messages) = prose {
+        output = messages.localize(lesson.id, &output);
+    }
+    print!("{}", output);
+}
+
+// Lessons can be named in full (lesson_3_borrow) or by number (3).
+fn find_lesson(name: &str) -> Option<&'static Lesson> {
+    let prefix = format!("lesson_{}_", name);
+    LESSONS
+        .iter()
+        .find(|lesson| lesson.id == name || lesson.id.starts_with(&prefix))
+}
+
+fn print_usage() {
+    eprintln!("Usage: learn-borrow-checker [run [lesson] [--visualize] [--lang <language>]]");
+    eprintln!("       learn-borrow-checker graph <lesson> [--out <dir>]");
+    eprintln!("       learn-borrow-checker step <lesson> <example>");
+    eprintln!("       learn-borrow-checker step --trace <file>");
+    eprintln!("       learn-borrow-checker trace <lesson> <example> [--out <file>]");
+    eprintln!("       learn-borrow-checker serve [--port <port>] [--edition <year>]");
+    eprintln!("       learn-borrow-checker matrix");
+    eprintln!(
+        "       learn-borrow-checker path [<lesson or exercise>] [--done <lessons,exercises>]"
+    );
+    eprintln!("Languages: {}", i18n::languages().join(", "));
+    eprintln!("Lessons:");
+    for lesson in LESSONS {
+        eprintln!("  {}", lesson.id);
+    }
 }
 
 fn _pick_random(x: u32) -> u32 {
@@ -0,0 +1,541 @@
+/////////////////////////////////////////////////////////
+// Edition and toolchain matrix
+/////////////////////////////////////////////////////////
+
+// What the borrow checker accepts has changed over the years: non-lexical lifetimes came with
+// Rust 2018, closures capture disjoint fields since 2021, and 2024 drops the temporaries of a
+// block's tail expression sooner. `matrix` checks the lessons' claims on every toolchain rustup
+// has installed, in every edition, so a lesson can say "since Rust 2021" and be right.
+//
+// Two suites make up the claims. The examples, meaning each lesson's unit tests, are run with
+// the crate copied and its edition changed, once per toolchain and edition. When a copy does
+// not build, none of its tests ran, so every lesson is marked as not building in that edition
+// whichever file the errors point into. The compile-fail
+// snippets are checked by running the lessons' compile-fail tests with REPORT set: the harness
+// then compiles every snippet in every edition and writes down whether its claim held, where it
+// would normally assert on the one edition the lesson names.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::compile_service::Edition;
+use crate::diagnostics::{Diagnostic, Level};
+
+/// Names the file the compile-fail harness writes its results to instead of asserting.
+pub const REPORT: &str = "LEARN_BORROW_CHECKER_MATRIX";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Holds,
+    Fails,
+    /// The crate did not compile in this edition, so the lesson's examples never ran.
+    NotBuilt,
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Cell::Holds => "holds",
+            Cell::Fails => "fails",
+            Cell::NotBuilt => "no build",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// One claim, and how it fared under each toolchain and edition it was checked with.
+#[derive(Debug, PartialEq, Eq)]
+struct Row {
+    claim: String,
+    cells: Vec<(usize, Edition, Cell)>,
+}
+
+impl Row {
+    fn cell(&self, toolchain: usize, edition: Edition) -> Option<Cell> {
+        self.cells
+            .iter()
+            .find(|(t, e, _)| *t == toolchain && *e == edition)
+            .map(|(_, _, cell)| *cell)
+    }
+
+    // Which editions the claim holds in, for the rows that failed somewhere. An edition
+    // counts when the claim held on every toolchain that checked it.
+    fn summary(&self, toolchains: usize) -> Option<String> {
+        if self.cells.iter().all(|(_, _, cell)| *cell == Cell::Holds) {
+            return None;
+        }
+        let mut holds = Vec::new();
+        let mut unknown = Vec::new();
+        let mut varies = Vec::new();
+        for edition in Edition::ALL {
+            let cells: Vec<Cell> = (0..toolchains)
+                .filter_map(|t| self.cell(t, edition))
+                .collect();
+            if cells.is_empty() {
+                unknown.push(edition);
+            } else if cells.iter().all(|cell| *cell == Cell::Holds) {
+                holds.push(edition);
+            } else if cells.contains(&Cell::Holds) {
+                varies.push(edition);
+            }
+        }
+        let list = |editions: &[Edition]| {
+            let years: Vec<String> = editions.iter().map(Edition::to_string).collect();
+            years.join(", ")
+        };
+        let mut summary = if holds.is_empty() {
+            String::from("holds in no edition")
+        } else if unknown.is_empty() && Edition::ALL.ends_with(&holds) {
+            format!("since Rust {}", holds[0])
+        } else if unknown.is_empty() && Edition::ALL.starts_with(&holds) {
+            format!("until Rust {}", holds[holds.len() - 1])
+        } else {
+            format!("holds in {}", list(&holds))
+        };
+        if !varies.is_empty() {
+            summary += &format!("; depends on the toolchain in {}", list(&varies));
+        }
+        if !unknown.is_empty() {
+            summary += &format!("; not checked in {}", list(&unknown));
+        }
+        Some(summary)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Matrix {
+    /// The toolchains by the name rustup knows them, without the host triple.
+    pub toolchains: Vec<String>,
+    rows: Vec<Row>,
+}
+
+impl Matrix {
+    pub fn record(&mut self, claim: &str, toolchain: usize, edition: Edition, cell: Cell) {
+        let index = match self.rows.iter().position(|row| row.claim == claim) {
+            Some(index) => index,
+            None => {
+                self.rows.push(Row {
+                    claim: claim.to_string(),
+                    cells: Vec::new(),
+                });
+                self.rows.len() - 1
+            }
+        };
+        let row = &mut self.rows[index];
+        row.cells
+            .retain(|(t, e, _)| (*t, *e) != (toolchain, edition));
+        row.cells.push((toolchain, edition, cell));
+    }
+}
+
+// A table per toolchain, then the claims that only hold in some editions.
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.claim.len())
+            .max()
+            .unwrap_or(0);
+        for (t, toolchain) in self.toolchains.iter().enumerate() {
+            writeln!(f, " --------------- {} ---------------", toolchain)?;
+            let mut header = format!("{:width$}", "");
+            for edition in Edition::ALL {
+                header += &format!("  {:<8}", edition.to_string());
+            }
+            writeln!(f, "{}", header.trim_end())?;
+            for row in &self.rows {
+                let mut line = format!("{:width$}", row.claim);
+                for edition in Edition::ALL {
+                    let cell = row
+                        .cell(t, edition)
+                        .map_or("-".to_string(), |c| c.to_string());
+                    line += &format!("  {:<8}", cell);
+                }
+                writeln!(f, "{}", line.trim_end())?;
+            }
+            writeln!(f)?;
+        }
+        let summaries: Vec<(&str, String)> = self
+            .rows
+            .iter()
+            .filter_map(|row| Some((row.claim.as_str(), row.summary(self.toolchains.len())?)))
+            .collect();
+        if summaries.is_empty() {
+            return writeln!(f, "Every claim holds wherever it was checked.");
+        }
+        writeln!(f, "Claims that do not hold everywhere:")?;
+        for (claim, summary) in summaries {
+            writeln!(f, "  {}: {}", claim, summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every lesson in every edition with every installed toolchain. Takes a while, and
+/// says what it is doing on stderr meanwhile.
+pub fn run(project: &Path, lessons: &[&str]) -> io::Result<Matrix> {
+    let toolchains = installed_toolchains()?;
+    let host = host()?;
+    let mut matrix = Matrix::default();
+    let copy = std::env::temp_dir().join(format!(
+        "learn-borrow-checker-matrix-{}",
+        std::process::id()
+    ));
+    for (t, toolchain) in toolchains.iter().enumerate() {
+        let suffix = format!("-{}", host);
+        matrix.toolchains.push(
+            toolchain
+                .strip_suffix(&suffix)
+                .unwrap_or(toolchain)
+                .to_string(),
+        );
+        // Builds for this toolchain share a target dir, so the dependencies build once.
+        let target = project.join("target").join("matrix").join(toolchain);
+        for edition in Edition::ALL {
+            eprintln!(
+                "Running the examples with {} in Rust {}",
+                toolchain, edition
+            );
+            copy_crate(project, &copy, edition)?;
+            let output = cargo(toolchain, &target)
+                .args(["test", "--offline", "--lib", "--message-format=json"])
+                .args(["--", "--skip", "test_compile"])
+                .current_dir(&copy)
+                .stderr(Stdio::null())
+                .output()?;
+            for (lesson, cell) in examples(&String::from_utf8_lossy(&output.stdout), lessons) {
+                matrix.record(&format!("{} examples", lesson), t, edition, cell);
+            }
+        }
+        eprintln!("Checking the compile-fail snippets with {}", toolchain);
+        let report = copy.join("matrix-report");
+        let _ = fs::remove_file(&report);
+        cargo(toolchain, &target)
+            .args(["test", "--offline", "--lib", "test_compile"])
+            .env(REPORT, &report)
+            .current_dir(project)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        let mut claims = claims(&fs::read_to_string(&report).unwrap_or_default());
+        claims.sort_by(|a, b| a.0.cmp(&b.0));
+        for (claim, edition, cell) in claims {
+            matrix.record(&claim, t, edition, cell);
+        }
+    }
+    let _ = fs::remove_dir_all(&copy);
+    Ok(matrix)
+}
+
+// These two are for the compile-fail harness, which is test only.
+
+/// True in the harness when it runs for the matrix rather than as a test.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn recording() -> bool {
+    std::env::var_os(REPORT).is_some()
+}
+
+/// Writes down, for the matrix, whether a compile-fail snippet's claim held in an edition.
+/// `claim` says what the snippet claims, such as "E0502" or "compiles".
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn record(id: &str, claim: &str, edition: Edition, holds: bool) {
+    let Some(path) = std::env::var_os(REPORT) else {
+        return;
+    };
+    // The tests run in parallel, and appending one whole line at a time keeps them apart.
+    let line = format!("{} {}\t{}\t{}\n", id, claim, edition, holds);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(error) = written {
+        panic!(
+            "Unable to write to {}: {}",
+            PathBuf::from(path).display(),
+            error
+        );
+    }
+}
+
+// The lines record() wrote. Lesson 16 checks the same snippets once per edition, so a claim
+// can be reported more than once.
+fn claims(report: &str) -> Vec<(String, Edition, Cell)> {
+    let unique: BTreeSet<&str> = report.lines().collect();
+    unique
+        .into_iter()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let claim = fields.next()?.to_string();
+            let edition = fields.next()?.parse().ok()?;
+            let cell = match fields.next()? {
+                "true" => Cell::Holds,
+                _ => Cell::Fails,
+            };
+            Some((claim, edition, cell))
+        })
+        .collect()
+}
+
+// How each lesson's examples fared, from the output of `cargo test --message-format=json`:
+// compiler messages as JSON, then the test harness's "test <name> ... ok" lines. When there
+// is a compile error, or no test ran at all, the crate did not build and every lesson is
+// NotBuilt. Otherwise the lessons without tests are left out.
+fn examples(output: &str, lessons: &[&str]) -> Vec<(String, Cell)> {
+    let mut built = true;
+    let mut results: Vec<(&str, Cell)> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with('{') {
+            if Diagnostic::from_json(line).is_some_and(|d| d.level == Level::Error) {
+                built = false;
+            }
+        } else if let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|test| test.split_once(" ... "))
+        {
+            let module = name.split("::").next().unwrap_or(name);
+            let cell = match result {
+                "ok" => Cell::Holds,
+                "ignored" => continue,
+                _ => Cell::Fails,
+            };
+            match results.iter_mut().find(|(m, _)| *m == module) {
+                Some((_, seen)) if cell == Cell::Fails => *seen = Cell::Fails,
+                Some(_) => {}
+                None => results.push((module, cell)),
+            }
+        }
+    }
+    if !built || results.is_empty() {
+        return lessons
+            .iter()
+            .map(|lesson| (lesson.to_string(), Cell::NotBuilt))
+            .collect();
+    }
+    lessons
+        .iter()
+        .filter_map(|lesson| {
+            let (_, cell) = results.iter().find(|(m, _)| m == lesson)?;
+            Some((lesson.to_string(), *cell))
+        })
+        .collect()
+}
+
+fn cargo(toolchain: &str, target: &Path) -> Command {
+    let mut command = Command::new("cargo");
+    command
+        .env("RUSTUP_TOOLCHAIN", toolchain)
+        .env("CARGO_TARGET_DIR", target);
+    command
+}
+
+fn installed_toolchains() -> io::Result<Vec<String>> {
+    let output = Command::new("rustup")
+        .args(["toolchain", "list"])
+        .output()?;
+    let list = String::from_utf8_lossy(&output.stdout);
+    let toolchains: Vec<String> = list
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect();
+    if !output.status.success() || toolchains.is_empty() {
+        return Err(io::Error::other("rustup lists no toolchains"));
+    }
+    Ok(toolchains)
+}
+
+// Such as x86_64-unknown-linux-gnu, which rustup appends to the toolchains' names.
+fn host() -> io::Result<String> {
+    let output = Command::new("rustc").arg("-vV").output()?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+        .ok_or_else(|| io::Error::other("rustc -vV names no host"))
+}
+
+// Just what `cargo test --lib` needs, with the edition in the manifest changed.
+fn copy_crate(project: &Path, copy: &Path, edition: Edition) -> io::Result<()> {
+    let _ = fs::remove_dir_all(copy);
+    for dir in ["src", "benches", "locales"] {
+        copy_dir(&project.join(dir), &copy.join(dir))?;
+    }
+    fs::copy(project.join("Cargo.lock"), copy.join("Cargo.lock"))?;
+    let manifest: Vec<String> = fs::read_to_string(project.join("Cargo.toml"))?
+        .lines()
+        .map(|line| {
+            if line.starts_with("edition = ") {
+                format!("edition = \"{}\"", edition)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    fs::write(copy.join("Cargo.toml"), manifest.join("\n") + "\n")
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_from_cargo_output() {
+        let output = [
+            r#"{"reason":"compiler-artifact","package_id":"rand"}"#,
+            "test lesson_1_scope::tests::test_a ... ok",
+            "test lesson_1_scope::tests::test_b ... FAILED",
+            "test lesson_3_borrow::tests::test_a ... ok",
+            "test lesson_3_borrow::tests::test_b ... ignored",
+            "test timeline::tests::test_a ... ok",
+        ]
+        .join("\n");
+        let lessons = [
+            "lesson_1_scope",
+            "lesson_3_borrow",
+            "lesson_7_threads",
+            "lesson_10_closures",
+        ];
+        assert_eq!(
+            vec![
+                (String::from("lesson_1_scope"), Cell::Fails),
+                (String::from("lesson_3_borrow"), Cell::Holds),
+            ],
+            examples(&output, &lessons)
+        );
+    }
+
+    #[test]
+    fn test_examples_when_the_copy_does_not_build() {
+        let error = r#"{"reason":"compiler-message","message":{"$message_type":"diagnostic","message":"couldn't read `src/../locales/en.txt`","code":null,"level":"error","spans":[{"file_name":"src/i18n.rs","byte_start":1,"byte_end":2,"line_start":27,"line_end":27,"column_start":5,"column_end":27,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[],"rendered":""}}"#;
+        let lessons = ["lesson_1_scope", "lesson_10_closures"];
+        let not_built = vec![
+            (String::from("lesson_1_scope"), Cell::NotBuilt),
+            (String::from("lesson_10_closures"), Cell::NotBuilt),
+        ];
+        assert_eq!(not_built, examples(error, &lessons));
+        // Nothing ran, such as when cargo could not start the build at all.
+        assert_eq!(not_built, examples("", &lessons));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Miri cannot start cargo
+    fn test_crate_copy_builds() {
+        let project = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let copy = std::env::temp_dir().join(format!(
+            "learn-borrow-checker-matrix-test-{}",
+            std::process::id()
+        ));
+        copy_crate(project, &copy, Edition::E2021).expect("Unable to copy the crate");
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
+        let output = Command::new(cargo)
+            .args(["check", "--offline", "--lib", "--tests"])
+            .env(
+                "CARGO_TARGET_DIR",
+                project.join("target").join("matrix-test"),
+            )
+            .current_dir(&copy)
+            .output()
+            .expect("Unable to run cargo");
+        let _ = fs::remove_dir_all(&copy);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn test_claims_are_read_once() {
+        let report = "lesson_16_a compiles\t2015\tfalse\n\
+                      lesson_16_a compiles\t2018\ttrue\n\
+                      lesson_16_a compiles\t2018\ttrue\n\
+                      garbage\n";
+        assert_eq!(
+            vec![
+                (
+                    String::from("lesson_16_a compiles"),
+                    Edition::E2015,
+                    Cell::Fails
+                ),
+                (
+                    String::from("lesson_16_a compiles"),
+                    Edition::E2018,
+                    Cell::Holds
+                ),
+            ],
+            claims(report)
+        );
+    }
+
+    #[test]
+    fn test_summary_names_the_first_edition() {
+        let mut matrix = Matrix {
+            toolchains: vec![String::from("stable"), String::from("nightly")],
+            ..Matrix::default()
+        };
+        for t in 0..2 {
+            for edition in Edition::ALL {
+                let capture = match edition {
+                    Edition::E2015 | Edition::E2018 => Cell::Fails,
+                    _ => Cell::Holds,
+                };
+                matrix.record("disjoint capture", t, edition, capture);
+                matrix.record("everywhere", t, edition, Cell::Holds);
+                let tail = match (t, edition) {
+                    (1, Edition::E2024) => Cell::Fails,
+                    _ => Cell::Holds,
+                };
+                matrix.record("tail temporaries", t, edition, tail);
+            }
+        }
+        matrix.record("examples", 0, Edition::E2021, Cell::Holds);
+        matrix.record("examples", 0, Edition::E2015, Cell::NotBuilt);
+
+        let text = matrix.to_string();
+        assert!(
+            text.contains(" --------------- nightly ---------------\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("\ndisjoint capture  fails     fails     holds     holds\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("\nexamples          no build  -         holds     -\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.ends_with(
+                "Claims that do not hold everywhere:\n  \
+             disjoint capture: since Rust 2021\n  \
+             tail temporaries: until Rust 2021; depends on the toolchain in 2024\n  \
+             examples: holds in 2021; not checked in 2018, 2024\n"
+            ),
+            "{}",
+            text
+        );
+    }
+}
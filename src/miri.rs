@@ -0,0 +1,108 @@
+/////////////////////////////////////////////////////////
+// Miri harness
+/////////////////////////////////////////////////////////
+
+// The borrow checker cannot see inside unsafe blocks, so the unsafe lesson backs up its
+// claims with Miri, an interpreter that reports undefined behavior as it happens. Miri is
+// a nightly component that many machines do not have. When it is missing these helpers
+// print why they are skipping and return, so the test suite still passes everywhere.
+// To run the checks: rustup +nightly component add miri
+
+use std::fs;
+use std::process::Command;
+
+use crate::compile_fail::scratch_dir;
+
+/// A program that compiles, but that Miri must stop with "Undefined Behavior".
+pub(crate) struct UndefinedBehavior {
+    pub id: &'static str,
+    pub source: &'static str,
+}
+
+/// Runs every snippet as its own binary crate under Miri and panics if any of them
+/// finishes without Miri reporting undefined behavior.
+pub(crate) fn assert_all_undefined(snippets: &[UndefinedBehavior]) {
+    let Some(toolchain) = find_miri() else {
+        return skip("undefined behavior snippets");
+    };
+    for snippet in snippets {
+        let dir = scratch_dir(snippet.id);
+        fs::create_dir_all(dir.join("src")).expect("Unable to create snippet crate");
+        fs::write(
+            dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+                snippet.id.replace('_', "-")
+            ),
+        )
+        .expect("Unable to write Cargo.toml");
+        fs::write(dir.join("src/main.rs"), snippet.source).expect("Unable to write snippet");
+
+        let output = cargo_miri(&toolchain)
+            .args(["run", "--quiet", "--manifest-path"])
+            .arg(dir.join("Cargo.toml"))
+            .output()
+            .expect("Unable to run cargo miri");
+        let _ = fs::remove_dir_all(&dir);
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !output.status.success() && stderr.contains("Undefined Behavior"),
+            "snippet `{}` should be reported as undefined behavior but Miri said:\n{}",
+            snippet.id,
+            stderr
+        );
+    }
+}
+
+/// Runs this crate's tests whose names contain filter under Miri and panics if any fail.
+pub(crate) fn assert_tests_pass(filter: &str) {
+    let Some(toolchain) = find_miri() else {
+        return skip(filter);
+    };
+    // A separate target dir, the one we are running from is locked by the outer cargo test
+    let target_dir = scratch_dir(&format!("miri-{}", filter));
+    let output = cargo_miri(&toolchain)
+        .args(["test", "--quiet", "--lib", "--target-dir"])
+        .arg(&target_dir)
+        .arg(filter)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Unable to run cargo miri");
+    let _ = fs::remove_dir_all(&target_dir);
+
+    assert!(
+        output.status.success(),
+        "tests matching `{}` failed under Miri:\n{}{}",
+        filter,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+// Miri may be installed for the default toolchain or only for nightly. Returns the
+// toolchain to ask for, None for the default one, or nothing when Miri is not installed.
+fn find_miri() -> Option<Option<&'static str>> {
+    [None, Some("nightly")].into_iter().find(|toolchain| {
+        cargo_miri(toolchain)
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+fn cargo_miri(toolchain: &Option<&str>) -> Command {
+    let mut command = Command::new("cargo");
+    if let Some(toolchain) = toolchain {
+        command.arg(format!("+{}", toolchain));
+    }
+    command.arg("miri");
+    command
+}
+
+fn skip(what: &str) {
+    eprintln!(
+        "skipping Miri checks for {}: Miri is not installed (rustup +nightly component add miri)",
+        what
+    );
+}
@@ -0,0 +1,410 @@
+/////////////////////////////////////////////////////////
+// Ownership graph
+/////////////////////////////////////////////////////////
+
+// A timeline shows one variable per column. Once values are shared, through Rc or behind a
+// RefCell, the interesting part is who points at what. An example can take a snapshot of
+// that at any point: it names its variables and hands them over, and the snapshot reads
+// the live counts and borrow flags from them. `graph <lesson>` runs the lesson and renders
+// every snapshot it took as Graphviz DOT, and as SVG too when a `dot` binary is installed.
+//
+// Owners on the stack point at heap cells, shared borrows are dashed, mutable borrows are
+// bold, and Weak pointers are dotted and do not keep their cell alive.
+//
+// The examples only say where a snapshot is taken. What it shows is described by a function
+// after the examples, and only `graph` calls it, so running a lesson does not draw anything.
+
+use std::any::type_name;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Write as _};
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+use std::rc::{Rc, Weak};
+
+thread_local! {
+    // Snapshots are recorded on the thread that takes them.
+    static RECORDED: RefCell<Vec<Graph>> = const { RefCell::new(Vec::new()) };
+    static RECORDING: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Place {
+    Stack,
+    Heap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Owns,
+    Borrows,
+    BorrowsMut,
+    Weak,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: String,
+    label: String,
+    place: Place,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+}
+
+/// Who owns and borrows what at one point of an example.
+#[derive(Debug)]
+pub struct Graph {
+    pub title: String,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    // Heap cells by address, so every Rc to the same allocation points at the same node.
+    cells: Vec<usize>,
+}
+
+impl Graph {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            cells: Vec::new(),
+        }
+    }
+
+    /// A Box on the stack owning its value on the heap.
+    // It takes &Box<T> rather than &T because the Box is what gets drawn.
+    #[allow(clippy::borrowed_box)]
+    pub fn boxed<T: ?Sized + Debug>(mut self, name: &str, value: &Box<T>) -> Self {
+        self.stack(name, format!("{}: {}", name, short_type_name::<Box<T>>()));
+        let cell = self.cell(
+            &**value as *const T as *const () as usize,
+            format!("{:?}", value),
+        );
+        self.edge(name, &cell, EdgeKind::Owns);
+        self
+    }
+
+    /// One of the owners of an Rc allocation. The cell shows the counts at this point.
+    pub fn rc<T: ?Sized + Debug>(mut self, name: &str, value: &Rc<T>) -> Self {
+        self.stack(name, format!("{}: {}", name, short_type_name::<Rc<T>>()));
+        let label = format!(
+            "{:?}\nstrong {}, weak {}",
+            value,
+            Rc::strong_count(value),
+            Rc::weak_count(value)
+        );
+        let cell = self.cell(Rc::as_ptr(value) as *const () as usize, label);
+        self.edge(name, &cell, EdgeKind::Owns);
+        self
+    }
+
+    /// A Weak pointer, drawn to its cell while the value is alive and to a tombstone after.
+    pub fn weak<T: Debug>(mut self, name: &str, value: &Weak<T>) -> Self {
+        self.stack(name, format!("{}: {}", name, short_type_name::<Weak<T>>()));
+        let cell = match value.upgrade() {
+            // The upgrade itself is a strong owner, so it is left out of the count.
+            Some(strong) => self.cell(
+                Rc::as_ptr(&strong) as usize,
+                format!(
+                    "{:?}\nstrong {}, weak {}",
+                    strong,
+                    Rc::strong_count(&strong) - 1,
+                    Rc::weak_count(&strong)
+                ),
+            ),
+            None => self.cell(value.as_ptr() as usize, String::from("dropped")),
+        };
+        self.edge(name, &cell, EdgeKind::Weak);
+        self
+    }
+
+    /// A RefCell on the stack, labelled with its runtime borrow state.
+    pub fn ref_cell<T: Debug>(mut self, name: &str, value: &RefCell<T>) -> Self {
+        // A shared borrow only blocks try_borrow_mut, a mutable one blocks both.
+        let state = if value.try_borrow_mut().is_ok() {
+            format!("{:?}\nnot borrowed", value.borrow())
+        } else {
+            match value.try_borrow() {
+                Ok(inner) => format!("{:?}\nborrowed", *inner),
+                Err(_) => String::from("borrowed mutably"),
+            }
+        };
+        let label = format!("{}: {}\n{}", name, short_type_name::<RefCell<T>>(), state);
+        self.stack(name, label);
+        self
+    }
+
+    /// A reference, a Ref or a RefMut held by `name`, borrowing the variable `of`.
+    pub fn borrows(mut self, name: &str, of: &str, mutable: bool) -> Self {
+        let kind = if mutable { "&mut" } else { "&" };
+        self.stack(name, format!("{}: {}", name, kind));
+        let edge = if mutable {
+            EdgeKind::BorrowsMut
+        } else {
+            EdgeKind::Borrows
+        };
+        self.edge(name, of, edge);
+        self
+    }
+
+    fn stack(&mut self, id: &str, label: String) {
+        self.nodes.push(Node {
+            id: id.to_string(),
+            label,
+            place: Place::Stack,
+        });
+    }
+
+    fn cell(&mut self, address: usize, label: String) -> String {
+        if let Some(index) = self.cells.iter().position(|&cell| cell == address) {
+            return format!("heap{}", index);
+        }
+        let id = format!("heap{}", self.cells.len());
+        self.cells.push(address);
+        self.nodes.push(Node {
+            id: id.clone(),
+            label,
+            place: Place::Heap,
+        });
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, kind: EdgeKind) {
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        });
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph ownership {{");
+        let _ = writeln!(dot, "    label={};", quote(&self.title));
+        let _ = writeln!(dot, "    labelloc=t;");
+        let _ = writeln!(dot, "    rankdir=LR;");
+        let _ = writeln!(dot, "    node [shape=box, fontname=\"monospace\"];");
+        for (place, name) in [(Place::Stack, "stack"), (Place::Heap, "heap")] {
+            let nodes: Vec<&Node> = self.nodes.iter().filter(|n| n.place == place).collect();
+            if nodes.is_empty() {
+                continue;
+            }
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", name);
+            let _ = writeln!(dot, "        label=\"{}\";", name);
+            for node in nodes {
+                let style = match place {
+                    Place::Stack => "",
+                    Place::Heap => ", style=rounded",
+                };
+                let _ = writeln!(
+                    dot,
+                    "        {} [label={}{}];",
+                    quote(&node.id),
+                    quote(&node.label),
+                    style
+                );
+            }
+            let _ = writeln!(dot, "    }}");
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Owns => "label=\"owns\"",
+                EdgeKind::Borrows => "label=\"&\", style=dashed",
+                EdgeKind::BorrowsMut => "label=\"&mut\", style=bold",
+                EdgeKind::Weak => "label=\"weak\", style=dotted",
+            };
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [{}];",
+                quote(&edge.from),
+                quote(&edge.to),
+                style
+            );
+        }
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+}
+
+/// Has the examples run on this thread from now on take their snapshots.
+pub fn start_recording() {
+    RECORDING.with(|recording| recording.set(true));
+}
+
+/// Takes a snapshot for `graph <lesson>` to render once the lesson has run, when recording.
+pub fn record(graph: impl FnOnce() -> Graph) {
+    if RECORDING.with(Cell::get) {
+        let graph = graph();
+        RECORDED.with(|recorded| recorded.borrow_mut().push(graph));
+    }
+}
+
+/// Returns the snapshots taken on the current thread so far, in order, and clears them.
+pub fn take_recorded() -> Vec<Graph> {
+    RECORDED.with(|recorded| recorded.borrow_mut().drain(..).collect())
+}
+
+/// Renders DOT text as SVG with the local Graphviz `dot`. None when it is not installed.
+pub fn to_svg(dot: &str) -> Option<String> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(dot.as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+// alloc::rc::Rc<alloc::string::String> reads better as Rc<String>.
+fn short_type_name<T: ?Sized>() -> String {
+    let full = type_name::<T>();
+    let mut short = String::with_capacity(full.len());
+    let mut segment = String::new();
+    for c in full.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or(""));
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or(""));
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(graph: &Graph) -> Vec<(&str, &str, EdgeKind)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str(), edge.kind))
+            .collect()
+    }
+
+    fn label<'g>(graph: &'g Graph, id: &str) -> Option<&'g str> {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.id == id)
+            .map(|node| node.label.as_str())
+    }
+
+    #[test]
+    fn test_rc_owners_share_one_cell() {
+        let first = Rc::new(String::from("shared"));
+        let second = Rc::clone(&first);
+        let graph = Graph::new("two owners")
+            .rc("first", &first)
+            .rc("second", &second);
+        let edges = edges(&graph);
+        assert_eq!(
+            vec![
+                ("first", "heap0", EdgeKind::Owns),
+                ("second", "heap0", EdgeKind::Owns)
+            ],
+            edges
+        );
+        assert_eq!(Some("\"shared\"\nstrong 2, weak 0"), label(&graph, "heap0"));
+        assert_eq!(Some("first: Rc<String>"), label(&graph, "first"));
+    }
+
+    #[test]
+    fn test_weak_outlives_its_value() {
+        let owner = Rc::new(String::from("watched"));
+        let watcher = Rc::downgrade(&owner);
+        let alive = Graph::new("alive")
+            .rc("owner", &owner)
+            .weak("watcher", &watcher);
+        assert_eq!(
+            Some("\"watched\"\nstrong 1, weak 1"),
+            label(&alive, "heap0")
+        );
+        assert_eq!(
+            2,
+            edges(&alive)
+                .iter()
+                .filter(|edge| edge.1 == "heap0")
+                .count()
+        );
+        drop(owner);
+        let gone = Graph::new("gone").weak("watcher", &watcher);
+        assert_eq!(Some("dropped"), label(&gone, "heap0"));
+        assert_eq!(vec![("watcher", "heap0", EdgeKind::Weak)], edges(&gone));
+    }
+
+    #[test]
+    fn test_ref_cell_shows_borrow_state() {
+        let data = RefCell::new(String::from("Hello"));
+        let state_of = |graph: Graph| label(&graph, "data").map(str::to_string);
+        assert_eq!(
+            Some(String::from(
+                "data: RefCell<String>\n\"Hello\"\nnot borrowed"
+            )),
+            state_of(Graph::new("free").ref_cell("data", &data))
+        );
+        {
+            let _borrowed = data.borrow();
+            assert_eq!(
+                Some(String::from("data: RefCell<String>\n\"Hello\"\nborrowed")),
+                state_of(Graph::new("shared").ref_cell("data", &data))
+            );
+        }
+        let _borrowed_mut = data.borrow_mut();
+        let graph =
+            Graph::new("exclusive")
+                .ref_cell("data", &data)
+                .borrows("borrowed_mut", "data", true);
+        assert_eq!(
+            Some("data: RefCell<String>\nborrowed mutably"),
+            label(&graph, "data")
+        );
+        assert!(graph
+            .to_dot()
+            .contains("\"borrowed_mut\" -> \"data\" [label=\"&mut\", style=bold];"));
+    }
+
+    #[test]
+    fn test_dot_escapes_labels() {
+        let dot = Graph::new("say \"hi\"")
+            .boxed("text", &Box::new("a\\b"))
+            .to_dot();
+        assert!(dot.contains("label=\"say \\\"hi\\\"\";"));
+        assert!(dot.contains("\"text\" -> \"heap0\" [label=\"owns\"];"));
+        assert!(dot.contains("\"heap0\" [label=\"\\\"a\\\\\\\\b\\\"\", style=rounded];"));
+    }
+
+    #[test]
+    fn test_snapshots_are_taken_in_order_while_recording() {
+        take_recorded();
+        record(|| Graph::new("not recording"));
+        assert!(take_recorded().is_empty());
+        start_recording();
+        record(|| Graph::new("one"));
+        record(|| Graph::new("two"));
+        let titles: Vec<String> = take_recorded().into_iter().map(|g| g.title).collect();
+        assert_eq!(vec!["one", "two"], titles);
+        assert!(take_recorded().is_empty());
+    }
+}
@@ -0,0 +1,723 @@
+/////////////////////////////////////////////////////////
+// Stepper
+/////////////////////////////////////////////////////////
+
+// A full-screen view for presenting one example a line at a time. The left pane shows the
+// example's source with the current line highlighted, the middle pane shows the variables
+// that are alive and what state they are in, and the right pane shows what the example has
+// printed so far. Right and down step forward, left and up step back, q or Esc quits.
+//
+// Nothing runs while stepping. The example is recorded first, as a list of steps holding
+// everything each frame shows, which is what makes stepping backwards possible. The screen
+// is driven with ANSI escape codes, and the terminal is put into raw mode with `stty`, so
+// this works in the terminals we present from without pulling in a terminal crate. When
+// stdin or stdout is not a terminal every frame is printed one after the other instead.
+
+use std::fmt;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::timeline::{Example, State, Timeline};
+use crate::trace::{Action, Trace};
+
+/// What a variable is doing at a step, as the middle pane shows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ownership {
+    Owned,
+    Borrowed,
+    BorrowedMut,
+    /// A reference, still in use.
+    Borrows {
+        of: String,
+        mutable: bool,
+    },
+    /// A reference that is never used again.
+    BorrowOver,
+    Moved,
+    Dropped,
+    /// Used in a way the borrow checker rejects, on a line the lesson says won't compile.
+    Conflict,
+}
+
+impl fmt::Display for Ownership {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ownership::Owned => write!(f, "owned"),
+            Ownership::Borrowed => write!(f, "borrowed"),
+            Ownership::BorrowedMut => write!(f, "borrowed mutably"),
+            Ownership::Borrows { of, mutable: false } => write!(f, "borrows {}", of),
+            Ownership::Borrows { of, mutable: true } => write!(f, "borrows {} mutably", of),
+            Ownership::BorrowOver => write!(f, "borrow over"),
+            Ownership::Moved => write!(f, "moved"),
+            Ownership::Dropped => write!(f, "dropped"),
+            Ownership::Conflict => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub ownership: Ownership,
+}
+
+/// One frame: the line that just ran and everything the panes show after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub line: usize,
+    pub variables: Vec<Variable>,
+    /// How many lines of the example's output have been printed by now.
+    pub printed: usize,
+    pub notes: Vec<String>,
+    /// A commented-out line the lesson says would not compile.
+    pub hypothetical: bool,
+}
+
+/// An example, recorded as the steps a presenter walks through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub title: String,
+    /// The example's source lines, with their line numbers in the lesson file.
+    pub source: Vec<(usize, String)>,
+    pub output: Vec<String>,
+    pub steps: Vec<Step>,
+}
+
+impl Recording {
+    /// Records an example from its timeline and the output it printed when it ran.
+    /// Each println! line gets the next line of output, and a line that drops values
+    /// gets the "Dropping ..." messages that follow.
+    pub fn from_timeline(example: &Example, output: Vec<String>) -> Recording {
+        let timeline = Timeline::of(example);
+        let mut last: Vec<Option<Ownership>> = vec![None; timeline.bindings.len()];
+        let mut printed = 0;
+        let mut steps = Vec::new();
+        for row in timeline.rows() {
+            let mut variables = Vec::new();
+            let mut drops = false;
+            for (b, binding) in timeline.bindings.iter().enumerate() {
+                let state = timeline.state(b, row.line);
+                let ownership = match state {
+                    Some(state) => {
+                        drops |= state == State::Dropped;
+                        Some(match state {
+                            State::Created | State::Owned => match binding.borrows {
+                                Some(borrow) => Ownership::Borrows {
+                                    of: timeline.bindings[borrow.of].name.clone(),
+                                    mutable: borrow.mutable,
+                                },
+                                None => Ownership::Owned,
+                            },
+                            State::Borrowed => Ownership::Borrowed,
+                            State::BorrowedMut => Ownership::BorrowedMut,
+                            State::BorrowOver => Ownership::BorrowOver,
+                            State::Moved => Ownership::Moved,
+                            State::Dropped => Ownership::Dropped,
+                            State::Conflict => Ownership::Conflict,
+                        })
+                    }
+                    // Moved and dropped values stay on screen, out of scope references do not.
+                    None => last[b]
+                        .clone()
+                        .filter(|o| matches!(o, Ownership::Moved | Ownership::Dropped)),
+                };
+                if !row.hypothetical && state.is_some() {
+                    last[b] = ownership.clone();
+                }
+                if let Some(ownership) = ownership {
+                    variables.push(Variable {
+                        name: binding.name.clone(),
+                        ownership,
+                    });
+                }
+            }
+            if !row.hypothetical {
+                printed += row.text.matches("println!(").count();
+                if drops {
+                    printed += output
+                        .iter()
+                        .skip(printed)
+                        .take_while(|line| line.starts_with("Dropping"))
+                        .count();
+                }
+                printed = printed.min(output.len());
+            }
+            steps.push(Step {
+                line: row.line,
+                variables,
+                printed,
+                notes: timeline.notes(row.line),
+                hypothetical: row.hypothetical,
+            });
+        }
+        if let Some(last) = steps.last_mut() {
+            last.printed = output.len();
+        }
+        // The example's own indentation, left out so the source pane fits more of each line.
+        let indent = example
+            .lines
+            .iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(_, line)| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        Recording {
+            title: example.title.clone(),
+            source: example
+                .lines
+                .iter()
+                // The comments introducing the next example are not part of this one.
+                .take_while(|&&(number, _)| steps.last().is_some_and(|step| number <= step.line))
+                .map(|&(number, line)| (number, line.get(indent..).unwrap_or("").to_string()))
+                .collect(),
+            output,
+            steps,
+        }
+    }
+
+    /// Replays a trace, one step per event, over the recording of the same example made
+    /// from its timeline. That recording gives the source and how much had been printed by
+    /// each line. Lines the trace went to outside the example, such as the body of a
+    /// function it called, are added to the source from the lesson.
+    pub fn from_trace(example: &Recording, trace: &Trace, lesson_source: &str) -> Recording {
+        let mut source = example.source.clone();
+        let mut variables: Vec<Variable> = Vec::new();
+        let mut printed = 0;
+        let mut steps = Vec::new();
+        for event in &trace.events {
+            if !source.iter().any(|(number, _)| *number == event.line) {
+                if let Some(line) = lesson_source.lines().nth(event.line.wrapping_sub(1)) {
+                    source.push((event.line, line.to_string()));
+                }
+            }
+            if let Some(step) = example
+                .steps
+                .iter()
+                .take_while(|step| step.line <= event.line)
+                .last()
+                .filter(|_| example.source.iter().any(|(n, _)| *n == event.line))
+            {
+                printed = printed.max(step.printed);
+            }
+            let mut set = |name: &str, ownership: Ownership| match variables
+                .iter_mut()
+                .find(|variable| variable.name == name)
+            {
+                Some(variable) => variable.ownership = ownership,
+                None => variables.push(Variable {
+                    name: name.to_string(),
+                    ownership,
+                }),
+            };
+            match &event.action {
+                Action::Created => set(&event.name, Ownership::Owned),
+                Action::Moved { .. } => set(&event.name, Ownership::Moved),
+                Action::Borrowed { by, mutable } => {
+                    let borrowed = if *mutable {
+                        Ownership::BorrowedMut
+                    } else {
+                        Ownership::Borrowed
+                    };
+                    set(&event.name, borrowed);
+                    set(
+                        by,
+                        Ownership::Borrows {
+                            of: event.name.clone(),
+                            mutable: *mutable,
+                        },
+                    );
+                }
+                Action::Dropped => set(&event.name, Ownership::Dropped),
+            }
+            steps.push(Step {
+                line: event.line,
+                variables: variables.clone(),
+                printed,
+                notes: vec![event.to_string()],
+                hypothetical: false,
+            });
+        }
+        if let Some(last) = steps.last_mut() {
+            last.printed = example.output.len();
+        }
+        Recording {
+            title: format!("{} (trace)", example.title),
+            source,
+            output: example.output.clone(),
+            steps,
+        }
+    }
+}
+
+/// The lines an example printed, cut out of its lesson's output by the example banners.
+pub fn example_output(lesson_output: &str, title: &str) -> Vec<String> {
+    let banner = format!(" --------------- {} ---------------", title);
+    lesson_output
+        .lines()
+        .skip_while(|line| *line != banner)
+        .skip(1)
+        .take_while(|line| !line.starts_with(" --------------- "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Next,
+    Previous,
+    First,
+    Last,
+    Quit,
+}
+
+/// Reads one key press from a terminal in raw mode. None for keys that do nothing.
+///
+/// A terminal sends the bytes of a key press together, so one read is one key. That is how
+/// Esc on its own, which quits, is told apart from the start of an arrow key without waiting
+/// for bytes that are not coming.
+pub fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let mut bytes = [0u8; 8];
+    let read = input.read(&mut bytes)?;
+    Ok(match bytes[..read] {
+        [] | [0x1b] => Some(Key::Quit),
+        // Arrow keys arrive as ESC [ A..D, Home and End as ESC [ H and ESC [ F.
+        [0x1b, b'[', b'A' | b'D', ..] => Some(Key::Previous),
+        [0x1b, b'[', b'B' | b'C', ..] => Some(Key::Next),
+        [0x1b, b'[', b'H', ..] => Some(Key::First),
+        [0x1b, b'[', b'F', ..] => Some(Key::Last),
+        [b'q' | b'Q' | 3 | 4, ..] => Some(Key::Quit),
+        [b' ' | b'l' | b'j' | b'\r' | b'\n', ..] => Some(Key::Next),
+        [b'h' | b'k' | 127, ..] => Some(Key::Previous),
+        [b'g', ..] => Some(Key::First),
+        [b'G', ..] => Some(Key::Last),
+        _ => None,
+    })
+}
+
+// Pads or cuts text to exactly this many characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
+
+/// Draws one step as lines of exactly `width` characters, plus escape codes when `ansi`
+/// is set. Without them the current source line is marked with `>` instead.
+pub fn frame(
+    recording: &Recording,
+    index: usize,
+    width: usize,
+    height: usize,
+    ansi: bool,
+) -> Vec<String> {
+    let step = &recording.steps[index];
+    let left = width * 40 / 100;
+    let middle = width * 30 / 100;
+    let right = width.saturating_sub(left + middle + 2);
+    let body = height.saturating_sub(3).max(1);
+    let (highlight, reset) = if ansi {
+        ("\x1b[7m", "\x1b[0m")
+    } else {
+        ("", "")
+    };
+
+    let current = recording
+        .source
+        .iter()
+        .position(|(number, _)| *number == step.line)
+        .unwrap_or(0);
+    let first = current
+        .saturating_sub(body / 2)
+        .min(recording.source.len().saturating_sub(body));
+    let source: Vec<String> = recording.source[first..]
+        .iter()
+        .take(body)
+        .map(|(number, line)| {
+            let marker = if *number == step.line && !ansi {
+                '>'
+            } else {
+                ' '
+            };
+            let text = fit(&format!("{}{:>4} {}", marker, number, line), left);
+            if *number == step.line {
+                format!("{}{}{}", highlight, text, reset)
+            } else {
+                text
+            }
+        })
+        .collect();
+    let name_width = step
+        .variables
+        .iter()
+        .map(|variable| variable.name.len())
+        .max()
+        .unwrap_or(0);
+    let variables: Vec<String> = step
+        .variables
+        .iter()
+        .map(|variable| {
+            let text = format!(
+                " {:<w$}  {}",
+                variable.name,
+                variable.ownership,
+                w = name_width
+            );
+            fit(&text, middle)
+        })
+        .collect();
+    let printed = &recording.output[..step.printed];
+    let output: Vec<String> = printed[printed.len().saturating_sub(body)..]
+        .iter()
+        .map(|line| fit(&format!(" {}", line), right))
+        .collect();
+
+    let kind = if step.hypothetical {
+        "  (would not compile)"
+    } else {
+        ""
+    };
+    let title = format!(
+        "{}  step {}/{}  line {}{}    <- -> step  q quit",
+        recording.title,
+        index + 1,
+        recording.steps.len(),
+        step.line,
+        kind
+    );
+    let mut lines = vec![format!("{}{}{}", highlight, fit(&title, width), reset)];
+    lines.push(format!(
+        "{}|{}|{}",
+        fit(" source", left),
+        fit(" variables", middle),
+        fit(" output", right)
+    ));
+    for row in 0..body {
+        let cell = |pane: &[String], width: usize| {
+            pane.get(row).cloned().unwrap_or_else(|| fit("", width))
+        };
+        lines.push(format!(
+            "{}|{}|{}",
+            cell(&source, left),
+            cell(&variables, middle),
+            cell(&output, right)
+        ));
+    }
+    lines.push(fit(&format!(" {}", step.notes.join("; ")), width));
+    lines
+}
+
+/// Steps through a recording in the terminal, or prints every frame when not in one.
+pub fn present(recording: &Recording) -> io::Result<()> {
+    if recording.steps.is_empty() {
+        println!("{} has no lines to step through", recording.title);
+        return Ok(());
+    }
+    if !(io::stdin().is_terminal() && io::stdout().is_terminal()) {
+        let height = recording.source.len().min(12) + 3;
+        for index in 0..recording.steps.len() {
+            for line in frame(recording, index, 120, height, false) {
+                println!("{}", line.trim_end());
+            }
+            println!();
+        }
+        return Ok(());
+    }
+    let _raw = RawMode::enter()?;
+    interact(recording)
+}
+
+// The terminal in raw mode on the alternate screen, put back as it was when dropped, whether
+// stepping ended, failed or panicked. Ctrl-C arrives as a key meanwhile, so this is the only
+// way back.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enter() -> io::Result<RawMode> {
+        let raw = RawMode {
+            saved: stty(&["-g"])?.trim().to_string(),
+        };
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        // The alternate screen keeps the presenter's scrollback intact, and the cursor is hidden.
+        print!("\x1b[?1049h\x1b[?25l");
+        Ok(raw)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn interact(recording: &Recording) -> io::Result<()> {
+    let mut index = 0;
+    let mut stdin = io::stdin().lock();
+    loop {
+        let (height, width) = terminal_size();
+        let mut screen = String::from("\x1b[H\x1b[2J");
+        screen.push_str(&frame(recording, index, width, height, true).join("\r\n"));
+        print!("{}", screen);
+        io::stdout().flush()?;
+        match read_key(&mut stdin)? {
+            Some(Key::Next) => index = (index + 1).min(recording.steps.len() - 1),
+            Some(Key::Previous) => index = index.saturating_sub(1),
+            Some(Key::First) => index = 0,
+            Some(Key::Last) => index = recording.steps.len() - 1,
+            Some(Key::Quit) => return Ok(()),
+            None => {}
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, is stdin a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Rows and columns, from `stty size`, with the classic 24x80 when that fails.
+fn terminal_size() -> (usize, usize) {
+    let size = stty(&["size"]).unwrap_or_default();
+    let mut numbers = size.split_whitespace().filter_map(|n| n.parse().ok());
+    match (numbers.next(), numbers.next()) {
+        (Some(rows), Some(columns)) => (rows, columns),
+        _ => (24, 80),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::examples;
+    use crate::trace;
+    use std::io::Cursor;
+
+    fn recording(title: &str, output: &[&str]) -> Recording {
+        let examples = examples(include_str!("lesson_3_borrow.rs"));
+        let example = examples
+            .iter()
+            .find(|example| example.title == title)
+            .expect("No such example");
+        Recording::from_timeline(example, output.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn ownership_of(step: &Step, name: &str) -> Option<Ownership> {
+        step.variables
+            .iter()
+            .find(|variable| variable.name == name)
+            .map(|variable| variable.ownership.clone())
+    }
+
+    #[test]
+    fn test_steps_follow_the_borrows() {
+        let recording = recording(
+            "lesson 3 example 2",
+            &["reference: Hello, Rust!", "data: Hello, Rust!"],
+        );
+        let states: Vec<(Option<Ownership>, Option<Ownership>, usize)> = recording
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    ownership_of(step, "data"),
+                    ownership_of(step, "reference"),
+                    step.printed,
+                )
+            })
+            .collect();
+        let borrows = Ownership::Borrows {
+            of: String::from("data"),
+            mutable: true,
+        };
+        assert_eq!(
+            vec![
+                (None, None, 0),
+                (Some(Ownership::Owned), None, 0),
+                (Some(Ownership::BorrowedMut), Some(borrows.clone()), 0),
+                (Some(Ownership::Conflict), Some(borrows.clone()), 0),
+                (Some(Ownership::BorrowedMut), Some(borrows.clone()), 0),
+                (Some(Ownership::BorrowedMut), Some(borrows), 1),
+                (Some(Ownership::Owned), Some(Ownership::BorrowOver), 2),
+                (Some(Ownership::Dropped), Some(Ownership::BorrowOver), 2),
+            ],
+            states
+        );
+        assert!(recording.steps[3].hypothetical);
+    }
+
+    #[test]
+    fn test_drop_messages_go_with_the_closing_brace() {
+        let recording = recording(
+            "lesson 3 example 5",
+            &[
+                "original: MyCloneableStruct { data: \"Hello\" }",
+                "borrowed: MyCloneableStruct { data: \"Hello\" }",
+                "cloned: MyCloneableStruct { data: \"Hello\" }",
+                "Dropping MyCloneableStruct with data: Hello",
+                "Dropping MyCloneableStruct with data: Hello",
+            ],
+        );
+        let printed: Vec<usize> = recording.steps.iter().map(|step| step.printed).collect();
+        let last = printed.len() - 1;
+        assert_eq!(3, printed[last - 1]);
+        assert_eq!(5, printed[last]);
+        let step = &recording.steps[last];
+        assert_eq!(Some(Ownership::Dropped), ownership_of(step, "original"));
+        assert_eq!(Some(Ownership::Dropped), ownership_of(step, "cloned"));
+    }
+
+    #[test]
+    fn test_moved_values_stay_on_screen() {
+        let examples = examples(include_str!("lesson_1_scope.rs"));
+        let example = examples
+            .iter()
+            .find(|example| example.title == "lesson 1 example 3")
+            .expect("No such example");
+        let recording =
+            Recording::from_timeline(example, vec![String::from("s2: [1, 2, 3, 4, 5]")]);
+        let last = recording.steps.last().expect("No steps");
+        assert_eq!(Some(Ownership::Moved), ownership_of(last, "s1"));
+        assert_eq!(Some(Ownership::Owned), ownership_of(last, "s2"));
+    }
+
+    #[test]
+    fn test_trace_replays_a_step_per_event() {
+        let source = include_str!("lesson_1_scope.rs");
+        let examples = examples(source);
+        let example = examples
+            .iter()
+            .find(|example| example.title == "lesson 1 example 2")
+            .expect("No such example");
+        let base = Recording::from_timeline(example, vec![String::from("data2: [1, 2, 3, 4, 5]")]);
+        // consume() drops data at its closing brace, the line after the trace!.
+        let drop_line = source
+            .lines()
+            .position(|line| line.trim().starts_with("trace!(data dropped);"))
+            .expect("consume() traces no drop")
+            + 2;
+        let trace = Trace {
+            lesson: String::from("lesson_1_scope"),
+            example: example.title.clone(),
+            events: vec![
+                trace::Event {
+                    line: base.steps[1].line,
+                    name: String::from("my_data2"),
+                    action: Action::Created,
+                },
+                trace::Event {
+                    line: drop_line,
+                    name: String::from("data"),
+                    action: Action::Dropped,
+                },
+            ],
+        };
+        let replay = Recording::from_trace(&base, &trace, source);
+        assert_eq!(2, replay.steps.len());
+        assert_eq!(
+            Some(Ownership::Owned),
+            ownership_of(&replay.steps[0], "my_data2")
+        );
+        assert_eq!(
+            Some(Ownership::Dropped),
+            ownership_of(&replay.steps[1], "data")
+        );
+        assert_eq!(vec![String::from("data dropped")], replay.steps[1].notes);
+        // The line of consume() that drops data is shown, though it is not part of the example.
+        let (_, line) = replay.source.last().expect("No source");
+        assert_eq!("}", line.trim());
+    }
+
+    #[test]
+    fn test_example_output_is_cut_at_banners() {
+        let output = " --------------- lesson 3 example 1 ---------------\n\
+                      one\n\
+                      \x20--------------- lesson 3 example 2 ---------------\n\
+                      two\n\
+                      three\n";
+        assert_eq!(
+            vec!["two", "three"],
+            example_output(output, "lesson 3 example 2")
+        );
+        assert!(example_output(output, "lesson 3 example 9").is_empty());
+    }
+
+    // Hands out one key press per read, the way a terminal does.
+    struct Presses(Vec<&'static [u8]>);
+
+    impl Read for Presses {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            let press = self.0.remove(0);
+            buf[..press.len()].copy_from_slice(press);
+            Ok(press.len())
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        let keys = |presses: &[&'static [u8]]| {
+            let mut input = Presses(presses.to_vec());
+            presses
+                .iter()
+                .map(|_| read_key(&mut input).expect("read failed"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![
+                Some(Key::Next),
+                Some(Key::Previous),
+                Some(Key::Next),
+                Some(Key::Previous),
+                Some(Key::First),
+                Some(Key::Last),
+                None,
+                Some(Key::Quit),
+            ],
+            keys(&[b"\x1b[C", b"\x1b[D", b"\x1b[B", b"\x1b[A", b"\x1b[H", b"\x1b[F", b"x", b"q"])
+        );
+        // Esc on its own quits straight away, other escape sequences do nothing.
+        assert_eq!(
+            vec![None, Some(Key::Next), Some(Key::Quit)],
+            keys(&[b"\x1b[5~", b"l", b"\x1b"])
+        );
+        assert_eq!(
+            Some(Key::Quit),
+            read_key(&mut Cursor::new(Vec::new())).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_frame_layout() {
+        let recording = recording(
+            "lesson 3 example 2",
+            &["reference: Hello, Rust!", "data: Hello, Rust!"],
+        );
+        let line = recording.steps[2].line;
+        let lines = frame(&recording, 2, 120, 10, false);
+        assert_eq!(10, lines.len());
+        assert!(lines.iter().all(|l| l.chars().count() == 120));
+        let title = format!("lesson 3 example 2  step 3/8  line {}", line);
+        assert!(lines[0].starts_with(&title));
+        let current = format!(">{:>4}     let reference = &mut data;", line);
+        assert!(lines.iter().any(|l| l.starts_with(&current)));
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("| reference  borrows data mutably")));
+        assert!(lines[9].contains("reference borrows data mutably"));
+        let highlighted = frame(&recording, 2, 120, 10, true);
+        let current = format!("\x1b[7m {:>4} ", line);
+        assert!(highlighted.iter().any(|l| l.starts_with(&current)));
+    }
+}
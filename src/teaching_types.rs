@@ -0,0 +1,227 @@
+/////////////////////////////////////////////////////////
+// Teaching types
+/////////////////////////////////////////////////////////
+
+// The structs the lessons keep coming back to, defined once. Lessons 2 and 3 used to
+// declare their own copies inside examples(), each with slightly different fields and
+// Drop behavior. These versions print when they are dropped, as the lessons always did,
+// and also record it with the drop tracer. The Clone impls count themselves, so an example
+// or test can check how many deep copies a piece of code made.
+
+use std::cell::Cell;
+
+use crate::drop_tracer;
+
+thread_local! {
+    // Clones are counted on the thread that performs them.
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns how many MyCloneableStruct and LargeStruct values were cloned on this thread.
+pub fn clone_count() -> usize {
+    CLONES.with(Cell::get)
+}
+
+fn count_clone() {
+    CLONES.with(|clones| clones.set(clones.get() + 1));
+}
+
+/// Owns a String, nothing else. Neither Copy nor Clone, so it can only be moved or borrowed.
+#[derive(Debug)]
+pub struct SimpleStruct {
+    pub data: String,
+}
+
+/// Announces its drop, recorded by the drop tracer as "MyStruct: <data>".
+#[derive(Debug)]
+pub struct MyStruct {
+    pub data: String,
+}
+
+impl Drop for MyStruct {
+    fn drop(&mut self) {
+        println!("Dropping MyStruct with data: {}", self.data);
+        drop_tracer::record(&format!("MyStruct: {}", self.data));
+    }
+}
+
+/// Copy, so assigning it copies the bits and the original stays usable.
+/// A Copy type cannot implement Drop, so this one is never traced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MyCopyableStruct {
+    pub data: i32,
+}
+
+/// Clone but not Copy: cloning makes a deep copy of the String, and is counted.
+/// Announces its drop, recorded by the drop tracer as "MyCloneableStruct: <data>".
+#[derive(Debug, PartialEq)]
+pub struct MyCloneableStruct {
+    pub data: String,
+}
+
+impl Clone for MyCloneableStruct {
+    fn clone(&self) -> Self {
+        count_clone();
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl Drop for MyCloneableStruct {
+    fn drop(&mut self) {
+        println!("Dropping MyCloneableStruct with data: {}", self.data);
+        drop_tracer::record(&format!("MyCloneableStruct: {}", self.data));
+    }
+}
+
+/// Two numbers, Copy like the numbers themselves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Owns a Vec that may be large, to show what a deep copy costs. Cloning is counted.
+#[derive(Debug)]
+pub struct LargeStruct {
+    pub data: Vec<i32>,
+}
+
+impl Clone for LargeStruct {
+    fn clone(&self) -> Self {
+        count_clone();
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
+/// Borrows its data instead of owning it, so it cannot outlive the &str it was made from.
+#[derive(Debug)]
+pub struct MyRefStruct<'a> {
+    pub data: &'a str,
+}
+
+impl<'a> MyRefStruct<'a> {
+    pub fn new(data: &'a str) -> Self {
+        Self { data }
+    }
+
+    pub fn display(&self) {
+        println!("Data: {}", self.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_fail::{assert_all_rejected, with_teaching_types, CompileFail};
+
+    // The semantics the lessons rely on, checked against the definitions above.
+    const COMPILE_FAIL: &[CompileFail] = &[
+        CompileFail {
+            id: "teaching_types_simple_struct_moves",
+            code: "E0382",
+            source: with_teaching_types!(
+                r#"
+pub fn demo() {
+    let original = SimpleStruct { data: String::from("moved") };
+    let moved = original;
+    println!("{:?} {:?}", original, moved);
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "teaching_types_simple_struct_not_clone",
+            code: "E0277",
+            source: with_teaching_types!(
+                r#"
+pub fn demo(original: &SimpleStruct) -> SimpleStruct {
+    SimpleStruct::clone(original)
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "teaching_types_copy_and_drop",
+            code: "E0184",
+            source: with_teaching_types!(
+                r#"
+impl Drop for MyCopyableStruct { fn drop(&mut self) {} }
+"#
+            ),
+        },
+        CompileFail {
+            id: "teaching_types_move_out_of_drop",
+            code: "E0509",
+            source: with_teaching_types!(
+                r#"
+pub fn demo(my_struct: MyStruct) -> String {
+    my_struct.data
+}
+"#
+            ),
+        },
+        CompileFail {
+            id: "teaching_types_ref_struct_outlives_data",
+            code: "E0597",
+            source: with_teaching_types!(
+                r#"
+pub fn demo() -> usize {
+    let my_ref;
+    {
+        let text = String::from("short lived");
+        my_ref = MyRefStruct::new(&text);
+    }
+    my_ref.data.len()
+}
+"#
+            ),
+        },
+    ];
+
+    #[test]
+    fn test_drops_are_traced() {
+        drop(MyStruct {
+            data: String::from("traced"),
+        });
+        drop(MyCloneableStruct {
+            data: String::from("traced"),
+        });
+        assert!(drop_tracer::was_dropped("MyStruct: traced"));
+        assert!(drop_tracer::was_dropped("MyCloneableStruct: traced"));
+    }
+
+    #[test]
+    fn test_clones_are_counted_and_deep() {
+        let before = clone_count();
+        let original = MyCloneableStruct {
+            data: String::from("original"),
+        };
+        let mut cloned = original.clone();
+        cloned.data.push_str(" changed");
+        assert_eq!("original", original.data);
+        let large = LargeStruct { data: vec![1; 10] };
+        let large_clone = large.clone();
+        assert_ne!(large.data.as_ptr(), large_clone.data.as_ptr());
+        assert_eq!(before + 2, clone_count());
+    }
+
+    #[test]
+    fn test_copies_are_not_counted() {
+        let before = clone_count();
+        let point = Point { x: 1, y: 2 };
+        let copied = point;
+        let number = MyCopyableStruct { data: 3 };
+        let number_copy = number;
+        assert_eq!((point, number), (copied, number_copy));
+        assert_eq!(before, clone_count());
+    }
+
+    #[test]
+    fn test_compile_fail() {
+        assert_all_rejected(COMPILE_FAIL);
+    }
+}
@@ -0,0 +1,1311 @@
+/////////////////////////////////////////////////////////
+// Borrow timeline
+/////////////////////////////////////////////////////////
+
+// Presenters used to draw the borrows of an example on a whiteboard by hand. This module
+// reads the example's source instead, and draws one column per variable: where it is
+// created, borrowed, moved and dropped, and how long each borrow lasts. It is a reading
+// aid, not a borrow checker. It knows about `let`, `&`, `&mut`, method calls, function
+// arguments and closing braces, and a borrow held by a `let` reference lasts until the
+// reference's last use, as it does since non-lexical lifetimes.
+//
+// The commented-out lines the lessons say would not compile are drawn too, marked with
+// `?`, so the conflict they would cause shows up against the borrows that cause it.
+//
+// A variable is Copy, and drawn as copied rather than moved, when it is given a literal or
+// one of the teaching types that derive Copy, or when the comment on its `let` line says it
+// "is Copy". The lessons' code is left as it is.
+
+use std::fmt;
+
+// Drawn at the top of every `run --visualize`.
+pub const LEGEND: &str = "* created  | owned  & borrowed  M borrowed mutably  : borrow over  \
+                          > moved  x dropped  ! conflict  ? line the lesson says won't compile";
+
+const SOURCE_WIDTH: usize = 44;
+
+const TEACHING_TYPES: &str = include_str!("teaching_types.rs");
+
+// The teaching types whose derive list includes Copy.
+fn copy_types() -> impl Iterator<Item = &'static str> {
+    let mut derives_copy = false;
+    TEACHING_TYPES.lines().filter_map(move |line| {
+        let line = line.trim();
+        if line.starts_with("#[derive(") {
+            derives_copy = line
+                .split(|c: char| !is_ident_char(c))
+                .any(|word| word == "Copy");
+            return None;
+        }
+        let name = line.strip_prefix("pub struct ")?;
+        let copy = std::mem::take(&mut derives_copy);
+        let end = name.find(|c: char| !is_ident_char(c)).unwrap_or(name.len());
+        copy.then_some(&name[..end])
+    })
+}
+
+// A value built by naming one of the Copy teaching types, `Point { .. }` or `Point::new(..)`.
+fn builds_copy_type(value: &str) -> bool {
+    copy_types().any(|name| {
+        value
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with(['{', '(']) || rest.starts_with("::"))
+    })
+}
+
+// The comment on a `let` line saying its value is Copy, such as "so it is Copy".
+fn says_copy(comment: &str) -> bool {
+    comment.contains("is Copy")
+}
+
+/// The lines of one example, found between its banner and the next one.
+pub struct Example<'a> {
+    pub title: String,
+    /// Each line with its 1-based line number in the lesson file.
+    pub lines: Vec<(usize, &'a str)>,
+}
+
+/// A variable introduced by `let`.
+#[derive(Debug)]
+pub struct Binding {
+    pub name: String,
+    pub line: usize,
+    /// Set when the variable was initialized with `&x` or `&mut x`.
+    pub borrows: Option<Borrow>,
+    /// A reference of any kind: one that borrows another binding, one whose type starts
+    /// with `&`. Dropping a reference does nothing.
+    pub reference: bool,
+    /// Declared on a line the lesson says would not compile.
+    pub hypothetical: bool,
+    copy: bool,
+    depth: i32,
+    moved: Option<usize>,
+    out_of_scope: Option<usize>,
+}
+
+/// What a reference variable borrows: another binding, shared or mutably.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Borrow {
+    pub of: usize,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    Created,
+    Borrowed { mutable: bool },
+    Used { mutable: bool },
+    Moved { into: String },
+    Copied,
+    Dropped,
+}
+
+/// Something that happens to a binding on a line of the example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub line: usize,
+    pub binding: usize,
+    pub kind: EventKind,
+    pub hypothetical: bool,
+}
+
+/// What a binding is doing on a line, drawn as one character in its column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Created,
+    /// Owned and not borrowed. For a reference, still in use.
+    Owned,
+    Borrowed,
+    BorrowedMut,
+    /// A reference still in scope, but never used again.
+    BorrowOver,
+    Moved,
+    Dropped,
+    Conflict,
+}
+
+impl State {
+    pub fn symbol(self) -> char {
+        match self {
+            State::Created => '*',
+            State::Owned => '|',
+            State::Borrowed => '&',
+            State::BorrowedMut => 'M',
+            State::BorrowOver => ':',
+            State::Moved => '>',
+            State::Dropped => 'x',
+            State::Conflict => '!',
+        }
+    }
+}
+
+/// A line where a binding is used in a way the borrow checker rejects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub line: usize,
+    pub binding: usize,
+    pub message: String,
+}
+
+/// A line of the example as drawn in the timeline.
+pub struct Row {
+    pub line: usize,
+    pub text: String,
+    /// A commented-out line the lesson says would not compile.
+    pub hypothetical: bool,
+}
+
+/// How long a borrow lasts: from the line it is taken to the holder's last use.
+struct Span {
+    of: usize,
+    holder: Option<usize>,
+    mutable: bool,
+    from: usize,
+    to: usize,
+}
+
+pub struct Timeline {
+    pub bindings: Vec<Binding>,
+    pub events: Vec<Event>,
+    pub conflicts: Vec<Conflict>,
+    rows: Vec<Row>,
+    spans: Vec<Span>,
+}
+
+/// A lesson's output with the timeline of each example after what the example printed.
+pub fn interleave(source: &str, output: &str) -> String {
+    let mut timelines: Vec<(String, String)> = examples(source)
+        .iter()
+        .map(|example| {
+            let timeline = format!(
+                " --------------- {} timeline ---------------\n{}",
+                example.title,
+                Timeline::of(example)
+            );
+            (example.title.clone(), timeline)
+        })
+        .collect();
+    let mut take = |title: Option<&str>| {
+        let index = timelines
+            .iter()
+            .position(|(t, _)| Some(t.as_str()) == title)?;
+        Some(timelines.remove(index).1)
+    };
+    let mut text = String::new();
+    let mut current = None;
+    for line in output.lines() {
+        let banner = line
+            .strip_prefix(" --------------- ")
+            .and_then(|rest| rest.strip_suffix(" ---------------"));
+        if banner.is_some() {
+            text += &take(current).unwrap_or_default();
+            current = banner;
+        }
+        text += line;
+        text.push('\n');
+    }
+    text += &take(current).unwrap_or_default();
+    text
+}
+
+/// Splits a lesson's source at its example banners. An example ends at the next banner,
+/// or at the brace that closes the function the examples live in.
+pub fn examples(source: &str) -> Vec<Example<'_>> {
+    let mut found: Vec<Example> = Vec::new();
+    let mut depth = 0;
+    let mut inside = false;
+    for (index, line) in source.lines().enumerate() {
+        if let Some(title) = banner_title(line) {
+            found.push(Example {
+                title,
+                lines: Vec::new(),
+            });
+            depth = 0;
+            inside = true;
+            continue;
+        }
+        if !inside {
+            continue;
+        }
+        if let Some(example) = found.last_mut() {
+            example.lines.push((index + 1, line));
+        }
+        depth += brace_balance(&split_comment(line).0);
+        if depth < 0 {
+            inside = false;
+        }
+    }
+    found
+}
+
+fn banner_title(line: &str) -> Option<String> {
+    let text = line.trim().strip_prefix("println!(\"")?.split('"').next()?;
+    let title = text.trim().trim_matches('-').trim();
+    (text.trim_start().starts_with("---") && title.contains(" example ")).then(|| title.to_string())
+}
+
+fn brace_balance(code: &str) -> i32 {
+    code.chars()
+        .map(|c| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+// Splits a line into its code and its trailing `//` comment. String and char literals in
+// the code are emptied, so their contents are not mistaken for braces, names or comments.
+fn split_comment(line: &str) -> (String, Option<&str>) {
+    let mut code = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' if line[i + 1..].starts_with('/') => return (code, Some(&line[i + 2..])),
+            '"' => {
+                code.push_str("\"\"");
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        break;
+                    }
+                }
+            }
+            '\'' => match char_literal_len(&line[i + 1..]) {
+                Some(len) => {
+                    code.push_str("' '");
+                    while chars.next_if(|&(j, _)| j <= i + len).is_some() {}
+                }
+                // A lifetime, such as 'a or 'static.
+                None => code.push(c),
+            },
+            _ => code.push(c),
+        }
+    }
+    (code, None)
+}
+
+// The length of a char literal after its opening quote, closing quote included.
+fn char_literal_len(rest: &str) -> Option<usize> {
+    if let Some(escaped) = rest.strip_prefix('\\') {
+        return escaped.find('\'').map(|end| end + 2);
+    }
+    let c = rest.chars().next()?;
+    rest[c.len_utf8()..]
+        .starts_with('\'')
+        .then(|| c.len_utf8() + 1)
+}
+
+fn claims_error(comment: &str) -> bool {
+    let comment = comment.to_lowercase();
+    comment.contains("compilation error")
+        || comment.contains("compile error")
+        || comment.contains("does not compile")
+}
+
+// A commented-out line of code, such as `// println!("{}", data); // error`.
+fn commented_code(comment: &str) -> Option<(String, Option<&str>)> {
+    if comment.starts_with('/') {
+        return None;
+    }
+    let (code, inner) = split_comment(comment);
+    let trimmed = code.trim();
+    let looks_like_code = trimmed.ends_with(';')
+        && trimmed.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '*');
+    looks_like_code.then_some((code, inner))
+}
+
+// The source of a line as shown in the timeline: comment removed, strings kept.
+fn display_code<'a>(line: &'a str, comment: Option<&str>) -> &'a str {
+    match comment {
+        Some(comment) => &line[..line.len() - comment.len() - 2],
+        None => line,
+    }
+    .trim_end()
+}
+
+fn is_item(code: &str) -> bool {
+    let code = code.trim_start();
+    let code = code.strip_prefix("pub(crate) ").unwrap_or(code);
+    let code = code.strip_prefix("pub ").unwrap_or(code);
+    [
+        "fn ", "struct ", "enum ", "impl", "trait ", "mod ", "use ", "#[",
+    ]
+    .iter()
+    .any(|keyword| code.starts_with(keyword))
+}
+
+// Lines that only observe the example, its ownership snapshots and the trace! lines beside
+// its code, are left out.
+fn is_instrumentation(code: &str) -> bool {
+    let code = code.trim_start();
+    code.starts_with("ownership_graph::record(") || code.starts_with("trace!(")
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while",
+];
+
+// Methods that need `&mut self`, so calling them on a value is a mutable use.
+const MUTATING_METHODS: &[&str] = &[
+    "append",
+    "as_mut",
+    "clear",
+    "drain",
+    "extend",
+    "get_mut",
+    "insert",
+    "iter_mut",
+    "pop",
+    "push",
+    "push_back",
+    "push_front",
+    "push_str",
+    "remove",
+    "retain",
+    "sort",
+    "truncate",
+];
+
+#[derive(Clone, PartialEq, Eq)]
+enum Context {
+    Top,
+    Call(String),
+    Group,
+}
+
+// A name on a line of code, with what surrounds it.
+struct Mention<'c> {
+    name: &'c str,
+    before: &'c str,
+    after: &'c str,
+    context: Context,
+}
+
+fn mentions(code: &str) -> Vec<Mention<'_>> {
+    let mut found = Vec::new();
+    let mut contexts = vec![Context::Top];
+    let mut last_word = "";
+    let mut i = 0;
+    while i < code.len() {
+        let c = code[i..].chars().next().unwrap_or(' ');
+        if is_ident_char(c) {
+            let end = code[i..]
+                .find(|c: char| !is_ident_char(c))
+                .map_or(code.len(), |n| i + n);
+            let word = &code[i..end];
+            if !word.starts_with(|c: char| c.is_ascii_digit()) {
+                found.push(Mention {
+                    name: word,
+                    before: &code[..i],
+                    after: &code[end..],
+                    context: contexts.last().cloned().unwrap_or(Context::Top),
+                });
+            }
+            last_word = word;
+            i = end;
+            continue;
+        }
+        match c {
+            '(' => {
+                let before = code[..i].trim_end();
+                // Macro arguments, such as println!'s, are only ever borrowed.
+                let context = if before.ends_with('!') {
+                    Context::Group
+                } else if before.ends_with(last_word)
+                    && !last_word.is_empty()
+                    && !KEYWORDS.contains(&last_word)
+                {
+                    Context::Call(last_word.to_string())
+                } else {
+                    Context::Group
+                };
+                contexts.push(context);
+            }
+            '[' => contexts.push(Context::Group),
+            ')' | ']' if contexts.len() > 1 => {
+                contexts.pop();
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            last_word = "";
+        }
+        i += c.len_utf8();
+    }
+    found
+}
+
+// Reads what a line does with one of the bindings it names. None when the name is not a
+// use of the binding at all, such as a field or a path segment of the same name.
+fn classify(mention: &Mention, let_value: Option<&str>) -> Option<EventKind> {
+    let before = mention.before.trim_end();
+    let after = mention.after.trim_start();
+    if mention.before.ends_with('.')
+        || mention.before.ends_with("::")
+        || after.starts_with("::")
+        || (after.starts_with(':') && !after.starts_with("::"))
+    {
+        return None;
+    }
+    if before.ends_with("&mut") {
+        return Some(EventKind::Borrowed { mutable: true });
+    }
+    if before.ends_with('&') && !before.ends_with("&&") {
+        return Some(EventKind::Borrowed { mutable: false });
+    }
+    if after.starts_with('.') {
+        return Some(method_access(after));
+    }
+    if is_assignment(after) {
+        return Some(EventKind::Used { mutable: true });
+    }
+    let whole_value = let_value.is_some_and(|value| value == mention.name);
+    let whole_argument = matches!(mention.context, Context::Call(_))
+        && (before.ends_with('(') || before.ends_with(','))
+        && (after.starts_with(')') || after.starts_with(','));
+    let iterated = before.ends_with(" in") && !after.starts_with('.');
+    if !(whole_value || whole_argument || iterated) {
+        return Some(EventKind::Used { mutable: false });
+    }
+    match &mention.context {
+        Context::Call(function) if function == "drop" && whole_argument => Some(EventKind::Dropped),
+        Context::Call(function) if whole_argument => Some(EventKind::Moved {
+            into: format!("{}()", function),
+        }),
+        _ if iterated => Some(EventKind::Moved {
+            into: String::from("the loop"),
+        }),
+        // The binding's own name is filled in by the caller.
+        _ => Some(EventKind::Moved {
+            into: String::new(),
+        }),
+    }
+}
+
+// `x.method(...)`, `x.field.method(...)` or `x.field`.
+fn method_access(after: &str) -> EventKind {
+    let mut rest = after;
+    let mut last = "";
+    while let Some(chain) = rest.strip_prefix('.') {
+        let end = chain
+            .find(|c: char| !is_ident_char(c))
+            .unwrap_or(chain.len());
+        last = &chain[..end];
+        rest = chain[end..].trim_start();
+    }
+    if rest.starts_with('(') {
+        if last.starts_with("into") {
+            return EventKind::Moved {
+                into: format!("{}()", last),
+            };
+        }
+        return EventKind::Used {
+            mutable: MUTATING_METHODS.contains(&last),
+        };
+    }
+    EventKind::Used {
+        mutable: is_assignment(rest),
+    }
+}
+
+fn is_assignment(after: &str) -> bool {
+    let after = after.trim_start();
+    let operator_end = after.find('=').unwrap_or(usize::MAX);
+    operator_end <= 2
+        && !after.starts_with("==")
+        && !after.starts_with("=>")
+        && after[..operator_end]
+            .chars()
+            .all(|c| "+-*/%|&^<>".contains(c))
+        && !after.starts_with("<=")
+        && !after.starts_with(">=")
+}
+
+// The names a `let` introduces, whether its type says it is a reference, and where its
+// value starts, if it has one. Only plain names and tuples of names are understood.
+struct Let<'c> {
+    names: Vec<&'c str>,
+    reference: bool,
+    value: Option<usize>,
+}
+
+fn let_binding(code: &str) -> Option<Let<'_>> {
+    let rest = code.trim_start().strip_prefix("let ")?.trim_start();
+    let (pattern, rest) = match rest.strip_prefix('(') {
+        Some(tuple) => tuple.split_once(')')?,
+        None => rest.split_at(
+            rest.find(|c: char| !is_ident_char(c) && c != ' ')
+                .unwrap_or(rest.len()),
+        ),
+    };
+    let mut names = Vec::new();
+    for name in pattern.split(',') {
+        let name = name.trim();
+        let name = name.strip_prefix("mut ").unwrap_or(name).trim();
+        if name.is_empty() || !name.chars().all(is_ident_char) {
+            return None;
+        }
+        if name != "_" {
+            names.push(name);
+        }
+    }
+    let rest = rest.trim_start();
+    if !(rest.starts_with('=') || rest.starts_with(':') || rest.starts_with(';')) {
+        return None;
+    }
+    let value = rest
+        .find('=')
+        .filter(|&at| !rest[at..].starts_with("=="))
+        .map(|at| code.len() - rest.len() + at + 1);
+    let reference = rest
+        .strip_prefix(':')
+        .is_some_and(|type_name| type_name.trim_start().starts_with('&'));
+    Some(Let {
+        names,
+        reference,
+        value,
+    })
+}
+
+struct Analysis {
+    bindings: Vec<Binding>,
+    events: Vec<Event>,
+    held: Vec<(usize, usize)>,
+    depth: i32,
+    // Bindings whose value is a block, created when the block closes: their names, whether
+    // they are references or said to be Copy, and the depth they are declared at.
+    pending: Vec<(Vec<String>, bool, bool, i32)>,
+}
+
+impl Analysis {
+    fn find(&self, name: &str, hypothetical: bool) -> Option<usize> {
+        (0..self.bindings.len()).rev().find(|&b| {
+            let binding = &self.bindings[b];
+            binding.name == name && (hypothetical || !binding.hypothetical)
+        })
+    }
+
+    fn push(&mut self, line: usize, binding: usize, kind: EventKind, hypothetical: bool) {
+        self.events.push(Event {
+            line,
+            binding,
+            kind,
+            hypothetical,
+        });
+    }
+
+    fn line(&mut self, number: usize, code: &str, said_copy: bool, hypothetical: bool) {
+        let binding = let_binding(code);
+        // Names in the pattern of a `let` are not uses, only the names in its value are.
+        let value_start = match &binding {
+            Some(binding) => binding.value.unwrap_or(code.len()),
+            None => 0,
+        };
+        // A value split over several lines is never just a name.
+        let value = binding
+            .as_ref()
+            .and_then(|binding| binding.value)
+            .filter(|_| code.trim_end().ends_with(';'))
+            .map(|start| code[start..].trim().trim_end_matches(';').trim());
+        let single = binding
+            .as_ref()
+            .and_then(|binding| match binding.names[..] {
+                [name] => Some(name),
+                _ => None,
+            });
+        let mut borrow = None;
+        let mut borrow_event = None;
+        let mut moved = Vec::new();
+        for mention in mentions(code) {
+            if mention.before.len() < value_start {
+                continue;
+            }
+            let Some(b) = self.find(mention.name, hypothetical) else {
+                continue;
+            };
+            let Some(mut kind) = classify(&mention, value) else {
+                continue;
+            };
+            if let EventKind::Moved { into } = &mut kind {
+                if into.is_empty() {
+                    *into = single.unwrap_or_default().to_string();
+                }
+            }
+            let binding = &self.bindings[b];
+            if let EventKind::Moved { .. } | EventKind::Dropped = kind {
+                if binding.reference {
+                    // References are Copy, and passing a &mut reborrows it.
+                    kind = EventKind::Used { mutable: false };
+                } else if binding.copy {
+                    kind = EventKind::Copied;
+                }
+            }
+            if let EventKind::Borrowed { mutable } = kind {
+                let holds = value.is_some_and(|value| value.starts_with('&')) && single.is_some();
+                if holds && borrow.is_none() {
+                    borrow = Some(Borrow { of: b, mutable });
+                    borrow_event = Some(self.events.len());
+                }
+            }
+            if !hypothetical {
+                if let EventKind::Moved { .. } | EventKind::Dropped = kind {
+                    moved.push(b);
+                }
+            }
+            self.push(number, b, kind, hypothetical);
+        }
+        // A value computed by a block, `let s = {`, exists once the block closes.
+        let block = binding
+            .as_ref()
+            .and_then(|binding| binding.value)
+            .is_some_and(|start| code[start..].trim() == "{");
+        match &binding {
+            Some(binding) if block && !hypothetical => {
+                let names = binding.names.iter().map(|name| name.to_string()).collect();
+                self.pending
+                    .push((names, binding.reference, said_copy, self.depth));
+            }
+            Some(binding) => {
+                // Literals are Copy: numbers, chars, bools and &'static str.
+                let copy = said_copy
+                    || value.is_some_and(|value| {
+                        value.starts_with(|c: char| c.is_ascii_digit() || c == '"' || c == '\'')
+                            || value == "true"
+                            || value == "false"
+                            || builds_copy_type(value)
+                    });
+                for name in &binding.names {
+                    let b =
+                        self.create(number, name, borrow, binding.reference, copy, hypothetical);
+                    if let Some(event) = borrow_event {
+                        self.held.push((event, b));
+                    }
+                }
+            }
+            None => {}
+        }
+        for b in moved {
+            self.bindings[b].moved.get_or_insert(number);
+        }
+        if hypothetical {
+            return;
+        }
+        for c in code.chars() {
+            match c {
+                '{' => self.depth += 1,
+                '}' => {
+                    self.depth -= 1;
+                    self.close_scope(number);
+                }
+                _ => {}
+            }
+        }
+        while let Some(at) = self.pending.iter().position(|p| p.3 == self.depth) {
+            let (names, reference, copy, _) = self.pending.remove(at);
+            for name in &names {
+                self.create(number, name, None, reference, copy, false);
+            }
+        }
+    }
+
+    fn create(
+        &mut self,
+        line: usize,
+        name: &str,
+        borrows: Option<Borrow>,
+        reference: bool,
+        copy: bool,
+        hypothetical: bool,
+    ) -> usize {
+        let b = self.bindings.len();
+        self.bindings.push(Binding {
+            name: name.to_string(),
+            line,
+            borrows,
+            reference: borrows.is_some() || reference,
+            hypothetical,
+            copy,
+            depth: self.depth,
+            moved: None,
+            out_of_scope: hypothetical.then_some(line),
+        });
+        self.push(line, b, EventKind::Created, hypothetical);
+        b
+    }
+
+    // Drops what the closing brace's scope declared, last declared first.
+    fn close_scope(&mut self, line: usize) {
+        for b in (0..self.bindings.len()).rev() {
+            let binding = &self.bindings[b];
+            if binding.depth <= self.depth || binding.out_of_scope.is_some() {
+                continue;
+            }
+            if binding.moved.is_none() && !binding.reference {
+                self.push(line, b, EventKind::Dropped, false);
+            }
+            self.bindings[b].out_of_scope = Some(line);
+        }
+    }
+}
+
+impl Timeline {
+    /// Reads the example's source and works out its borrows and conflicts.
+    pub fn of(example: &Example) -> Timeline {
+        let mut analysis = Analysis {
+            bindings: Vec::new(),
+            events: Vec::new(),
+            held: Vec::new(),
+            depth: 0,
+            pending: Vec::new(),
+        };
+        let mut rows = Vec::new();
+        let mut item_depth = 0;
+        let mut next_line_fails = false;
+        let mut last_indent = 0;
+        let indent = example
+            .lines
+            .iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(_, line)| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        for &(number, line) in &example.lines {
+            let (code, comment) = split_comment(line);
+            if item_depth > 0 || (item_depth == 0 && is_item(&code)) {
+                item_depth += brace_balance(&code);
+                continue;
+            }
+            if is_instrumentation(&code) {
+                continue;
+            }
+            let shown = line.get(indent..).unwrap_or(line.trim_start());
+            if !code.trim().is_empty() {
+                next_line_fails = false;
+                analysis.line(number, &code, comment.is_some_and(says_copy), false);
+                last_indent = shown.len() - shown.trim_start().len();
+                rows.push(Row {
+                    line: number,
+                    text: display_code(shown, comment).to_string(),
+                    hypothetical: false,
+                });
+                continue;
+            }
+            let Some(comment) = comment else {
+                continue;
+            };
+            match commented_code(comment) {
+                Some((inner, inner_comment)) => {
+                    if next_line_fails || inner_comment.is_some_and(claims_error) {
+                        let said_copy = inner_comment.is_some_and(says_copy);
+                        analysis.line(number, &inner, said_copy, true);
+                        let at = line.find("//").unwrap_or(0) + 2;
+                        let text = display_code(&line[at..], inner_comment).trim_start();
+                        // Drawn at the indentation of the code around it, not of the comment.
+                        let prefix = " ".repeat(last_indent);
+                        rows.push(Row {
+                            line: number,
+                            text: format!("{}{}", prefix, text),
+                            hypothetical: true,
+                        });
+                    }
+                    next_line_fails = false;
+                }
+                None => {
+                    next_line_fails = claims_error(comment) && comment.contains("next line ");
+                }
+            }
+        }
+        let mut timeline = Timeline {
+            bindings: analysis.bindings,
+            events: analysis.events,
+            conflicts: Vec::new(),
+            rows,
+            spans: Vec::new(),
+        };
+        timeline.spans = timeline.borrow_spans(&analysis.held);
+        timeline.conflicts = timeline.find_conflicts();
+        timeline
+    }
+
+    /// The last line a binding is really used on, not counting lines that would not compile.
+    pub fn last_use(&self, binding: usize) -> usize {
+        self.events
+            .iter()
+            .filter(|event| event.binding == binding && !event.hypothetical)
+            .filter(|event| event.kind != EventKind::Dropped)
+            .map(|event| event.line)
+            .max()
+            .unwrap_or(self.bindings[binding].line)
+    }
+
+    fn borrow_spans(&self, held: &[(usize, usize)]) -> Vec<Span> {
+        self.events
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| match event.kind {
+                EventKind::Borrowed { mutable } if !event.hypothetical => {
+                    let holder = held
+                        .iter()
+                        .find(|&&(borrow, _)| borrow == index)
+                        .map(|&(_, holder)| holder);
+                    Some(Span {
+                        of: event.binding,
+                        holder,
+                        mutable,
+                        from: event.line,
+                        to: holder.map_or(event.line, |holder| self.last_use(holder)),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The borrows of a binding still alive at a line, taken on an earlier line.
+    fn live_borrows(&self, binding: usize, line: usize) -> impl Iterator<Item = &Span> {
+        self.spans
+            .iter()
+            .filter(move |span| span.of == binding && span.from < line && line <= span.to)
+    }
+
+    fn find_conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for event in &self.events {
+            let name = &self.bindings[event.binding].name;
+            let exclusive = match event.kind {
+                EventKind::Created => continue,
+                EventKind::Borrowed { mutable } | EventKind::Used { mutable } => mutable,
+                EventKind::Moved { .. } | EventKind::Dropped => true,
+                EventKind::Copied => false,
+            };
+            let message = if let Some(moved) = self.bindings[event.binding]
+                .moved
+                .filter(|&moved| moved < event.line)
+            {
+                let dropped = self.events.iter().any(|earlier| {
+                    earlier.binding == event.binding
+                        && earlier.line == moved
+                        && earlier.kind == EventKind::Dropped
+                });
+                let how = if dropped { "dropped" } else { "moved" };
+                Some(format!("{} was {} on line {}", name, how, moved))
+            } else if self.bindings[event.binding]
+                .out_of_scope
+                .is_some_and(|end| end < event.line)
+            {
+                Some(format!("{} is out of scope", name))
+            } else {
+                let spans: Vec<&Span> = self
+                    .live_borrows(event.binding, event.line)
+                    .filter(|span| exclusive || span.mutable)
+                    .filter(|span| span.holder.is_some())
+                    .collect();
+                let holders: Vec<&str> = spans
+                    .iter()
+                    .filter_map(|span| span.holder)
+                    .map(|holder| self.bindings[holder].name.as_str())
+                    .collect();
+                (!holders.is_empty()).then(|| {
+                    let what = match event.kind {
+                        EventKind::Borrowed { mutable: true } => format!("borrow {} mutably", name),
+                        EventKind::Moved { .. } => format!("move {}", name),
+                        EventKind::Dropped => format!("drop {}", name),
+                        _ if exclusive => format!("change {}", name),
+                        _ => format!("use {}", name),
+                    };
+                    let how = if spans.iter().any(|span| span.mutable) {
+                        "borrowed mutably by"
+                    } else {
+                        "borrowed by"
+                    };
+                    format!("cannot {} while it is {} {}", what, how, holders.join(", "))
+                })
+            };
+            if let Some(message) = message {
+                conflicts.push(Conflict {
+                    line: event.line,
+                    binding: event.binding,
+                    message,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// What a binding is doing on a line. None before it exists and after it is gone.
+    pub fn state(&self, b: usize, line: usize) -> Option<State> {
+        let binding = &self.bindings[b];
+        let at = |wanted: fn(&EventKind) -> bool| {
+            self.events.iter().any(|event| {
+                event.binding == b
+                    && event.line == line
+                    && !event.hypothetical
+                    && wanted(&event.kind)
+            })
+        };
+        if line < binding.line || (binding.hypothetical && line != binding.line) {
+            return None;
+        }
+        if self
+            .conflicts
+            .iter()
+            .any(|c| c.binding == b && c.line == line)
+        {
+            return Some(State::Conflict);
+        }
+        if line == binding.line {
+            return Some(State::Created);
+        }
+        if at(|kind| matches!(kind, EventKind::Moved { .. })) {
+            return Some(State::Moved);
+        }
+        if at(|kind| *kind == EventKind::Dropped) {
+            return Some(State::Dropped);
+        }
+        let ended = [binding.moved, binding.out_of_scope]
+            .into_iter()
+            .flatten()
+            .any(|end| end < line);
+        if ended {
+            return None;
+        }
+        if binding.reference {
+            return Some(if line <= self.last_use(b) {
+                State::Owned
+            } else {
+                State::BorrowOver
+            });
+        }
+        let held = self
+            .spans
+            .iter()
+            .filter(|span| span.of == b && span.from <= line && line <= span.to);
+        Some(match held.map(|span| span.mutable).max() {
+            Some(true) => State::BorrowedMut,
+            Some(false) => State::Borrowed,
+            None => State::Owned,
+        })
+    }
+
+    fn cell(&self, b: usize, line: usize) -> char {
+        self.state(b, line).map_or(' ', State::symbol)
+    }
+
+    /// The lines drawn in the timeline: the example's code and the lines that would not compile.
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// What happens on a line, in words: borrows, moves, drops and conflicts.
+    pub fn notes(&self, line: usize) -> Vec<String> {
+        let mut notes = Vec::new();
+        for event in self.events.iter().filter(|event| event.line == line) {
+            let name = &self.bindings[event.binding].name;
+            let holder = self.bindings.iter().find(|b| {
+                b.line == line && b.borrows.is_some_and(|borrow| borrow.of == event.binding)
+            });
+            let note = match (&event.kind, holder) {
+                (EventKind::Borrowed { mutable }, Some(holder)) => {
+                    let how = if *mutable { " mutably" } else { "" };
+                    format!("{} borrows {}{}", holder.name, name, how)
+                }
+                (EventKind::Moved { into }, _) if !into.is_empty() => {
+                    format!("{} moved into {}", name, into)
+                }
+                (EventKind::Moved { .. }, _) => format!("{} moved", name),
+                (EventKind::Copied, _) => format!("{} copied", name),
+                (EventKind::Dropped, _) => format!("{} dropped", name),
+                _ => continue,
+            };
+            notes.push(note);
+        }
+        for conflict in self.conflicts.iter().filter(|c| c.line == line) {
+            notes.push(format!("error: {}", conflict.message));
+        }
+        notes
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self
+            .bindings
+            .iter()
+            .enumerate()
+            .map(|(b, binding)| {
+                let shadows = self.bindings[..b]
+                    .iter()
+                    .filter(|other| other.name == binding.name);
+                format!("{}{}", binding.name, "'".repeat(shadows.count()))
+            })
+            .collect();
+        write!(
+            f,
+            "{:>5}   {:<width$}",
+            "line",
+            "source",
+            width = SOURCE_WIDTH
+        )?;
+        for name in &names {
+            write!(f, " {}", name)?;
+        }
+        writeln!(f)?;
+        for row in &self.rows {
+            let mut text: String = row.text.chars().take(SOURCE_WIDTH).collect();
+            if row.text.chars().count() > SOURCE_WIDTH {
+                text.truncate(text.len() - text.chars().last().map_or(0, char::len_utf8));
+                text.push('~');
+            }
+            let marker = if row.hypothetical { '?' } else { ' ' };
+            let mut out = format!(
+                "{:>5} {} {:<width$}",
+                row.line,
+                marker,
+                text,
+                width = SOURCE_WIDTH
+            );
+            for (b, name) in names.iter().enumerate() {
+                let cell = self.cell(b, row.line);
+                out.push_str(&format!(" {:<width$}", cell, width = name.len()));
+            }
+            let notes = self.notes(row.line);
+            if !notes.is_empty() {
+                out.push_str("  ");
+                out.push_str(&notes.join("; "));
+            }
+            writeln!(f, "{}", out.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(source: &str, title: &str) -> Timeline {
+        let examples = examples(source);
+        let example = examples
+            .iter()
+            .find(|example| example.title == title)
+            .expect("No such example");
+        Timeline::of(example)
+    }
+
+    fn binding(timeline: &Timeline, name: &str) -> usize {
+        timeline
+            .bindings
+            .iter()
+            .position(|binding| binding.name == name)
+            .expect("No such binding")
+    }
+
+    fn conflict_lines(timeline: &Timeline) -> Vec<usize> {
+        timeline.conflicts.iter().map(|c| c.line).collect()
+    }
+
+    const LESSON_3: &str = include_str!("lesson_3_borrow.rs");
+
+    #[test]
+    fn test_split_comment_empties_literals() {
+        let (code, comment) = split_comment(r#"let s = "{ // }"; let c = '}'; // note"#);
+        assert_eq!(r#"let s = ""; let c = ' '; "#, code);
+        assert_eq!(Some(" note"), comment);
+        let (code, comment) = split_comment("fn f<'a>(x: &'a str) {}");
+        assert_eq!("fn f<'a>(x: &'a str) {}", code);
+        assert_eq!(None, comment);
+    }
+
+    #[test]
+    fn test_examples_are_split_at_banners() {
+        let titles: Vec<String> = examples(LESSON_3).into_iter().map(|e| e.title).collect();
+        assert_eq!(11, titles.len());
+        assert_eq!("lesson 3 example 1", titles[0]);
+        assert_eq!("lesson 3 example 11", titles[10]);
+    }
+
+    #[test]
+    fn test_mutable_borrow_lasts_until_last_use() {
+        let timeline = timeline(LESSON_3, "lesson 3 example 2");
+        let data = binding(&timeline, "data");
+        let reference = binding(&timeline, "reference");
+        assert_eq!(
+            Some(Borrow {
+                of: data,
+                mutable: true
+            }),
+            timeline.bindings[reference].borrows
+        );
+        let created = timeline.bindings[reference].line;
+        let last_use = timeline.last_use(reference);
+        assert!(last_use > created);
+        assert_eq!('M', timeline.cell(data, last_use));
+        assert_eq!('|', timeline.cell(data, last_use + 2));
+        assert_eq!(':', timeline.cell(reference, last_use + 2));
+        // Only the commented-out read of data conflicts with the borrow.
+        let [conflict] = &timeline.conflicts[..] else {
+            panic!("expected one conflict, got {:?}", timeline.conflicts);
+        };
+        assert_eq!(data, conflict.binding);
+        assert!(conflict.line > created && conflict.line < last_use);
+        assert_eq!(
+            "cannot use data while it is borrowed mutably by reference",
+            conflict.message
+        );
+    }
+
+    #[test]
+    fn test_overlapping_borrows_conflict() {
+        let timeline = timeline(LESSON_3, "lesson 3 example 3");
+        let messages: Vec<&str> = timeline
+            .conflicts
+            .iter()
+            .map(|c| c.message.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "cannot change data while it is borrowed by reference1, reference2",
+                "cannot borrow data mutably while it is borrowed by reference1, reference2",
+            ],
+            messages
+        );
+        let reference4 = binding(&timeline, "reference4");
+        assert!(!timeline.bindings[reference4].hypothetical);
+        assert!(timeline.bindings[binding(&timeline, "reference3")].hypothetical);
+    }
+
+    #[test]
+    fn test_scopes_end_borrows_and_drop() {
+        let timeline = timeline(LESSON_3, "lesson 3 example 4");
+        let data = binding(&timeline, "data");
+        let reference1 = binding(&timeline, "reference1");
+        let dropped: Vec<usize> = timeline
+            .events
+            .iter()
+            .filter(|event| event.kind == EventKind::Dropped)
+            .map(|event| event.binding)
+            .collect();
+        // A reference going out of scope drops nothing, the String does.
+        assert_eq!(vec![data], dropped);
+        assert!(timeline.bindings[reference1].out_of_scope.is_some());
+        assert!(timeline.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_move_into_function_then_use() {
+        let timeline = timeline(include_str!("lesson_1_scope.rs"), "lesson 1 example 2");
+        let my_data2 = binding(&timeline, "my_data2");
+        let moved = timeline
+            .events
+            .iter()
+            .find(|event| {
+                event.binding == my_data2 && matches!(event.kind, EventKind::Moved { .. })
+            })
+            .expect("my_data2 is never moved");
+        assert_eq!(
+            EventKind::Moved {
+                into: String::from("consume()")
+            },
+            moved.kind
+        );
+        assert_eq!('>', timeline.cell(my_data2, moved.line));
+        let [conflict] = &timeline.conflicts[..] else {
+            panic!("expected one conflict, got {:?}", timeline.conflicts);
+        };
+        assert_eq!(
+            format!("my_data2 was moved on line {}", moved.line),
+            conflict.message
+        );
+    }
+
+    #[test]
+    fn test_copy_types() {
+        assert_eq!(
+            vec!["MyCopyableStruct", "Point"],
+            copy_types().collect::<Vec<_>>()
+        );
+        assert!(builds_copy_type("Point { x: 1, y: 2 }"));
+        assert!(!builds_copy_type("PointCloud { x: 1 }"));
+        assert!(!builds_copy_type("MyCloneableStruct { data }"));
+    }
+
+    #[test]
+    fn test_copies() {
+        let copied = |timeline: &Timeline| {
+            timeline
+                .events
+                .iter()
+                .filter(|event| event.kind == EventKind::Copied)
+                .map(|event| timeline.bindings[event.binding].name.clone())
+                .collect::<Vec<_>>()
+        };
+        // A teaching type that derives Copy.
+        let timeline = timeline(LESSON_3, "lesson 3 example 6");
+        assert!(timeline.conflicts.is_empty(), "{:?}", timeline.conflicts);
+        assert_eq!(vec!["original"], copied(&timeline));
+        // A closure whose `let` line says it is Copy.
+        let source = "\
+    println!(\" --------------- lesson 10 example 1 ---------------\");
+    let greeting = String::from(\"Hello\");
+    let greet = || format!(\"{}!\", greeting); // Only holds a &, so it is Copy
+    call_fn(greet);
+    call_fn_once(greet);
+";
+        let timeline = self::timeline(source, "lesson 10 example 1");
+        assert!(timeline.conflicts.is_empty(), "{:?}", timeline.conflicts);
+        assert_eq!(vec!["greet", "greet"], copied(&timeline));
+    }
+
+    #[test]
+    fn test_block_values_exist_once_the_block_closes() {
+        let timeline = timeline(LESSON_3, "lesson 3 example 8");
+        let s = timeline
+            .bindings
+            .iter()
+            .position(|binding| binding.name == "s")
+            .expect("No s");
+        let closes = LESSON_3
+            .lines()
+            .position(|line| line.trim() == "static_ref")
+            .expect("No block tail")
+            + 2;
+        assert_eq!(closes, timeline.bindings[s].line);
+        assert!(timeline.bindings[s].reference);
+    }
+
+    #[test]
+    fn test_lessons_only_conflict_on_lines_that_would_not_compile() {
+        for lesson in crate::LESSONS {
+            for example in examples(lesson.source) {
+                let timeline = Timeline::of(&example);
+                let real: Vec<usize> = conflict_lines(&timeline)
+                    .into_iter()
+                    .filter(|&line| {
+                        timeline
+                            .rows
+                            .iter()
+                            .any(|row| row.line == line && !row.hypothetical)
+                    })
+                    .collect();
+                assert!(
+                    real.is_empty(),
+                    "{} conflicts on lines {:?}",
+                    example.title,
+                    real
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,522 @@
+/////////////////////////////////////////////////////////
+// Trace
+/////////////////////////////////////////////////////////
+
+// The timelines and the stepper read an example's source and work out what should happen.
+// A trace is what did happen: the examples record the points that matter, when a value is
+// created, moved, borrowed or dropped, with a `trace!` line beside the code it describes.
+// The lesson's own code is left as it is, `consume(my_data2);` stays a plain call, and the
+// timelines skip the `trace!` lines.
+//
+// A `trace!` line records where it is, and the event is moved onto the code it describes
+// when the trace is cut out of the lesson's events: the nearest line that names the value,
+// above for creations and borrows and below for moves, so blank lines, comments and wrapped
+// statements in between do not matter. A drop is observed rather than announced, the value
+// is handed to a guard that records the event when it really goes, at the brace that ends
+// its block.
+//
+// Traces are saved as plain text, one event per line, so they can be written to a file
+// with `trace <lesson> <example> --out <file>` and replayed later with `step --trace <file>`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::timeline::examples;
+
+thread_local! {
+    // Events are recorded on the thread that makes them, like the drops in drop_tracer.
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Created,
+    Moved {
+        into: String,
+    },
+    /// Borrowed by the reference named `by`.
+    Borrowed {
+        by: String,
+        mutable: bool,
+    },
+    Dropped,
+}
+
+/// Something that happened to a value, on a line of its lesson file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The line of the code the event happened on. Until the event is placed by
+    /// Trace::of_example, the line of the trace! that recorded it.
+    pub line: usize,
+    pub name: String,
+    pub action: Action,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.action {
+            Action::Created => write!(f, "{} created", self.name),
+            Action::Moved { into } => write!(f, "{} moved into {}", self.name, into),
+            Action::Borrowed { by, mutable: false } => {
+                write!(f, "{} borrowed & by {}", self.name, by)
+            }
+            Action::Borrowed { by, mutable: true } => {
+                write!(f, "{} borrowed &mut by {}", self.name, by)
+            }
+            Action::Dropped => write!(f, "{} dropped", self.name),
+        }
+    }
+}
+
+/// Records an event on a line of the lesson, for the trace! macro.
+pub fn record(line: u32, name: &str, action: Action) {
+    let event = Event {
+        line: line as usize,
+        name: name.to_string(),
+        action,
+    };
+    EVENTS.with(|events| events.borrow_mut().push(event));
+}
+
+/// Records what happens to a value on the line beside it. Creations and borrows are written
+/// after the code, once the value or reference exists, and moves before it, while the value
+/// is still there to be moved: `trace!(data created)`, `trace!(data borrowed by reference1)`,
+/// `trace!(data borrowed mut by reference2)` and `trace!(my_data2 moved into consume())`.
+/// `trace!(data dropped)` hands data to a guard, so the drop is recorded when it happens.
+/// The names must be in scope, so a trace cannot name a value the code around it does not
+/// have.
+macro_rules! trace {
+    ($name:ident created) => {{
+        let _ = &$name;
+        $crate::trace::record(line!(), stringify!($name), $crate::trace::Action::Created)
+    }};
+    ($name:ident borrowed by $by:ident) => {{
+        let _ = &$by;
+        $crate::trace::record(
+            line!(),
+            stringify!($name),
+            $crate::trace::Action::Borrowed {
+                by: String::from(stringify!($by)),
+                mutable: false,
+            },
+        )
+    }};
+    ($name:ident borrowed mut by $by:ident) => {{
+        let _ = &$by;
+        $crate::trace::record(
+            line!(),
+            stringify!($name),
+            $crate::trace::Action::Borrowed {
+                by: String::from(stringify!($by)),
+                mutable: true,
+            },
+        )
+    }};
+    ($name:ident moved into $($into:tt)+) => {{
+        let _ = &$name;
+        $crate::trace::record(
+            line!(),
+            stringify!($name),
+            $crate::trace::Action::Moved {
+                into: String::from(stringify!($($into)+)),
+            },
+        )
+    }};
+    ($name:ident dropped) => {
+        let $name = $crate::trace::Dropping::new($name, stringify!($name), line!());
+        let _ = &$name;
+    };
+}
+pub(crate) use trace;
+
+/// A value handed over by `trace!(name dropped)`, used through it as before. Dropping it
+/// records the event, then drops the value.
+pub struct Dropping<T> {
+    value: T,
+    name: &'static str,
+    line: u32,
+}
+
+impl<T> Dropping<T> {
+    pub fn new(value: T, name: &'static str, line: u32) -> Dropping<T> {
+        Dropping { value, name, line }
+    }
+}
+
+impl<T> Deref for Dropping<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Dropping<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Dropping<T> {
+    fn drop(&mut self) {
+        record(self.line, self.name, Action::Dropped);
+    }
+}
+
+/// Returns the events recorded on the current thread so far, in order, and clears the record.
+pub fn take_events() -> Vec<Event> {
+    EVENTS.with(|events| events.borrow_mut().drain(..).collect())
+}
+
+/// The events of one example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub lesson: String,
+    /// The example's title, such as "lesson 1 example 2".
+    pub example: String,
+    pub events: Vec<Event>,
+}
+
+impl Trace {
+    /// Picks the events that belong to an example out of everything its lesson recorded,
+    /// after placing each on the code it describes. An event on a line of the example belongs
+    /// to it, and so does an event made from outside any example, such as in a function the
+    /// example called, when the event before it did.
+    ///
+    /// Panics when a trace! has no code naming its value beside it, as that is a mistake in
+    /// the lesson.
+    pub fn of_example(lesson: &str, source: &str, example: &str, events: Vec<Event>) -> Trace {
+        let lines: Vec<&str> = source.lines().collect();
+        let events = events.into_iter().map(|mut event| {
+            event.line = place(&lines, &event).unwrap_or_else(|| {
+                panic!(
+                    "The trace! on line {} of {} is not beside code naming its value",
+                    event.line, lesson
+                )
+            });
+            event
+        });
+        let ranges: Vec<(String, usize, usize)> = examples(source)
+            .into_iter()
+            .map(|example| {
+                let first = example.lines.first().map_or(0, |(number, _)| *number);
+                let last = example.lines.last().map_or(0, |(number, _)| *number);
+                (example.title, first, last)
+            })
+            .collect();
+        let mut current: Option<&str> = None;
+        let events = events
+            .filter(|event| {
+                if let Some((title, _, _)) = ranges
+                    .iter()
+                    .find(|(_, first, last)| (*first..=*last).contains(&event.line))
+                {
+                    current = Some(title);
+                }
+                current == Some(example)
+            })
+            .collect();
+        Trace {
+            lesson: lesson.to_string(),
+            example: example.to_string(),
+            events,
+        }
+    }
+
+    /// Reads a trace back from the text it was saved as.
+    pub fn parse(text: &str) -> Result<Trace, ParseError> {
+        let mut lesson = None;
+        let mut example = None;
+        let mut events = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: &str| ParseError {
+                line: index + 1,
+                message: message.to_string(),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["lesson", id] => lesson = Some(id.to_string()),
+                ["example", title] => example = Some(title.to_string()),
+                [number, name, ref action @ ..] => {
+                    let line = number
+                        .parse()
+                        .map_err(|_| error("expected a line number"))?;
+                    let action = match *action {
+                        ["created"] => Action::Created,
+                        ["moved", into] => Action::Moved {
+                            into: into.to_string(),
+                        },
+                        ["borrowed", by] => Action::Borrowed {
+                            by: by.to_string(),
+                            mutable: false,
+                        },
+                        ["borrowed mut", by] => Action::Borrowed {
+                            by: by.to_string(),
+                            mutable: true,
+                        },
+                        ["dropped"] => Action::Dropped,
+                        _ => return Err(error("unknown event")),
+                    };
+                    events.push(Event {
+                        line,
+                        name: name.to_string(),
+                        action,
+                    });
+                }
+                _ => return Err(error("expected an event")),
+            }
+        }
+        Ok(Trace {
+            lesson: lesson.ok_or(ParseError {
+                line: 0,
+                message: String::from("no lesson given"),
+            })?,
+            example: example.ok_or(ParseError {
+                line: 0,
+                message: String::from("no example given"),
+            })?,
+            events,
+        })
+    }
+}
+
+// The line of the code a trace! describes, from the line of the trace! itself.
+fn place(lines: &[&str], event: &Event) -> Option<usize> {
+    let traced = event.line.checked_sub(1)?;
+    let code = |index: &usize| {
+        let line = lines[*index].trim();
+        !line.starts_with("//") && !line.starts_with("trace!(")
+    };
+    // A borrow is made where the reference is.
+    let name = match &event.action {
+        Action::Borrowed { by, .. } => by,
+        _ => &event.name,
+    };
+    let naming = |index: &usize| code(index) && names(lines[*index], name);
+    let index = match &event.action {
+        Action::Created | Action::Borrowed { .. } => (0..traced).rev().find(naming),
+        Action::Moved { .. } => (traced + 1..lines.len()).find(naming),
+        // The brace that closes the block the guard was made in.
+        Action::Dropped => {
+            let mut depth = 0;
+            (traced + 1..lines.len()).find(|index| {
+                let line = lines[*index];
+                let line = line.split_once("//").map_or(line, |(code, _)| code);
+                line.chars().any(|c| match c {
+                    '{' => {
+                        depth += 1;
+                        false
+                    }
+                    '}' if depth == 0 => true,
+                    '}' => {
+                        depth -= 1;
+                        false
+                    }
+                    _ => false,
+                })
+            })
+        }
+    };
+    index.map(|index| index + 1)
+}
+
+// Whether the line uses the name as a whole word.
+fn names(line: &str, name: &str) -> bool {
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(name).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + name.len()..].chars().next();
+        !before.is_some_and(word) && !after.is_some_and(word)
+    })
+}
+
+/// Saved as tab separated text: the lesson and example, then one event per line.
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lesson\t{}", self.lesson)?;
+        writeln!(f, "example\t{}", self.example)?;
+        for event in &self.events {
+            write!(f, "{}\t{}\t", event.line, event.name)?;
+            match &event.action {
+                Action::Created => writeln!(f, "created")?,
+                Action::Moved { into } => writeln!(f, "moved\t{}", into)?,
+                Action::Borrowed { by, mutable: false } => writeln!(f, "borrowed\t{}", by)?,
+                Action::Borrowed { by, mutable: true } => writeln!(f, "borrowed mut\t{}", by)?,
+                Action::Dropped => writeln!(f, "dropped")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the trace file, or 0 when something is missing from it.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_placed_on_their_code() {
+        take_events();
+        let line = line!() as usize + 1;
+        let mut s = String::from(
+            "long enough for rustfmt to keep this call on more than the one line it starts on",
+        );
+        // Comments, blank lines and the rest of a wrapped statement are stepped over
+
+        trace!(s created);
+        let r = &mut s;
+        // Like this one
+        trace!(s borrowed mut by r);
+        r.push('!');
+        trace!(s moved into drop());
+
+        drop(s);
+        let lines: Vec<&str> = include_str!("trace.rs").lines().collect();
+        let placed: Vec<(usize, String)> = take_events()
+            .iter()
+            .map(|event| (place(&lines, event).expect("Not placed"), event.to_string()))
+            .collect();
+        assert_eq!(
+            vec![
+                (line, String::from("s created")),
+                (line + 6, String::from("s borrowed &mut by r")),
+                (line + 12, String::from("s moved into drop()")),
+            ],
+            placed
+        );
+    }
+
+    #[test]
+    fn test_drops_are_recorded_when_they_happen() {
+        take_events();
+        let data = vec![1, 2, 3];
+        let line = line!() as usize + 2;
+        {
+            trace!(data dropped);
+            assert_eq!(3, data.len());
+            assert!(take_events().is_empty());
+        }
+        let lines: Vec<&str> = include_str!("trace.rs").lines().collect();
+        let events = take_events();
+        assert_eq!(1, events.len());
+        assert_eq!(Action::Dropped, events[0].action);
+        assert_eq!(Some(line + 3), place(&lines, &events[0]));
+    }
+
+    #[test]
+    fn test_lesson_traces_are_placed() {
+        for lesson in crate::LESSONS {
+            take_events();
+            (lesson.examples)();
+            let events = take_events();
+            for example in examples(lesson.source) {
+                Trace::of_example(lesson.id, lesson.source, &example.title, events.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_consume_moves_then_drops_inside_consume() {
+        take_events();
+        crate::lesson_1_scope::examples();
+        let source = include_str!("lesson_1_scope.rs");
+        let trace = Trace::of_example(
+            "lesson_1_scope",
+            source,
+            "lesson 1 example 2",
+            take_events(),
+        );
+        let line_of = |text: &str| {
+            source
+                .lines()
+                .position(|line| line.trim().starts_with(text))
+                .expect("No such line")
+                + 1
+        };
+        let expected = vec![
+            Event {
+                line: line_of("let my_data2 = vec![1, 2, 3, 4, 5];"),
+                name: String::from("my_data2"),
+                action: Action::Created,
+            },
+            Event {
+                line: line_of("consume(my_data2);"),
+                name: String::from("my_data2"),
+                action: Action::Moved {
+                    into: String::from("consume()"),
+                },
+            },
+            Event {
+                line: line_of("trace!(data dropped);") + 1,
+                name: String::from("data"),
+                action: Action::Dropped,
+            },
+        ];
+        assert_eq!(expected, trace.events[..3]);
+    }
+
+    #[test]
+    fn test_trace_survives_being_saved() {
+        let trace = Trace {
+            lesson: String::from("lesson_3_borrow"),
+            example: String::from("lesson 3 example 2"),
+            events: vec![
+                Event {
+                    line: 40,
+                    name: String::from("s"),
+                    action: Action::Created,
+                },
+                Event {
+                    line: 41,
+                    name: String::from("s"),
+                    action: Action::Borrowed {
+                        by: String::from("r"),
+                        mutable: true,
+                    },
+                },
+                Event {
+                    line: 42,
+                    name: String::from("s"),
+                    action: Action::Moved {
+                        into: String::from("take()"),
+                    },
+                },
+                Event {
+                    line: 50,
+                    name: String::from("s"),
+                    action: Action::Dropped,
+                },
+            ],
+        };
+        assert_eq!(Ok(trace.clone()), Trace::parse(&trace.to_string()));
+    }
+
+    #[test]
+    fn test_parse_reports_bad_lines() {
+        let error = Trace::parse("lesson\tlesson_1_scope\nexample\tx\n12\ts\tvanished\n");
+        assert_eq!(
+            Err(ParseError {
+                line: 3,
+                message: String::from("unknown event"),
+            }),
+            error
+        );
+        assert_eq!(0, Trace::parse("12\ts\tcreated\n").unwrap_err().line);
+    }
+}
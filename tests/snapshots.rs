@@ -0,0 +1,131 @@
+// Runs lessons through the binary and compares their output with the files in
+// tests/snapshots. Run with UPDATE_SNAPSHOTS=1 to rewrite the files after a lesson changes.
+// Only lessons with deterministic output belong here; lesson 2 prints clone timings.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn assert_snapshot(lesson: &str) {
+    assert_snapshot_of(lesson, &["run", lesson]);
+}
+
+fn assert_snapshot_of(name: &str, args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_learn-borrow-checker"))
+        .args(args)
+        .output()
+        .expect("Unable to run the lessons binary");
+    assert!(output.status.success(), "{} exited with an error", name);
+    let actual = String::from_utf8(output.stdout).expect("Lesson output is not UTF-8");
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).expect("Unable to write snapshot");
+        return;
+    }
+    let expected =
+        fs::read_to_string(&path).expect("Missing snapshot, run with UPDATE_SNAPSHOTS=1");
+    assert_eq!(expected, actual, "{} output changed", name);
+}
+
+#[test]
+fn test_lesson_5_lifetimes() {
+    assert_snapshot("lesson_5_lifetimes");
+}
+
+#[test]
+fn test_lesson_6_shared_ownership() {
+    assert_snapshot("lesson_6_shared_ownership");
+}
+
+#[test]
+fn test_lesson_7_threads() {
+    assert_snapshot("lesson_7_threads");
+}
+
+#[test]
+fn test_lesson_8_interior_mutability() {
+    assert_snapshot("lesson_8_interior_mutability");
+}
+
+#[test]
+fn test_lesson_9_cow() {
+    assert_snapshot("lesson_9_cow");
+}
+
+#[test]
+fn test_lesson_10_closures() {
+    assert_snapshot("lesson_10_closures");
+}
+
+#[test]
+fn test_lesson_11_iterators() {
+    assert_snapshot("lesson_11_iterators");
+}
+
+#[test]
+fn test_lesson_12_slices() {
+    assert_snapshot("lesson_12_slices");
+}
+
+#[test]
+fn test_lesson_13_pin() {
+    assert_snapshot("lesson_13_pin");
+}
+
+#[test]
+fn test_lesson_14_async() {
+    assert_snapshot("lesson_14_async");
+}
+
+#[test]
+fn test_lesson_15_unsafe() {
+    assert_snapshot("lesson_15_unsafe");
+}
+
+#[test]
+fn test_lesson_16_nll() {
+    assert_snapshot("lesson_16_nll");
+}
+
+#[test]
+fn test_lesson_17_trait_objects() {
+    assert_snapshot("lesson_17_trait_objects");
+}
+
+#[test]
+fn test_lesson_3_borrow_visualize() {
+    assert_snapshot_of(
+        "lesson_3_borrow_visualize",
+        &["run", "lesson_3_borrow", "--visualize"],
+    );
+}
+
+#[test]
+fn test_lesson_6_shared_ownership_graph() {
+    assert_snapshot_of(
+        "lesson_6_shared_ownership_graph",
+        &["graph", "lesson_6_shared_ownership"],
+    );
+}
+
+#[test]
+fn test_lesson_3_borrow_step() {
+    assert_snapshot_of("lesson_3_borrow_step_2", &["step", "lesson_3_borrow", "2"]);
+}
+
+#[test]
+fn test_lesson_3_borrow_step_traced() {
+    assert_snapshot_of("lesson_3_borrow_step_4", &["step", "lesson_3_borrow", "4"]);
+}
+
+#[test]
+fn test_lesson_3_borrow_trace() {
+    assert_snapshot_of(
+        "lesson_3_borrow_trace_4",
+        &["trace", "lesson_3_borrow", "4"],
+    );
+}